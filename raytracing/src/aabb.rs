@@ -0,0 +1,70 @@
+use crate::primitives::Ray;
+use crate::vec::{Float, Vec3};
+
+/// Axis-aligned bounding box.
+#[derive(Copy, Clone, Debug)]
+pub struct Aabb<T = f32> {
+    pub min: Vec3<T>,
+    pub max: Vec3<T>,
+}
+
+impl<T: Float> Aabb<T> {
+    pub fn new(min: Vec3<T>, max: Vec3<T>) -> Self {
+        Self { min, max }
+    }
+
+    pub fn union(&self, other: &Aabb<T>) -> Aabb<T> {
+        Aabb {
+            min: Vec3::new(
+                self.min[0].min(other.min[0]),
+                self.min[1].min(other.min[1]),
+                self.min[2].min(other.min[2]),
+            ),
+            max: Vec3::new(
+                self.max[0].max(other.max[0]),
+                self.max[1].max(other.max[1]),
+                self.max[2].max(other.max[2]),
+            ),
+        }
+    }
+
+    pub fn centroid(&self) -> Vec3<T> {
+        (self.min + self.max) * 0.5f32.cast::<T>()
+    }
+
+    pub fn largest_axis(&self) -> usize {
+        let extent = self.max - self.min;
+        if extent[0] >= extent[1] && extent[0] >= extent[2] {
+            0
+        } else if extent[1] >= extent[2] {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Slab test: whether the ray passes through the box within `[t_min, t_max]`.
+    pub fn hit(&self, ray: Ray<T>, t_min: T, t_max: T) -> bool {
+        self.ray_range(ray, t_min, t_max).is_some()
+    }
+
+    /// Part of `[t_min, t_max]` where the ray is inside the box.
+    pub fn ray_range(&self, ray: Ray<T>, t_min: T, t_max: T) -> Option<(T, T)> {
+        let mut t_min = t_min;
+        let mut t_max = t_max;
+        for axis in 0..3 {
+            let inv_direction = T::ONE / ray.direction[axis];
+            let mut t0 = (self.min[axis] - ray.origin[axis]) * inv_direction;
+            let mut t1 = (self.max[axis] - ray.origin[axis]) * inv_direction;
+            if inv_direction < T::ZERO {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = t0.max(t_min);
+            t_max = t1.min(t_max);
+            if t_max < t_min {
+                return None;
+            }
+        }
+        Some((t_min, t_max))
+    }
+}
@@ -0,0 +1,198 @@
+//! Keyframe animation of the camera, objects, lights and materials.
+
+use std::ops::{Add, Mul, Sub};
+
+use crate::error::RenderError;
+use crate::primitives::MaterialId;
+use crate::render::RenderParams;
+use crate::scene::{Renderable, Scene};
+use crate::translated::Translated;
+use crate::vec::Vec3f;
+
+/// Value that can be interpolated between keyframes.
+pub trait Keyframed:
+    Copy + Add<Output = Self> + Sub<Output = Self> + Mul<f32, Output = Self>
+{
+}
+
+impl<T> Keyframed for T where T: Copy + Add<Output = T> + Sub<Output = T> + Mul<f32, Output = T> {}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Straight lines between keys, changing speed abruptly at each key.
+    Linear,
+    /// Cubic spline through the keys with Catmull–Rom tangents, starting and stopping
+    /// with zero velocity at the first and last keys.
+    Smooth,
+}
+
+/// Value changing over time, given at keyframes.
+#[derive(Debug, Clone)]
+pub struct Track<T> {
+    keys: Vec<(f32, T)>,
+    interpolation: Interpolation,
+}
+
+impl<T: Keyframed> Track<T> {
+    /// Track through `keys`, pairs of a time in seconds and the value at that time.
+    ///
+    /// Panics if `keys` is empty.
+    pub fn new(interpolation: Interpolation, mut keys: Vec<(f32, T)>) -> Self {
+        assert!(!keys.is_empty(), "a track needs at least one key");
+        keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self {
+            keys,
+            interpolation,
+        }
+    }
+
+    /// Value at `time`, held constant before the first and after the last key.
+    pub fn sample(&self, time: f32) -> T {
+        let keys = &self.keys;
+        let next = keys.partition_point(|&(key_time, _)| key_time <= time);
+        if next == 0 {
+            return keys[0].1;
+        }
+        if next == keys.len() {
+            return keys[keys.len() - 1].1;
+        }
+        let (t0, p0) = keys[next - 1];
+        let (t1, p1) = keys[next];
+        let span = t1 - t0;
+        let s = (time - t0) / span;
+        match self.interpolation {
+            Interpolation::Linear => p0 + (p1 - p0) * s,
+            Interpolation::Smooth => {
+                // Cubic Hermite basis.
+                let s2 = s * s;
+                let s3 = s2 * s;
+                let h00 = 2.0 * s3 - 3.0 * s2 + 1.0;
+                let h10 = s3 - 2.0 * s2 + s;
+                let h01 = -2.0 * s3 + 3.0 * s2;
+                let h11 = s3 - s2;
+                p0 * h00
+                    + self.tangent(next - 1) * (h10 * span)
+                    + p1 * h01
+                    + self.tangent(next) * (h11 * span)
+            }
+        }
+    }
+
+    /// Rate of change at key `index`, zero at both ends of the track.
+    fn tangent(&self, index: usize) -> T {
+        let keys = &self.keys;
+        let value = keys[index].1;
+        if index == 0 || index + 1 == keys.len() {
+            return value * 0.0;
+        }
+        let (before, previous) = keys[index - 1];
+        let (after, next) = keys[index + 1];
+        (next - previous) * (1.0 / (after - before))
+    }
+}
+
+/// Property of the scene or camera driven by a track.
+#[derive(Debug, Clone)]
+pub enum Animated {
+    CameraPosition(Track<Vec3f>),
+    CameraTarget(Track<Vec3f>),
+    FieldOfView(Track<f32>),
+    /// Displacement of the object at the given index of [`Scene::objects`] from where the
+    /// scene puts it, see [`Translated`].
+    ObjectOffset(usize, Track<Vec3f>),
+    LightPosition(usize, Track<Vec3f>),
+    LightIntensity(usize, Track<f32>),
+    DiffuseColor(MaterialId, Track<Vec3f>),
+    Emission(MaterialId, Track<Vec3f>),
+    SpecularExponent(MaterialId, Track<f32>),
+    RefractiveIndex(MaterialId, Track<f32>),
+}
+
+/// Set of tracks animating a scene, sampled at a fixed frame rate.
+#[derive(Debug, Clone)]
+pub struct Animation {
+    pub frame_rate: f32,
+    pub tracks: Vec<Animated>,
+}
+
+impl Animation {
+    /// Time of `frame` in seconds, frame 0 being at time 0.
+    pub fn frame_time(&self, frame: usize) -> f32 {
+        frame as f32 / self.frame_rate
+    }
+
+    /// Checks that the frame rate is positive and that every track animates an object,
+    /// light or material `scene` has.
+    pub fn validate(&self, scene: &Scene) -> Result<(), RenderError> {
+        if !(self.frame_rate > 0.0 && self.frame_rate.is_finite()) {
+            return Err(RenderError::FrameRate(self.frame_rate));
+        }
+        for (index, track) in self.tracks.iter().enumerate() {
+            let exists = match track {
+                Animated::CameraPosition(_)
+                | Animated::CameraTarget(_)
+                | Animated::FieldOfView(_) => true,
+                Animated::ObjectOffset(object, _) => *object < scene.objects.len(),
+                Animated::LightPosition(light, _) | Animated::LightIntensity(light, _) => {
+                    *light < scene.lights.len()
+                }
+                Animated::DiffuseColor(id, _)
+                | Animated::Emission(id, _)
+                | Animated::SpecularExponent(id, _)
+                | Animated::RefractiveIndex(id, _) => id.0 < scene.materials.len(),
+            };
+            if !exists {
+                return Err(RenderError::UnknownTrackTarget(index));
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets every animated property to its value at `time`, leaving `scene` and `params`
+    /// unchanged if the animation is not valid for `scene`, see [`validate`](Self::validate).
+    ///
+    /// Objects are moved relative to their position in `scene`, so the scene must be the
+    /// one built for the first frame rather than the result of an earlier call.
+    pub fn apply(
+        &self,
+        time: f32,
+        scene: &mut Scene,
+        params: &mut RenderParams,
+    ) -> Result<(), RenderError> {
+        self.validate(scene)?;
+        for track in &self.tracks {
+            match track {
+                Animated::CameraPosition(track) => params.pose.position = track.sample(time),
+                Animated::CameraTarget(track) => params.pose.target = track.sample(time),
+                Animated::FieldOfView(track) => params.field_of_view = track.sample(time),
+                Animated::ObjectOffset(index, track) => {
+                    let placeholder: Box<dyn Renderable> = Box::new(Vec::new());
+                    let object = std::mem::replace(&mut scene.objects[*index], placeholder);
+                    scene.objects[*index] = Box::new(Translated {
+                        object,
+                        offset: track.sample(time),
+                    });
+                }
+                Animated::LightPosition(index, track) => {
+                    scene.lights[*index].position = track.sample(time)
+                }
+                Animated::LightIntensity(index, track) => {
+                    scene.lights[*index].intensity = track.sample(time)
+                }
+                Animated::DiffuseColor(id, track) => {
+                    scene.materials[id.0].diffuse_color = track.sample(time)
+                }
+                Animated::Emission(id, track) => {
+                    scene.materials[id.0].emission = track.sample(time)
+                }
+                Animated::SpecularExponent(id, track) => {
+                    scene.materials[id.0].spectacular_component = track.sample(time)
+                }
+                Animated::RefractiveIndex(id, track) => {
+                    scene.materials[id.0].refractive_index = track.sample(time)
+                }
+            }
+        }
+        Ok(())
+    }
+}
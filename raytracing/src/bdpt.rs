@@ -0,0 +1,595 @@
+//! Bidirectional path tracer.
+
+use std::f32::consts::PI;
+
+use crate::bsdf::Bsdf;
+use crate::camera::Camera;
+use crate::path_tracer::{bsdf_samples, LightSamples, PathTracer};
+use crate::primitives::{Material, Ray};
+use crate::sampler::Sampler;
+use crate::sampling::{cosine_hemisphere, MisHeuristic};
+use crate::scene::{Emitter, RayIntersection, Renderable, Scene, RAY_EPSILON};
+use crate::stats;
+use crate::vec::{Float, Vec3, Vec3f};
+
+#[derive(Copy, Clone)]
+enum VertexKind<'a, T: Float> {
+    Camera,
+    /// Point sampled on an emitter; its emission is included in the vertex `beta`.
+    Light,
+    Surface {
+        material: &'a Material,
+        incoming: Vec3f,
+        /// The hit primitive, if it is one of the emitters light paths start from.
+        emitter: Option<&'a dyn Emitter<T>>,
+    },
+}
+
+/// Vertex of a camera or a light subpath.
+///
+/// `pdf_fwd` is the area density of sampling the vertex from the previous one of its
+/// subpath, `pdf_rev` the density of sampling it from the next one in the reversed direction.
+#[derive(Copy, Clone)]
+struct Vertex<'a, T: Float> {
+    kind: VertexKind<'a, T>,
+    point: Vec3<T>,
+    normal: Vec3f,
+    beta: Vec3f,
+    pdf_fwd: f32,
+    pdf_rev: f32,
+    delta: bool,
+}
+
+impl<'a, T: Float> Vertex<'a, T> {
+    fn is_on_surface(&self) -> bool {
+        !matches!(self.kind, VertexKind::Camera)
+    }
+
+    fn bsdf(&self, incoming: Vec3f) -> Option<Bsdf<'a>> {
+        match self.kind {
+            VertexKind::Surface { material, .. } => {
+                Some(Bsdf::new(material, self.normal, incoming))
+            }
+            _ => None,
+        }
+    }
+
+    fn is_connectible(&self) -> bool {
+        match self.kind {
+            VertexKind::Surface { incoming, .. } => !self.bsdf(incoming).unwrap().is_specular(),
+            _ => true,
+        }
+    }
+
+    /// Converts the solid-angle density of sampling `next` from this vertex to area density.
+    fn convert_density(&self, pdf: f32, next: &Vertex<T>) -> f32 {
+        let offset: Vec3f = (next.point - self.point).cast();
+        let distance_sqr = offset * offset;
+        if distance_sqr == 0.0 {
+            return 0.0;
+        }
+        let mut pdf = pdf / distance_sqr;
+        if next.is_on_surface() {
+            pdf *= (next.normal * offset).abs() / distance_sqr.sqrt();
+        }
+        pdf
+    }
+
+    /// Scattering from this vertex towards `next`, for light arriving from the previous
+    /// vertex of the subpath. Emission of light vertices is already part of their `beta`.
+    fn f(&self, next: &Vertex<T>) -> Vec3f {
+        let direction = (next.point - self.point).normalized().cast();
+        match self.kind {
+            VertexKind::Surface { incoming, .. } => self.bsdf(incoming).unwrap().f(direction),
+            _ => Vec3f::new(1.0, 1.0, 1.0),
+        }
+    }
+
+    /// Area density of sampling `next` from this vertex, reached from `prev`.
+    fn pdf(&self, camera: &dyn Camera<T>, prev: Option<&Vertex<T>>, next: &Vertex<T>) -> f32 {
+        let direction = (next.point - self.point).normalized();
+        let pdf = match self.kind {
+            VertexKind::Camera => camera.pdf(direction),
+            VertexKind::Light => return self.pdf_light(next),
+            VertexKind::Surface { .. } => {
+                let prev = match prev {
+                    Some(prev) => prev,
+                    None => return 0.0,
+                };
+                let incoming = (self.point - prev.point).normalized().cast();
+                self.bsdf(incoming).unwrap().pdf(direction.cast())
+            }
+        };
+        self.convert_density(pdf, next)
+    }
+
+    /// Area density of emitting light from this vertex towards `next`.
+    fn pdf_light(&self, next: &Vertex<T>) -> f32 {
+        let direction = (next.point - self.point).normalized().cast();
+        self.convert_density(emission_pdf(self.normal, direction), next)
+    }
+}
+
+/// Solid-angle density of emission directions, cosine distributed on both sides.
+fn emission_pdf(normal: Vec3f, direction: Vec3f) -> f32 {
+    (normal * direction).abs() / (2.0 * PI)
+}
+
+fn remap_zero(pdf: f32) -> f32 {
+    if pdf != 0.0 {
+        pdf
+    } else {
+        1.0
+    }
+}
+
+/// Estimates radiance by connecting every vertex of a camera subpath to every vertex of a
+/// light subpath, weighting all strategies producing a path by multiple importance sampling.
+///
+/// Light subpaths start on emitters. Strategies ending on the camera splat their light into
+/// the pixel they reach instead of the one being rendered. Point lights, which do not fall
+/// off with distance, and the environment are instead connected to the camera subpath as in
+/// the [`PathTracer`]. Participating media only attenuate light, they do not scatter it.
+pub struct BidirectionalPathTracer<'a, T: Float = f32> {
+    scene: &'a Scene<T>,
+    path_tracer: PathTracer<'a, T>,
+    emitters: Vec<&'a dyn Emitter<T>>,
+    camera: Box<dyn Camera<T>>,
+    width: usize,
+    height: usize,
+    pub heuristic: MisHeuristic,
+}
+
+impl<'a, T: Float> BidirectionalPathTracer<'a, T> {
+    /// Path tracer rendering an image of `width` by `height` pixels seen by `camera`.
+    pub fn new(
+        scene: &'a Scene<T>,
+        camera: Box<dyn Camera<T>>,
+        width: usize,
+        height: usize,
+    ) -> Self {
+        Self {
+            scene,
+            path_tracer: PathTracer::new(scene),
+            emitters: scene.emitters(),
+            camera,
+            width,
+            height,
+            heuristic: MisHeuristic::Power,
+        }
+    }
+
+    /// Radiance arriving along the camera `ray`, for paths of at most `depth + 1` segments.
+    ///
+    /// Light reaching other pixels is appended to `splats` as pairs of pixel index and
+    /// radiance; it must be added to the image divided by the number of camera rays traced.
+    pub fn radiance(
+        &self,
+        ray: Ray<T>,
+        depth: usize,
+        sampler: &mut dyn Sampler,
+        splats: &mut Vec<(usize, Vec3f)>,
+    ) -> Vec3f {
+        let mut radiance = Vec3f::default();
+        let mut camera_path = vec![Vertex {
+            kind: VertexKind::Camera,
+            point: ray.origin,
+            normal: Vec3f::new(0.0, 0.0, -1.0),
+            beta: Vec3f::new(1.0, 1.0, 1.0),
+            pdf_fwd: 1.0,
+            pdf_rev: 0.0,
+            // Cameras without a center cannot be reached by light subpaths.
+            delta: self.camera.center().is_none(),
+        }];
+        let pdf = self.camera.pdf(ray.direction);
+        let beta = Vec3f::new(1.0, 1.0, 1.0);
+        self.walk(
+            ray,
+            beta,
+            pdf,
+            depth,
+            &mut camera_path,
+            sampler,
+            Some(&mut radiance),
+        );
+        let light_path = self.light_subpath(depth, sampler);
+
+        for t in 1..=camera_path.len() {
+            for s in 0..=light_path.len() {
+                // Emitters seen directly are left to the camera subpath alone.
+                if s + t < 2 || (s == 1 && t == 1) || s + t > depth + 2 {
+                    continue;
+                }
+                if t == 1 {
+                    if let Some((pixel, contribution)) = self.connect_to_camera(&light_path, s) {
+                        splats.push((pixel, contribution));
+                    }
+                } else {
+                    let contribution = self.connect(&light_path, &camera_path, s, t, sampler);
+                    radiance = radiance + contribution;
+                }
+            }
+        }
+        radiance
+    }
+
+    fn pick_emitter(&self, sampler: &mut dyn Sampler) -> &'a dyn Emitter<T> {
+        let count = self.emitters.len();
+        self.emitters[((sampler.next_1d() * count as f32) as usize).min(count - 1)]
+    }
+
+    /// Area density of sampling a point on `emitter` as the start of a light path.
+    fn pdf_light_origin(&self, emitter: &dyn Emitter<T>) -> f32 {
+        1.0 / (emitter.area() * self.emitters.len() as f32)
+    }
+
+    /// Samples a point on an emitter, as the vertex a light subpath starts from.
+    fn sample_light_vertex(&self, sampler: &mut dyn Sampler) -> Vertex<'a, T> {
+        let emitter = self.pick_emitter(sampler);
+        let (u, v) = sampler.next_2d();
+        let sample = emitter.sample_surface(u, v);
+        let pdf = self.pdf_light_origin(emitter);
+        let emission = self.scene.material(emitter.material()).emission;
+        Vertex {
+            kind: VertexKind::Light,
+            point: sample.point,
+            normal: sample.normal.cast(),
+            beta: emission * (1.0 / pdf),
+            pdf_fwd: pdf,
+            pdf_rev: 0.0,
+            delta: false,
+        }
+    }
+
+    fn light_subpath(&self, depth: usize, sampler: &mut dyn Sampler) -> Vec<Vertex<'a, T>> {
+        if self.emitters.is_empty() {
+            return Vec::new();
+        }
+        let origin = self.sample_light_vertex(sampler);
+        let (u, v) = sampler.next_2d();
+        let mut direction = cosine_hemisphere(origin.normal, u, v);
+        if sampler.next_1d() < 0.5 {
+            direction = direction * -1.0;
+        }
+        let pdf = emission_pdf(origin.normal, direction);
+        if pdf <= 0.0 {
+            return vec![origin];
+        }
+        let beta = origin.beta * ((origin.normal * direction).abs() / pdf);
+        let ray = Ray {
+            origin: origin.point,
+            direction: direction.cast(),
+        };
+        let mut path = vec![origin];
+        self.walk(ray, beta, pdf, depth, &mut path, sampler, None);
+        path
+    }
+
+    /// Extends `path` by following `ray` through up to `depth` surfaces. `pdf` is the
+    /// solid-angle density `ray` was sampled with. Camera subpaths pass `radiance`, which
+    /// collects light of the point lights and the environment.
+    #[allow(clippy::too_many_arguments)]
+    fn walk(
+        &self,
+        ray: Ray<T>,
+        beta: Vec3f,
+        pdf: f32,
+        depth: usize,
+        path: &mut Vec<Vertex<'a, T>>,
+        sampler: &mut dyn Sampler,
+        mut radiance: Option<&mut Vec3f>,
+    ) {
+        let scene = self.scene;
+        let mut ray = ray;
+        let mut beta = beta;
+        let mut pdf_fwd = pdf;
+        // Solid-angle density of the last BSDF sample, `None` for camera rays and specular bounces.
+        let mut bsdf_pdf: Option<f32> = None;
+        while path.len() <= depth {
+            let from_camera = path.len() == 1 && radiance.is_some();
+            stats::record(|counters| {
+                if from_camera {
+                    counters.primary_rays += 1;
+                } else {
+                    counters.secondary_rays += 1;
+                }
+                counters.max_depth = counters.max_depth.max(path.len() - 1);
+            });
+            let t_min = if from_camera {
+                T::ZERO
+            } else {
+                RAY_EPSILON.cast()
+            };
+            let closest = scene.objects.ray_intersect(ray, t_min, T::INFINITY);
+            let distance = closest.map_or(T::INFINITY, |hit| hit.distance);
+            beta = beta * self.path_tracer.transmittance(ray, distance);
+
+            let closest = match closest {
+                Some(closest) => closest,
+                None => {
+                    if let Some(radiance) = radiance {
+                        *radiance = *radiance
+                            + beta.component_mul(scene.background_color)
+                                * self.path_tracer.environment_weight(bsdf_pdf);
+                    }
+                    return;
+                }
+            };
+            let RayIntersection {
+                hit,
+                normal,
+                material,
+                ..
+            } = closest.surface(ray);
+            let material = scene.material(material);
+            let (normal, incoming): (Vec3f, Vec3f) = (normal.cast(), ray.direction.cast());
+            let prev = path.len() - 1;
+            let mut vertex = Vertex {
+                kind: VertexKind::Surface {
+                    material,
+                    incoming,
+                    emitter: self.path_tracer.sampled_emitter(closest.primitive),
+                },
+                point: hit,
+                normal,
+                beta,
+                pdf_fwd: 0.0,
+                pdf_rev: 0.0,
+                delta: false,
+            };
+            vertex.pdf_fwd = path[prev].convert_density(pdf_fwd, &vertex);
+
+            let bsdf = Bsdf::new(material, normal, incoming);
+            if let Some(radiance) = radiance.as_deref_mut() {
+                let light = LightSamples::draw(sampler);
+                if !bsdf.is_specular() {
+                    let mut direct = self.path_tracer.point_lights(&bsdf, hit);
+                    if self.path_tracer.has_environment() {
+                        // The walk stops after the vertex at `depth`.
+                        let continued = path.len() < depth;
+                        let environment = self.path_tracer.sample_environment(
+                            &bsdf,
+                            hit,
+                            light.environment,
+                            continued,
+                        );
+                        direct = direct + environment;
+                    }
+                    *radiance = *radiance + beta.component_mul(direct);
+                }
+            }
+
+            let sample = match bsdf.sample(bsdf_samples(sampler)) {
+                Some(sample) => sample,
+                None => {
+                    path.push(vertex);
+                    return;
+                }
+            };
+            let pdf_rev = if sample.specular {
+                vertex.delta = true;
+                pdf_fwd = 0.0;
+                bsdf_pdf = None;
+                0.0
+            } else {
+                pdf_fwd = sample.pdf;
+                bsdf_pdf = Some(sample.pdf);
+                Bsdf::new(material, normal, sample.direction * -1.0).pdf(incoming * -1.0)
+            };
+            path[prev].pdf_rev = vertex.convert_density(pdf_rev, &path[prev]);
+            beta = beta.component_mul(sample.weight);
+            path.push(vertex);
+            ray = Ray {
+                origin: hit,
+                direction: sample.direction.cast(),
+            };
+        }
+    }
+
+    /// Whether nothing blocks the segment between two points, and the fraction of light
+    /// passing along it.
+    fn transmittance(&self, from: Vec3<T>, to: Vec3<T>) -> f32 {
+        let offset = to - from;
+        let distance = offset.norm();
+        let ray = Ray {
+            origin: from,
+            direction: offset * (T::ONE / distance),
+        };
+        stats::record(|counters| counters.shadow_rays += 1);
+        if self
+            .scene
+            .objects
+            .occluded(ray, distance - RAY_EPSILON.cast())
+        {
+            return 0.0;
+        }
+        self.path_tracer.transmittance(ray, distance)
+    }
+
+    /// Geometry term between two vertices, including visibility.
+    fn geometry(&self, a: &Vertex<T>, b: &Vertex<T>) -> f32 {
+        let offset: Vec3f = (b.point - a.point).cast();
+        let distance_sqr = offset * offset;
+        let direction = offset * (1.0 / distance_sqr.sqrt());
+        let mut g = 1.0 / distance_sqr;
+        if a.is_on_surface() {
+            g *= (a.normal * direction).abs();
+        }
+        if b.is_on_surface() {
+            g *= (b.normal * direction).abs();
+        }
+        if g == 0.0 {
+            return 0.0;
+        }
+        g * self.transmittance(a.point, b.point)
+    }
+
+    /// Light of the strategy using `s` light and `t >= 2` camera subpath vertices.
+    fn connect(
+        &self,
+        light_path: &[Vertex<'a, T>],
+        camera_path: &[Vertex<'a, T>],
+        s: usize,
+        t: usize,
+        sampler: &mut dyn Sampler,
+    ) -> Vec3f {
+        let camera = &camera_path[t - 1];
+        let mut sampled = None;
+        let contribution = if s == 0 {
+            match camera.kind {
+                // No other strategy can find light of emitters that are not sampled, so it
+                // is not weighted.
+                VertexKind::Surface {
+                    material,
+                    emitter: None,
+                    ..
+                } => return camera.beta.component_mul(material.emission),
+                VertexKind::Surface { material, .. } => {
+                    camera.beta.component_mul(material.emission)
+                }
+                _ => return Vec3f::default(),
+            }
+        } else {
+            if !camera.is_connectible() {
+                return Vec3f::default();
+            }
+            let light = if s == 1 {
+                if self.emitters.is_empty() {
+                    return Vec3f::default();
+                }
+                let vertex = self.sample_light_vertex(sampler);
+                sampled = Some(vertex);
+                vertex
+            } else {
+                light_path[s - 1]
+            };
+            if !light.is_connectible() {
+                return Vec3f::default();
+            }
+            let scattered = light
+                .beta
+                .component_mul(light.f(camera))
+                .component_mul(camera.f(&light))
+                .component_mul(camera.beta);
+            if scattered * scattered == 0.0 {
+                return Vec3f::default();
+            }
+            scattered * self.geometry(&light, camera)
+        };
+        if contribution * contribution == 0.0 {
+            return contribution;
+        }
+        contribution * self.mis_weight(light_path, camera_path, s, t, sampled)
+    }
+
+    /// Connects vertex `s` of the light subpath to the camera, returning the pixel it is
+    /// seen in and its weighted contribution.
+    fn connect_to_camera(&self, light_path: &[Vertex<'a, T>], s: usize) -> Option<(usize, Vec3f)> {
+        let light = &light_path[s - 1];
+        if !light.is_connectible() {
+            return None;
+        }
+        let center = self.camera.center()?;
+        let direction = light.point - center;
+        let (x, y) = self.camera.project(direction)?;
+        let pixel =
+            (y as usize).min(self.height - 1) * self.width + (x as usize).min(self.width - 1);
+        // Importance normalized so that splatted light gives the average radiance over the
+        // pixel, for which camera rays have density `pdf` times the number of pixels.
+        let importance = self.camera.pdf(direction) * (self.width * self.height) as f32;
+        let camera = Vertex {
+            kind: VertexKind::Camera,
+            point: center,
+            normal: Vec3f::new(0.0, 0.0, -1.0),
+            beta: Vec3f::new(1.0, 1.0, 1.0) * importance,
+            pdf_fwd: 1.0,
+            pdf_rev: 0.0,
+            delta: false,
+        };
+        let contribution = light
+            .beta
+            .component_mul(light.f(&camera))
+            .component_mul(camera.beta)
+            * self.geometry(light, &camera);
+        if contribution * contribution == 0.0 {
+            return None;
+        }
+        let weight = self.mis_weight(light_path, &[camera], s, 1, None);
+        Some((pixel, contribution * weight))
+    }
+
+    /// Weight of the strategy using `s` light and `t` camera vertices among all strategies
+    /// able to produce the same path. `sampled` replaces the last light vertex for `s == 1`.
+    fn mis_weight(
+        &self,
+        light_path: &[Vertex<'a, T>],
+        camera_path: &[Vertex<'a, T>],
+        s: usize,
+        t: usize,
+        sampled: Option<Vertex<'a, T>>,
+    ) -> f32 {
+        if s + t == 2 {
+            return 1.0;
+        }
+        let mut light: Vec<Vertex<T>> = light_path[..s].to_vec();
+        let mut camera: Vec<Vertex<T>> = camera_path[..t].to_vec();
+        if let Some(sampled) = sampled {
+            light[0] = sampled;
+        }
+
+        // Densities of the connecting vertices when sampled by the other subpath.
+        if s > 0 {
+            light[s - 1].delta = false;
+        }
+        camera[t - 1].delta = false;
+        camera[t - 1].pdf_rev = if s > 0 {
+            let prev = if s > 1 { Some(&light[s - 2]) } else { None };
+            light[s - 1].pdf(&*self.camera, prev, &camera[t - 1])
+        } else {
+            match camera[t - 1].kind {
+                VertexKind::Surface {
+                    emitter: Some(emitter),
+                    ..
+                } => self.pdf_light_origin(emitter),
+                _ => 0.0,
+            }
+        };
+        if t > 1 {
+            camera[t - 2].pdf_rev = if s > 0 {
+                camera[t - 1].pdf(&*self.camera, Some(&light[s - 1]), &camera[t - 2])
+            } else {
+                camera[t - 1].pdf_light(&camera[t - 2])
+            };
+        }
+        if s > 0 {
+            let prev = if t > 1 { Some(&camera[t - 2]) } else { None };
+            light[s - 1].pdf_rev = camera[t - 1].pdf(&*self.camera, prev, &light[s - 1]);
+        }
+        if s > 1 {
+            light[s - 2].pdf_rev =
+                light[s - 1].pdf(&*self.camera, Some(&camera[t - 1]), &light[s - 2]);
+        }
+
+        let heuristic = |ratio: f32| match self.heuristic {
+            MisHeuristic::Balance => ratio,
+            MisHeuristic::Power => ratio * ratio,
+        };
+        let mut sum = 0.0;
+        let mut ratio = 1.0;
+        for i in (1..t).rev() {
+            ratio *= remap_zero(camera[i].pdf_rev) / remap_zero(camera[i].pdf_fwd);
+            if !camera[i].delta && !camera[i - 1].delta {
+                sum += heuristic(ratio);
+            }
+        }
+        ratio = 1.0;
+        for i in (0..s).rev() {
+            ratio *= remap_zero(light[i].pdf_rev) / remap_zero(light[i].pdf_fwd);
+            let prev_delta = i > 0 && light[i - 1].delta;
+            if !light[i].delta && !prev_delta {
+                sum += heuristic(ratio);
+            }
+        }
+        1.0 / (1.0 + sum)
+    }
+}
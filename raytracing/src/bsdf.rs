@@ -0,0 +1,166 @@
+//! Scattering of the Whitted [`Material`] model, reinterpreted for stochastic integrators.
+//!
+//! Each of the four albedo components is a lobe: Lambertian diffuse tinted by
+//! `diffuse_color`, a normalized Phong highlight around the mirror direction, a perfect
+//! mirror and a perfect refraction. Lobes are picked in proportion to their albedo.
+
+use std::f32::consts::PI;
+
+use crate::primitives::Material;
+use crate::sampling::{around_axis, cosine_hemisphere};
+use crate::scene::reflect;
+use crate::vec::Vec3f;
+
+/// Direction sampled from a [`Bsdf`].
+#[derive(Copy, Clone, Debug)]
+pub struct BsdfSample {
+    pub direction: Vec3f,
+    /// Scattered value times cosine, divided by `pdf`.
+    pub weight: Vec3f,
+    /// Solid-angle density of `direction`. For specular lobes only the probability of
+    /// picking the lobe.
+    pub pdf: f32,
+    /// Whether the direction comes from a mirror or refraction lobe, which cannot be
+    /// evaluated for arbitrary directions.
+    pub specular: bool,
+}
+
+/// Scattering at one surface point, seen along the `incoming` ray direction.
+#[derive(Copy, Clone, Debug)]
+pub struct Bsdf<'a> {
+    material: &'a Material,
+    normal: Vec3f,
+    /// Normal flipped to the side the ray comes from.
+    facing: Vec3f,
+    incoming: Vec3f,
+    probabilities: [f32; 4],
+}
+
+impl<'a> Bsdf<'a> {
+    pub fn new(material: &'a Material, normal: Vec3f, incoming: Vec3f) -> Self {
+        let mut probabilities = [0.0; 4];
+        let total: f32 = material.albedo.iter().map(|albedo| albedo.max(0.0)).sum();
+        if total > 0.0 {
+            for (probability, albedo) in probabilities.iter_mut().zip(material.albedo.iter()) {
+                *probability = albedo.max(0.0) / total;
+            }
+        }
+        Self {
+            material,
+            normal,
+            facing: if incoming * normal > 0.0 {
+                normal * -1.0
+            } else {
+                normal
+            },
+            incoming,
+            probabilities,
+        }
+    }
+
+    /// Whether every lobe is a mirror or a refraction.
+    pub fn is_specular(&self) -> bool {
+        self.probabilities[0] == 0.0 && self.probabilities[1] == 0.0
+    }
+
+    fn exponent(&self) -> f32 {
+        self.material.spectacular_component.max(0.0)
+    }
+
+    /// Cosine between `direction` and the mirror direction, zero if behind the surface.
+    fn highlight_cosine(&self, direction: Vec3f) -> f32 {
+        (reflect(self.incoming, self.facing) * direction).max(0.0)
+    }
+
+    /// Scattered value times the cosine at the surface, for the non-specular lobes.
+    pub fn eval(&self, direction: Vec3f) -> Vec3f {
+        self.f(direction) * (direction * self.facing).max(0.0)
+    }
+
+    /// Scattered value towards `direction`, for the non-specular lobes.
+    pub fn f(&self, direction: Vec3f) -> Vec3f {
+        if direction * self.facing <= 0.0 {
+            return Vec3f::default();
+        }
+        let albedo = self.material.albedo;
+        let exponent = self.exponent();
+        let diffuse = self.material.diffuse_color * (albedo[0].max(0.0) / PI);
+        let highlight = albedo[1].max(0.0) * (exponent + 2.0) / (2.0 * PI)
+            * self.highlight_cosine(direction).powf(exponent);
+        diffuse + Vec3f::new(1.0, 1.0, 1.0) * highlight
+    }
+
+    /// Solid-angle density of sampling `direction` from the non-specular lobes.
+    pub fn pdf(&self, direction: Vec3f) -> f32 {
+        let cosine = direction * self.facing;
+        if cosine <= 0.0 {
+            return 0.0;
+        }
+        let exponent = self.exponent();
+        self.probabilities[0] * cosine / PI
+            + self.probabilities[1] * (exponent + 1.0) / (2.0 * PI)
+                * self.highlight_cosine(direction).powf(exponent)
+    }
+
+    /// Samples a scattered direction from three uniform numbers in `[0, 1)`.
+    pub fn sample(&self, u: [f32; 3]) -> Option<BsdfSample> {
+        let mut pick = u[0];
+        let mut lobe = 0;
+        while lobe < 3 && pick >= self.probabilities[lobe] {
+            pick -= self.probabilities[lobe];
+            lobe += 1;
+        }
+        if self.probabilities[lobe] == 0.0 {
+            return None;
+        }
+
+        let direction = match lobe {
+            0 => cosine_hemisphere(self.facing, u[1], u[2]),
+            1 => around_axis(
+                reflect(self.incoming, self.facing),
+                u[1].powf(1.0 / (self.exponent() + 1.0)),
+                2.0 * PI * u[2],
+            ),
+            _ => {
+                let probability = self.probabilities[lobe];
+                return Some(BsdfSample {
+                    direction: if lobe == 2 {
+                        reflect(self.incoming, self.facing)
+                    } else {
+                        self.transmitted()
+                    },
+                    weight: Vec3f::new(1.0, 1.0, 1.0) * (self.material.albedo[lobe] / probability),
+                    pdf: probability,
+                    specular: true,
+                });
+            }
+        };
+        let pdf = self.pdf(direction);
+        if pdf <= 0.0 {
+            return None;
+        }
+        Some(BsdfSample {
+            direction,
+            weight: self.eval(direction) * (1.0 / pdf),
+            pdf,
+            specular: false,
+        })
+    }
+
+    /// Refracted direction, or the mirror one on total internal reflection.
+    fn transmitted(&self) -> Vec3f {
+        let entering = self.incoming * self.normal < 0.0;
+        let eta = if entering {
+            1.0 / self.material.refractive_index
+        } else {
+            self.material.refractive_index
+        };
+        let cos_i = -(self.incoming * self.facing);
+        let k = 1.0 - eta * eta * (1.0 - cos_i * cos_i);
+        if k < 0.0 {
+            reflect(self.incoming, self.facing)
+        } else {
+            (self.incoming * eta + self.facing * (eta * cos_i - k.sqrt())).normalized()
+        }
+    }
+}
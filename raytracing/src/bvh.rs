@@ -0,0 +1,205 @@
+use crate::aabb::Aabb;
+use crate::error::GeometryError;
+use crate::primitives::{MaterialId, Ray};
+use crate::scene::{Hit, Renderable};
+use crate::stats;
+use crate::vec::Float;
+
+const MAX_LEAF_SIZE: usize = 2;
+
+/// Object to place in the hierarchy, with its bounds.
+type Entry<T> = (Aabb<T>, Box<dyn Renderable<T>>);
+
+#[derive(Clone, Debug)]
+enum Node<T> {
+    Leaf {
+        bounds: Aabb<T>,
+        first: usize,
+        count: usize,
+    },
+    Interior {
+        bounds: Aabb<T>,
+        left: usize,
+        right: usize,
+    },
+}
+
+impl<T> Node<T> {
+    fn bounds(&self) -> &Aabb<T> {
+        match self {
+            Node::Leaf { bounds, .. } | Node::Interior { bounds, .. } => bounds,
+        }
+    }
+}
+
+/// Bounding volume hierarchy over a set of objects.
+///
+/// Objects without bounds (see [`Renderable::bounds`]) are kept aside and tested
+/// against every ray.
+pub struct Bvh<T: Float = f32> {
+    objects: Vec<Box<dyn Renderable<T>>>,
+    unbounded: Vec<Box<dyn Renderable<T>>>,
+    nodes: Vec<Node<T>>,
+}
+
+impl<T: Float> Bvh<T> {
+    pub fn new(objects: Vec<Box<dyn Renderable<T>>>) -> Self {
+        let (bounded, unbounded): (Vec<_>, Vec<_>) = objects
+            .into_iter()
+            .partition(|object| object.bounds().is_some());
+        let mut entries: Vec<Entry<T>> = bounded
+            .into_iter()
+            .map(|object| (object.bounds().unwrap(), object))
+            .collect();
+
+        let mut nodes = Vec::new();
+        if !entries.is_empty() {
+            let count = entries.len();
+            build(&mut nodes, &mut entries, 0, count);
+        }
+        Self {
+            objects: entries.into_iter().map(|(_, object)| object).collect(),
+            unbounded,
+            nodes,
+        }
+    }
+}
+
+fn build<T: Float>(
+    nodes: &mut Vec<Node<T>>,
+    entries: &mut [Entry<T>],
+    first: usize,
+    count: usize,
+) -> usize {
+    let bounds = entries[first..first + count]
+        .iter()
+        .skip(1)
+        .fold(entries[first].0, |acc, (bounds, _)| acc.union(bounds));
+    let index = nodes.len();
+    if count <= MAX_LEAF_SIZE {
+        nodes.push(Node::Leaf {
+            bounds,
+            first,
+            count,
+        });
+        return index;
+    }
+
+    let centroid_bounds = entries[first..first + count]
+        .iter()
+        .map(|(bounds, _)| {
+            let centroid = bounds.centroid();
+            Aabb::new(centroid, centroid)
+        })
+        .fold(None, |acc: Option<Aabb<T>>, bounds| {
+            Some(acc.map_or(bounds, |acc| acc.union(&bounds)))
+        })
+        .unwrap();
+    let axis = centroid_bounds.largest_axis();
+    entries[first..first + count].sort_by(|(a, _), (b, _)| {
+        a.centroid()[axis]
+            .partial_cmp(&b.centroid()[axis])
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    nodes.push(Node::Interior {
+        bounds,
+        left: 0,
+        right: 0,
+    });
+    let half = count / 2;
+    let left = build(nodes, entries, first, half);
+    let right = build(nodes, entries, first + half, count - half);
+    nodes[index] = Node::Interior {
+        bounds,
+        left,
+        right,
+    };
+    index
+}
+
+impl<T: Float> Renderable<T> for Bvh<T> {
+    fn ray_intersect(&self, ray: Ray<T>, t_min: T, t_max: T) -> Option<Hit<'_, T>> {
+        let mut closest = self.unbounded.ray_intersect(ray, t_min, t_max);
+        let mut t_max = closest.map_or(t_max, |hit| hit.distance);
+
+        let mut stack = Vec::new();
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            stats::record(|counters| counters.bounds_tests += 1);
+            if !node.bounds().hit(ray, t_min, t_max) {
+                continue;
+            }
+            match *node {
+                Node::Leaf { first, count, .. } => {
+                    for object in &self.objects[first..first + count] {
+                        if let Some(hit) = object.ray_intersect(ray, t_min, t_max) {
+                            t_max = hit.distance;
+                            closest = Some(hit);
+                        }
+                    }
+                }
+                Node::Interior { left, right, .. } => {
+                    stack.push(right);
+                    stack.push(left);
+                }
+            }
+        }
+        closest
+    }
+
+    fn occluded(&self, ray: Ray<T>, max_dist: T) -> bool {
+        if self.unbounded.occluded(ray, max_dist) {
+            return true;
+        }
+        let mut stack = Vec::new();
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            stats::record(|counters| counters.bounds_tests += 1);
+            if !node.bounds().hit(ray, T::ZERO, max_dist) {
+                continue;
+            }
+            match *node {
+                Node::Leaf { first, count, .. } => {
+                    if self.objects[first..first + count]
+                        .iter()
+                        .any(|object| object.occluded(ray, max_dist))
+                    {
+                        return true;
+                    }
+                }
+                Node::Interior { left, right, .. } => {
+                    stack.push(right);
+                    stack.push(left);
+                }
+            }
+        }
+        false
+    }
+
+    fn bounds(&self) -> Option<Aabb<T>> {
+        if !self.unbounded.is_empty() {
+            return None;
+        }
+        self.nodes.first().map(|node| *node.bounds())
+    }
+
+    fn materials(&self) -> Vec<MaterialId> {
+        self.objects
+            .iter()
+            .chain(&self.unbounded)
+            .flat_map(|object| object.materials())
+            .collect()
+    }
+
+    fn validate(&self) -> Result<(), GeometryError> {
+        self.unbounded.validate()?;
+        self.objects.iter().try_for_each(|object| object.validate())
+    }
+}
@@ -0,0 +1,384 @@
+//! Projections turning image points into camera rays.
+//!
+//! All projections sit at the origin and look down the negative z axis, with y pointing up.
+//! A [`PosedCamera`] places them elsewhere in the scene, in the precision of its geometry.
+
+use std::f32::consts::PI;
+
+use crate::primitives::Ray;
+use crate::vec::{Float, Vec3, Vec3f};
+
+/// Projection of the image onto rays leaving the camera.
+///
+/// Image points are measured in pixels from the top-left corner of the image. Cameras whose
+/// rays all leave a single point can also be reached by light paths traced from the scene;
+/// they report that point from [`center`](Camera::center), and implement
+/// [`project`](Camera::project) and [`pdf`](Camera::pdf). Rays are in precision `T`.
+pub trait Camera<T: Float = f32> {
+    /// Ray through the point `(x, y)` of the image, `None` where the projection covers no
+    /// direction.
+    fn generate_ray(&self, x: f32, y: f32) -> Option<Ray<T>>;
+
+    /// Point all camera rays leave from, `None` if there is no such point.
+    fn center(&self) -> Option<Vec3<T>> {
+        None
+    }
+
+    /// Point of the image whose ray leaves the center along `direction`.
+    fn project(&self, _direction: Vec3<T>) -> Option<(f32, f32)> {
+        None
+    }
+
+    /// Solid-angle density of camera rays along `direction` for image points spread
+    /// uniformly over the image.
+    fn pdf(&self, _direction: Vec3<T>) -> f32 {
+        0.0
+    }
+}
+
+/// Shape of the projection used to render an image.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Projection {
+    /// Pinhole camera with a vertical field of view.
+    Perspective,
+    /// Parallel rays through an image plane `height` units tall.
+    Orthographic { height: f32 },
+    /// Equidistant fisheye covering the field of view across the largest circle fitting in
+    /// the image.
+    Fisheye,
+    /// Full sphere of directions, longitude along the width and latitude along the height.
+    Equirectangular,
+}
+
+impl Projection {
+    /// Camera of this projection for an image of `width` by `height` pixels.
+    pub fn camera(self, field_of_view: f32, width: usize, height: usize) -> Box<dyn Camera> {
+        match self {
+            Projection::Perspective => {
+                Box::new(PerspectiveCamera::new(field_of_view, width, height))
+            }
+            Projection::Orthographic {
+                height: view_height,
+            } => Box::new(OrthographicCamera::new(view_height, width, height)),
+            Projection::Fisheye => Box::new(FisheyeCamera::new(field_of_view, width, height)),
+            Projection::Equirectangular => Box::new(EquirectangularCamera::new(width, height)),
+        }
+    }
+}
+
+/// Placement of the camera in the scene.
+#[derive(Debug, Copy, Clone)]
+pub struct CameraPose<T = f32> {
+    pub position: Vec3<T>,
+    /// Point the camera looks at. The image stays upright with respect to the y axis.
+    pub target: Vec3<T>,
+}
+
+impl<T: Float> Default for CameraPose<T> {
+    fn default() -> Self {
+        Self {
+            position: Vec3::default(),
+            target: Vec3::new(T::ZERO, T::ZERO, -T::ONE),
+        }
+    }
+}
+
+/// Camera moved from the origin to a [`CameraPose`]. The projection works around the
+/// origin in `f32`; rays are moved to the pose in precision `T`.
+pub struct PosedCamera<T = f32> {
+    camera: Box<dyn Camera>,
+    position: Vec3<T>,
+    right: Vec3<T>,
+    up: Vec3<T>,
+    /// Viewing direction, the negative z axis of the posed camera.
+    forward: Vec3<T>,
+}
+
+impl<T: Float> PosedCamera<T> {
+    pub fn new(camera: Box<dyn Camera>, pose: CameraPose<T>) -> Self {
+        let forward = (pose.target - pose.position).normalized();
+        let world_up = if forward[1].abs() > 0.999f32.cast() {
+            Vec3::new(T::ZERO, T::ZERO, -T::ONE)
+        } else {
+            Vec3::new(T::ZERO, T::ONE, T::ZERO)
+        };
+        let right = forward.cross(world_up).normalized();
+        Self {
+            camera,
+            position: pose.position,
+            right,
+            up: right.cross(forward),
+            forward,
+        }
+    }
+
+    fn to_scene(&self, v: Vec3f) -> Vec3<T> {
+        let v: Vec3<T> = v.cast();
+        self.right * v[0] + self.up * v[1] - self.forward * v[2]
+    }
+
+    fn to_camera(&self, v: Vec3<T>) -> Vec3f {
+        Vec3::new(v * self.right, v * self.up, -(v * self.forward)).cast()
+    }
+}
+
+impl<T: Float> Camera<T> for PosedCamera<T> {
+    fn generate_ray(&self, x: f32, y: f32) -> Option<Ray<T>> {
+        let ray = self.camera.generate_ray(x, y)?;
+        Some(Ray {
+            origin: self.to_scene(ray.origin) + self.position,
+            direction: self.to_scene(ray.direction),
+        })
+    }
+
+    fn center(&self) -> Option<Vec3<T>> {
+        let center = self.camera.center()?;
+        Some(self.to_scene(center) + self.position)
+    }
+
+    fn project(&self, direction: Vec3<T>) -> Option<(f32, f32)> {
+        self.camera.project(self.to_camera(direction))
+    }
+
+    fn pdf(&self, direction: Vec3<T>) -> f32 {
+        self.camera.pdf(self.to_camera(direction))
+    }
+}
+
+fn ray_from_origin(direction: Vec3f) -> Ray {
+    Ray {
+        origin: Vec3f::default(),
+        direction,
+    }
+}
+
+/// The image point `(x, y)` if it lies inside an image of `width` by `height` pixels.
+fn inside_image(x: f32, y: f32, width: usize, height: usize) -> Option<(f32, f32)> {
+    if (0.0..width as f32).contains(&x) && (0.0..height as f32).contains(&y) {
+        Some((x, y))
+    } else {
+        None
+    }
+}
+
+/// Pinhole camera projecting onto a plane at unit distance.
+#[derive(Debug, Copy, Clone)]
+pub struct PerspectiveCamera {
+    width: usize,
+    height: usize,
+    /// Half of the image plane height at unit distance.
+    scale: f32,
+    aspect: f32,
+}
+
+impl PerspectiveCamera {
+    pub fn new(field_of_view: f32, width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            scale: (field_of_view / 2.0).tan(),
+            aspect: width as f32 / height as f32,
+        }
+    }
+
+    /// Area of the whole image plane at unit distance.
+    fn image_area(&self) -> f32 {
+        4.0 * self.scale * self.scale * self.aspect
+    }
+}
+
+impl Camera for PerspectiveCamera {
+    fn generate_ray(&self, x: f32, y: f32) -> Option<Ray> {
+        let dir_x = (2.0 * x / self.width as f32 - 1.0) * self.scale * self.aspect;
+        let dir_y = -(2.0 * y / self.height as f32 - 1.0) * self.scale;
+        Some(ray_from_origin(Vec3f::new(dir_x, dir_y, -1.0).normalized()))
+    }
+
+    fn center(&self) -> Option<Vec3f> {
+        Some(Vec3f::default())
+    }
+
+    fn project(&self, direction: Vec3f) -> Option<(f32, f32)> {
+        if direction[2] >= 0.0 {
+            return None;
+        }
+        let u = (direction[0] / -direction[2] / (self.scale * self.aspect) + 1.0) * 0.5;
+        let v = (1.0 - direction[1] / -direction[2] / self.scale) * 0.5;
+        let (x, y) = (u * self.width as f32, v * self.height as f32);
+        inside_image(x, y, self.width, self.height)
+    }
+
+    fn pdf(&self, direction: Vec3f) -> f32 {
+        if self.project(direction).is_none() {
+            return 0.0;
+        }
+        let cos_theta = -direction[2] / direction.norm();
+        1.0 / (self.image_area() * cos_theta.powi(3))
+    }
+}
+
+/// Camera with parallel rays, keeping sizes independent of the distance to the camera.
+#[derive(Debug, Copy, Clone)]
+pub struct OrthographicCamera {
+    width: usize,
+    height: usize,
+    view_height: f32,
+}
+
+impl OrthographicCamera {
+    pub fn new(view_height: f32, width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            view_height,
+        }
+    }
+}
+
+impl Camera for OrthographicCamera {
+    fn generate_ray(&self, x: f32, y: f32) -> Option<Ray> {
+        let view_width = self.view_height * self.width as f32 / self.height as f32;
+        let origin_x = (x / self.width as f32 - 0.5) * view_width;
+        let origin_y = (0.5 - y / self.height as f32) * self.view_height;
+        Some(Ray {
+            origin: Vec3f::new(origin_x, origin_y, 0.0),
+            direction: Vec3f::new(0.0, 0.0, -1.0),
+        })
+    }
+}
+
+/// Equidistant fisheye: the distance from the image center is proportional to the angle
+/// from the viewing axis. Points outside the image circle see nothing.
+#[derive(Debug, Copy, Clone)]
+pub struct FisheyeCamera {
+    width: usize,
+    height: usize,
+    /// Angle between the viewing axis and the rim of the image circle.
+    max_angle: f32,
+}
+
+impl FisheyeCamera {
+    pub fn new(field_of_view: f32, width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            max_angle: field_of_view / 2.0,
+        }
+    }
+
+    /// Radius of the image circle in pixels.
+    fn radius(&self) -> f32 {
+        self.width.min(self.height) as f32 / 2.0
+    }
+}
+
+impl Camera for FisheyeCamera {
+    fn generate_ray(&self, x: f32, y: f32) -> Option<Ray> {
+        let offset_x = (x - self.width as f32 / 2.0) / self.radius();
+        let offset_y = (self.height as f32 / 2.0 - y) / self.radius();
+        let r = (offset_x * offset_x + offset_y * offset_y).sqrt();
+        if r > 1.0 {
+            return None;
+        }
+        let theta = r * self.max_angle;
+        let (sin_theta, cos_theta) = theta.sin_cos();
+        let scale = if r > 0.0 { sin_theta / r } else { 0.0 };
+        Some(ray_from_origin(Vec3f::new(
+            offset_x * scale,
+            offset_y * scale,
+            -cos_theta,
+        )))
+    }
+
+    fn center(&self) -> Option<Vec3f> {
+        Some(Vec3f::default())
+    }
+
+    fn project(&self, direction: Vec3f) -> Option<(f32, f32)> {
+        let direction = direction.normalized();
+        let theta = (-direction[2]).clamp(-1.0, 1.0).acos();
+        let r = theta / self.max_angle;
+        if r > 1.0 {
+            return None;
+        }
+        let sin_theta = theta.sin();
+        let (offset_x, offset_y) = if sin_theta > 0.0 {
+            (direction[0] * r / sin_theta, direction[1] * r / sin_theta)
+        } else {
+            (0.0, 0.0)
+        };
+        let x = self.width as f32 / 2.0 + offset_x * self.radius();
+        let y = self.height as f32 / 2.0 - offset_y * self.radius();
+        inside_image(x, y, self.width, self.height)
+    }
+
+    fn pdf(&self, direction: Vec3f) -> f32 {
+        if self.project(direction).is_none() {
+            return 0.0;
+        }
+        let theta = (-direction[2] / direction.norm()).clamp(-1.0, 1.0).acos();
+        // Image area around the direction per unit solid angle: r dr dφ / (sin θ dθ dφ).
+        let radius = self.radius();
+        let jacobian = if theta > 1e-4 {
+            theta / theta.sin()
+        } else {
+            1.0
+        } * radius
+            * radius
+            / (self.max_angle * self.max_angle);
+        jacobian / (self.width * self.height) as f32
+    }
+}
+
+/// Latitude-longitude panorama covering every direction around the camera.
+///
+/// The center of the image looks down the negative z axis, longitude grows towards the
+/// positive x axis from left to right, and the top edge looks straight up.
+#[derive(Debug, Copy, Clone)]
+pub struct EquirectangularCamera {
+    width: usize,
+    height: usize,
+}
+
+impl EquirectangularCamera {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { width, height }
+    }
+}
+
+impl Camera for EquirectangularCamera {
+    fn generate_ray(&self, x: f32, y: f32) -> Option<Ray> {
+        let phi = 2.0 * PI * x / self.width as f32 - PI;
+        let theta = PI * y / self.height as f32;
+        let (sin_theta, cos_theta) = theta.sin_cos();
+        let (sin_phi, cos_phi) = phi.sin_cos();
+        Some(ray_from_origin(Vec3f::new(
+            sin_theta * sin_phi,
+            cos_theta,
+            -sin_theta * cos_phi,
+        )))
+    }
+
+    fn center(&self) -> Option<Vec3f> {
+        Some(Vec3f::default())
+    }
+
+    fn project(&self, direction: Vec3f) -> Option<(f32, f32)> {
+        let direction = direction.normalized();
+        let theta = direction[1].clamp(-1.0, 1.0).acos();
+        let phi = direction[0].atan2(-direction[2]);
+        let x = (phi + PI) / (2.0 * PI) * self.width as f32;
+        let y = theta / PI * self.height as f32;
+        inside_image(x, y, self.width, self.height)
+    }
+
+    fn pdf(&self, direction: Vec3f) -> f32 {
+        let sin_theta = (1.0 - (direction[1] / direction.norm()).powi(2))
+            .max(0.0)
+            .sqrt();
+        if sin_theta == 0.0 {
+            return 0.0;
+        }
+        1.0 / (2.0 * PI * PI * sin_theta)
+    }
+}
@@ -1,34 +1,68 @@
-use crate::primitives::{Material, Ray};
-use crate::scene::{RayIntersection, Renderable};
-use crate::vec::Vec3f;
+use crate::aabb::Aabb;
+use crate::primitives::{MaterialId, Ray};
+use crate::scene::{Hit, Primitive, RayIntersection, Renderable, RAY_EPSILON};
+use crate::stats::{self, PrimitiveKind};
+use crate::vec::{Float, Vec3};
 
+/// Board in the `y = -4` plane, alternating between the `even` and `odd` materials.
 #[derive(Copy, Clone, Debug, Default)]
-pub struct Checkerboard;
+pub struct Checkerboard {
+    pub even: MaterialId,
+    pub odd: MaterialId,
+}
 
-impl Renderable for Checkerboard {
-    fn ray_intersect(&self, ray: Ray) -> Option<RayIntersection> {
-        if ray.direction[1].abs() <= 1e-3 {
-            return None;
-        }
-        let dist = -(ray.origin[1] + 4.0) / ray.direction[1];
+impl Checkerboard {
+    /// Distance to the board, if the ray meets it within `[t_min, t_max]`.
+    fn hit<T: Float>(ray: Ray<T>, t_min: T, t_max: T) -> Option<T> {
+        stats::count_intersection_test(PrimitiveKind::Plane);
+        let dist = -(ray.origin[1] + 4.0f32.cast()) / ray.direction[1];
         let point = ray.origin + ray.direction * dist;
 
-        if dist > 0.0 && point[0].abs() < 10.0 && point[2] < -10.0 && point[2] > -30.0 {
-            let even = ((0.5 * point[0] + 1000.0) as i32 + (0.5 * point[2]) as i32) % 2 == 1;
-            let mut material = Material::default();
-            material.diffuse_color = if even {
-                Vec3f::new(1.0, 1.0, 1.0)
-            } else {
-                Vec3f::new(1.0, 0.7, 0.3)
-            } * 0.3;
-            Some(RayIntersection {
-                distance: dist,
-                hit: point,
-                normal: Vec3f::new(0.0, 1.0, 0.0),
-                material,
-            })
+        if dist >= t_min
+            && dist <= t_max
+            && point[0].abs() < 10.0f32.cast()
+            && point[2] < (-10.0f32).cast()
+            && point[2] > (-30.0f32).cast()
+        {
+            Some(dist)
         } else {
             None
         }
     }
 }
+
+impl<T: Float> Renderable<T> for Checkerboard {
+    fn ray_intersect(&self, ray: Ray<T>, t_min: T, t_max: T) -> Option<Hit<'_, T>> {
+        Self::hit(ray, t_min, t_max).map(|distance| Hit::new(distance, self))
+    }
+
+    fn occluded(&self, ray: Ray<T>, max_dist: T) -> bool {
+        Self::hit(ray, RAY_EPSILON.cast(), max_dist).is_some()
+    }
+
+    fn bounds(&self) -> Option<Aabb<T>> {
+        Some(Aabb::new(
+            Vec3::new(-10.0f32, -4.0, -30.0).cast(),
+            Vec3::new(10.0f32, -4.0, -10.0).cast(),
+        ))
+    }
+
+    fn materials(&self) -> Vec<MaterialId> {
+        vec![self.even, self.odd]
+    }
+}
+
+impl<T: Float> Primitive<T> for Checkerboard {
+    fn surface(&self, ray: Ray<T>, distance: T) -> RayIntersection<T> {
+        let point = ray.origin + ray.direction * distance;
+        let (x, z): (f32, f32) = (point[0].cast(), point[2].cast());
+        let even = ((0.5 * x + 1000.0) as i32 + (0.5 * z) as i32) % 2 == 1;
+        RayIntersection {
+            distance,
+            hit: point,
+            normal: Vec3::new(T::ZERO, T::ONE, T::ZERO),
+            uv: (((x + 10.0) / 20.0).cast(), ((z + 30.0) / 20.0).cast()),
+            material: if even { self.even } else { self.odd },
+        }
+    }
+}
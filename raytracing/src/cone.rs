@@ -0,0 +1,114 @@
+use crate::aabb::Aabb;
+use crate::cylinder::{cap_intersection, cylindrical_uv, disk_uv};
+use crate::error::{check_finite, check_positive, GeometryError};
+use crate::polynomial::solve_quadratic;
+use crate::primitives::{MaterialId, Ray};
+use crate::scene::{
+    convex_span, nearest_in_range, Hit, Primitive, RayIntersection, Renderable, Span,
+};
+use crate::stats::{self, PrimitiveKind};
+use crate::vec::{Float, Vec3};
+
+/// Cone with a capped circular base at `base` and its apex `height` above it along the y axis.
+#[derive(Copy, Clone, Debug)]
+pub struct Cone<T = f32> {
+    pub base: Vec3<T>,
+    pub radius: T,
+    pub height: T,
+    pub material: MaterialId,
+}
+
+impl<T: Float> Cone<T> {
+    /// Sorted distances at which the ray's line crosses the side or the base.
+    fn crossings(&self, ray: Ray<T>) -> Vec<T> {
+        stats::count_intersection_test(PrimitiveKind::Cone);
+        let local = Ray {
+            origin: ray.origin - self.base,
+            direction: ray.direction,
+        };
+        let (o, d) = (local.origin, local.direction);
+        // x^2 + z^2 = k^2 (h - y)^2
+        let k = self.radius / self.height;
+        let k2 = k * k;
+        let to_apex = self.height - o[1];
+        let side = solve_quadratic(
+            d[0] * d[0] + d[2] * d[2] - k2 * d[1] * d[1],
+            2.0f32.cast::<T>() * (o[0] * d[0] + o[2] * d[2] + k2 * to_apex * d[1]),
+            o[0] * o[0] + o[2] * o[2] - k2 * to_apex * to_apex,
+        )
+        .map_or([None, None], |(t0, t1)| [Some(t0), Some(t1)]);
+        let within_height = |t: &T| {
+            let y = o[1] + d[1] * *t;
+            y >= T::ZERO && y <= self.height
+        };
+        let mut crossings: Vec<T> = side
+            .iter()
+            .flatten()
+            .copied()
+            .filter(within_height)
+            .chain(cap_intersection(local, T::ZERO, self.radius))
+            .collect();
+        crossings.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        crossings
+    }
+}
+
+impl<T: Float> Renderable<T> for Cone<T> {
+    fn ray_intersect(&self, ray: Ray<T>, t_min: T, t_max: T) -> Option<Hit<'_, T>> {
+        nearest_in_range(self.crossings(ray), t_min, t_max).map(|distance| Hit::new(distance, self))
+    }
+
+    fn spans(&self, ray: Ray<T>) -> Vec<Span<'_, T>> {
+        convex_span(&self.crossings(ray), self)
+    }
+
+    fn bounds(&self) -> Option<Aabb<T>> {
+        Some(Aabb::new(
+            self.base - Vec3::new(self.radius, T::ZERO, self.radius),
+            self.base + Vec3::new(self.radius, self.height, self.radius),
+        ))
+    }
+
+    fn materials(&self) -> Vec<MaterialId> {
+        vec![self.material]
+    }
+
+    fn validate(&self) -> Result<(), GeometryError> {
+        check_finite(&[self.base])?;
+        check_positive("radius", self.radius)?;
+        check_positive("height", self.height)
+    }
+}
+
+impl<T: Float> Primitive<T> for Cone<T> {
+    fn surface(&self, ray: Ray<T>, distance: T) -> RayIntersection<T> {
+        let hit = ray.origin + ray.direction * distance;
+        let local = hit - self.base;
+        let radial = (local[0] * local[0] + local[2] * local[2]).sqrt();
+        let cap_epsilon = 1e-4f32.cast::<T>() * self.height.max(self.radius);
+
+        let (normal, uv) = if local[1] <= cap_epsilon && radial < self.radius - cap_epsilon {
+            (
+                Vec3::new(T::ZERO, -T::ONE, T::ZERO),
+                disk_uv(local, self.radius),
+            )
+        } else {
+            let k = self.radius / self.height;
+            let normal = Vec3::new(local[0], k * k * (self.height - local[1]), local[2]);
+            // The slope has no normal at the apex, take the axis there.
+            let normal = if normal.norm() > T::ZERO {
+                normal.normalized()
+            } else {
+                Vec3::new(T::ZERO, T::ONE, T::ZERO)
+            };
+            (normal, cylindrical_uv(local, self.height))
+        };
+        RayIntersection {
+            distance,
+            hit,
+            normal,
+            uv,
+            material: self.material,
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use crate::aabb::Aabb;
+use crate::error::GeometryError;
+use crate::primitives::{MaterialId, Ray};
+use crate::scene::{Hit, Renderable, Span};
+use crate::vec::Float;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    /// Points inside `left` but not inside `right`.
+    Difference,
+}
+
+impl CsgOperation {
+    fn contains(self, in_left: bool, in_right: bool) -> bool {
+        match self {
+            CsgOperation::Union => in_left || in_right,
+            CsgOperation::Intersection => in_left && in_right,
+            CsgOperation::Difference => in_left && !in_right,
+        }
+    }
+}
+
+/// Solid built from two other solids by a boolean operation.
+///
+/// Both operands must report their interiors through [`Renderable::spans`], so only closed
+/// solids (and other `Csg` nodes) can be combined.
+pub struct Csg<T: Float = f32> {
+    pub operation: CsgOperation,
+    pub left: Box<dyn Renderable<T>>,
+    pub right: Box<dyn Renderable<T>>,
+}
+
+impl<T: Float> Csg<T> {
+    pub fn union(left: Box<dyn Renderable<T>>, right: Box<dyn Renderable<T>>) -> Self {
+        Self {
+            operation: CsgOperation::Union,
+            left,
+            right,
+        }
+    }
+
+    pub fn intersection(left: Box<dyn Renderable<T>>, right: Box<dyn Renderable<T>>) -> Self {
+        Self {
+            operation: CsgOperation::Intersection,
+            left,
+            right,
+        }
+    }
+
+    pub fn difference(left: Box<dyn Renderable<T>>, right: Box<dyn Renderable<T>>) -> Self {
+        Self {
+            operation: CsgOperation::Difference,
+            left,
+            right,
+        }
+    }
+}
+
+/// Boundary crossing of one of the operands.
+struct Event<'a, T: Float> {
+    hit: Hit<'a, T>,
+    from_left: bool,
+    entering: bool,
+}
+
+impl<T: Float> Renderable<T> for Csg<T> {
+    fn ray_intersect(&self, ray: Ray<T>, t_min: T, t_max: T) -> Option<Hit<'_, T>> {
+        self.spans(ray)
+            .into_iter()
+            .flat_map(|span| vec![span.enter, span.exit])
+            .find(|hit| hit.distance >= t_min && hit.distance <= t_max)
+    }
+
+    fn bounds(&self) -> Option<Aabb<T>> {
+        match self.operation {
+            CsgOperation::Union => Some(self.left.bounds()?.union(&self.right.bounds()?)),
+            CsgOperation::Intersection => self.left.bounds().or_else(|| self.right.bounds()),
+            CsgOperation::Difference => self.left.bounds(),
+        }
+    }
+
+    fn spans(&self, ray: Ray<T>) -> Vec<Span<'_, T>> {
+        let mut events = Vec::new();
+        for (spans, from_left) in [(self.left.spans(ray), true), (self.right.spans(ray), false)] {
+            for span in spans {
+                events.push(Event {
+                    hit: span.enter,
+                    from_left,
+                    entering: true,
+                });
+                events.push(Event {
+                    hit: span.exit,
+                    from_left,
+                    entering: false,
+                });
+            }
+        }
+        events.sort_by(|a, b| {
+            a.hit
+                .distance
+                .partial_cmp(&b.hit.distance)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        // Depth counters rather than flags, so overlapping spans of one operand are fine.
+        let mut left_depth = 0i32;
+        let mut right_depth = 0i32;
+        let mut inside = false;
+        let mut enter = None;
+        let mut result = Vec::new();
+        for event in events {
+            let delta = if event.entering { 1 } else { -1 };
+            if event.from_left {
+                left_depth += delta;
+            } else {
+                right_depth += delta;
+            }
+            let now_inside = self.operation.contains(left_depth > 0, right_depth > 0);
+            if now_inside == inside {
+                continue;
+            }
+            inside = now_inside;
+
+            let mut hit = event.hit;
+            if self.operation == CsgOperation::Difference && !event.from_left {
+                // Surfaces of the subtracted solid face inwards.
+                hit.flip_normal = !hit.flip_normal;
+            }
+            if inside {
+                enter = Some(hit);
+            } else if let Some(enter) = enter.take() {
+                result.push(Span { enter, exit: hit });
+            }
+        }
+        result
+    }
+
+    fn materials(&self) -> Vec<MaterialId> {
+        let mut materials = self.left.materials();
+        materials.extend(self.right.materials());
+        materials
+    }
+
+    fn validate(&self) -> Result<(), GeometryError> {
+        self.left.validate()?;
+        self.right.validate()
+    }
+}
@@ -0,0 +1,116 @@
+use crate::aabb::Aabb;
+use crate::error::{check_finite, check_positive, GeometryError};
+use crate::primitives::{MaterialId, Ray};
+use crate::scene::{convex_span, Hit, Primitive, RayIntersection, Renderable, Span};
+use crate::stats::{self, PrimitiveKind};
+use crate::vec::{Float, Vec3};
+
+/// Axis-aligned box spanning from `min` to `max`.
+#[derive(Copy, Clone, Debug)]
+pub struct Cuboid<T = f32> {
+    pub min: Vec3<T>,
+    pub max: Vec3<T>,
+    pub material: MaterialId,
+}
+
+impl<T: Float> Cuboid<T> {
+    /// Distances at which the ray's line enters and leaves the box.
+    fn slab(&self, ray: Ray<T>) -> Option<(T, T)> {
+        stats::count_intersection_test(PrimitiveKind::Cuboid);
+        let mut t_near = T::NEG_INFINITY;
+        let mut t_far = T::INFINITY;
+        for axis in 0..3 {
+            let inv_direction = T::ONE / ray.direction[axis];
+            let mut t0 = (self.min[axis] - ray.origin[axis]) * inv_direction;
+            let mut t1 = (self.max[axis] - ray.origin[axis]) * inv_direction;
+            if inv_direction < T::ZERO {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_near = t_near.max(t0);
+            t_far = t_far.min(t1);
+        }
+        if t_near > t_far {
+            None
+        } else {
+            Some((t_near, t_far))
+        }
+    }
+}
+
+impl<T: Float> Renderable<T> for Cuboid<T> {
+    fn ray_intersect(&self, ray: Ray<T>, t_min: T, t_max: T) -> Option<Hit<'_, T>> {
+        let (t_near, t_far) = self.slab(ray)?;
+        let distance = if t_near >= t_min && t_near <= t_max {
+            t_near
+        } else if t_far >= t_min && t_far <= t_max {
+            t_far
+        } else {
+            return None;
+        };
+        Some(Hit::new(distance, self))
+    }
+
+    fn spans(&self, ray: Ray<T>) -> Vec<Span<'_, T>> {
+        match self.slab(ray) {
+            Some((t_near, t_far)) => convex_span(&[t_near, t_far], self),
+            None => Vec::new(),
+        }
+    }
+
+    fn bounds(&self) -> Option<Aabb<T>> {
+        Some(Aabb::new(self.min, self.max))
+    }
+
+    fn materials(&self) -> Vec<MaterialId> {
+        vec![self.material]
+    }
+
+    fn validate(&self) -> Result<(), GeometryError> {
+        check_finite(&[self.min, self.max])?;
+        let size = self.max - self.min;
+        check_positive("width", size[0])?;
+        check_positive("height", size[1])?;
+        check_positive("depth", size[2])
+    }
+}
+
+impl<T: Float> Primitive<T> for Cuboid<T> {
+    fn surface(&self, ray: Ray<T>, distance: T) -> RayIntersection<T> {
+        let hit = ray.origin + ray.direction * distance;
+        let half = 0.5f32.cast::<T>();
+        let center = (self.min + self.max) * half;
+        let half_size = (self.max - self.min) * half;
+
+        // The face that was hit is the one the point sticks out of the most.
+        let mut axis = 0;
+        let mut largest = T::NEG_INFINITY;
+        for i in 0..3 {
+            // A flat box is all face along its zero extent.
+            let offset = if half_size[i] > T::ZERO {
+                ((hit[i] - center[i]) / half_size[i]).abs()
+            } else {
+                T::INFINITY
+            };
+            if offset > largest {
+                largest = offset;
+                axis = i;
+            }
+        }
+        let mut normal = Vec3::default();
+        normal.coordinates[axis] = if hit[axis] != center[axis] {
+            (hit[axis] - center[axis]).signum()
+        } else {
+            -ray.direction[axis].signum()
+        };
+
+        let (u_axis, v_axis) = ((axis + 1) % 3, (axis + 2) % 3);
+        let local = |i: usize| (hit[i] - self.min[i]) / (self.max[i] - self.min[i]);
+        RayIntersection {
+            distance,
+            hit,
+            normal,
+            uv: (local(u_axis), local(v_axis)),
+            material: self.material,
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use crate::aabb::Aabb;
+use crate::error::{check_finite, check_positive, GeometryError};
+use crate::polynomial::solve_quadratic;
+use crate::primitives::{MaterialId, Ray};
+use crate::scene::{
+    convex_span, nearest_in_range, Hit, Primitive, RayIntersection, Renderable, Span,
+};
+use crate::stats::{self, PrimitiveKind};
+use crate::vec::{Float, Vec3};
+
+/// Capped cylinder standing on `base` and going up along the y axis.
+#[derive(Copy, Clone, Debug)]
+pub struct Cylinder<T = f32> {
+    pub base: Vec3<T>,
+    pub radius: T,
+    pub height: T,
+    pub material: MaterialId,
+}
+
+/// Texture coordinates around a y-aligned solid: angle around the axis and relative height.
+pub(crate) fn cylindrical_uv<T: Float>(local: Vec3<T>, height: T) -> (T, T) {
+    (
+        0.5f32.cast::<T>() + local[2].atan2(local[0]) / (T::PI + T::PI),
+        (local[1] / height).clamp(T::ZERO, T::ONE),
+    )
+}
+
+/// Distance to the horizontal cap at `cap_height` of a solid, if the hit is within `radius`.
+pub(crate) fn cap_intersection<T: Float>(local: Ray<T>, cap_height: T, radius: T) -> Option<T> {
+    let t = (cap_height - local.origin[1]) / local.direction[1];
+    let point = local.origin + local.direction * t;
+    if t.is_finite() && point[0] * point[0] + point[2] * point[2] <= radius * radius {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+impl<T: Float> Cylinder<T> {
+    /// Sorted distances at which the ray's line crosses the side or the caps.
+    fn crossings(&self, ray: Ray<T>) -> Vec<T> {
+        stats::count_intersection_test(PrimitiveKind::Cylinder);
+        let local = Ray {
+            origin: ray.origin - self.base,
+            direction: ray.direction,
+        };
+        let (o, d) = (local.origin, local.direction);
+        let side = solve_quadratic(
+            d[0] * d[0] + d[2] * d[2],
+            2.0f32.cast::<T>() * (o[0] * d[0] + o[2] * d[2]),
+            o[0] * o[0] + o[2] * o[2] - self.radius * self.radius,
+        )
+        .map_or([None, None], |(t0, t1)| [Some(t0), Some(t1)]);
+        let within_height = |t: &T| {
+            let y = o[1] + d[1] * *t;
+            y >= T::ZERO && y <= self.height
+        };
+        let mut crossings: Vec<T> = side
+            .iter()
+            .flatten()
+            .copied()
+            .filter(within_height)
+            .chain(cap_intersection(local, T::ZERO, self.radius))
+            .chain(cap_intersection(local, self.height, self.radius))
+            .collect();
+        crossings.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        crossings
+    }
+}
+
+impl<T: Float> Renderable<T> for Cylinder<T> {
+    fn ray_intersect(&self, ray: Ray<T>, t_min: T, t_max: T) -> Option<Hit<'_, T>> {
+        nearest_in_range(self.crossings(ray), t_min, t_max).map(|distance| Hit::new(distance, self))
+    }
+
+    fn spans(&self, ray: Ray<T>) -> Vec<Span<'_, T>> {
+        convex_span(&self.crossings(ray), self)
+    }
+
+    fn bounds(&self) -> Option<Aabb<T>> {
+        Some(Aabb::new(
+            self.base - Vec3::new(self.radius, T::ZERO, self.radius),
+            self.base + Vec3::new(self.radius, self.height, self.radius),
+        ))
+    }
+
+    fn materials(&self) -> Vec<MaterialId> {
+        vec![self.material]
+    }
+
+    fn validate(&self) -> Result<(), GeometryError> {
+        check_finite(&[self.base])?;
+        check_positive("radius", self.radius)?;
+        check_positive("height", self.height)
+    }
+}
+
+impl<T: Float> Primitive<T> for Cylinder<T> {
+    fn surface(&self, ray: Ray<T>, distance: T) -> RayIntersection<T> {
+        let hit = ray.origin + ray.direction * distance;
+        let local = hit - self.base;
+        let radial = (local[0] * local[0] + local[2] * local[2]).sqrt();
+        let cap_epsilon = 1e-4f32.cast::<T>() * self.height.max(self.radius);
+
+        let (normal, uv) = if local[1] <= cap_epsilon && radial < self.radius - cap_epsilon {
+            (
+                Vec3::new(T::ZERO, -T::ONE, T::ZERO),
+                disk_uv(local, self.radius),
+            )
+        } else if local[1] >= self.height - cap_epsilon && radial < self.radius - cap_epsilon {
+            (
+                Vec3::new(T::ZERO, T::ONE, T::ZERO),
+                disk_uv(local, self.radius),
+            )
+        } else {
+            (
+                Vec3::new(local[0], T::ZERO, local[2]).normalized(),
+                cylindrical_uv(local, self.height),
+            )
+        };
+        RayIntersection {
+            distance,
+            hit,
+            normal,
+            uv,
+            material: self.material,
+        }
+    }
+}
+
+/// Planar texture coordinates of a cap, mapping the disk of `radius` onto the unit square.
+pub(crate) fn disk_uv<T: Float>(local: Vec3<T>, radius: T) -> (T, T) {
+    let half = 0.5f32.cast::<T>();
+    (
+        (local[0] / radius + T::ONE) * half,
+        (local[2] / radius + T::ONE) * half,
+    )
+}
@@ -0,0 +1,133 @@
+//! Visualizations of the scene geometry replacing lighting, for tracking down scene errors.
+
+use crate::primitives::Ray;
+use crate::scene::{reflect, refract, Renderable, Scene, RAY_EPSILON};
+use crate::stats;
+use crate::vec::{Float, Vec3f};
+
+/// Quantity shown instead of the light along camera rays. Every view is black where the
+/// camera ray misses the scene.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DebugView {
+    /// Surface normal, each coordinate mapped from `[-1, 1]` to `[0, 1]`.
+    Normals,
+    /// Distance to the first hit, from white at the camera to black at `far`.
+    Depth { far: f32 },
+    /// Checkerboard of 8 by 8 squares in the surface coordinates of each object.
+    UvChecker,
+    /// Distinct colour for each material of the scene.
+    MaterialId,
+    /// Length of the longest chain of reflections and refractions the Whitted integrator
+    /// follows from the camera ray, blue where it stops at the first surface to red where
+    /// it reaches the render depth.
+    RecursionDepth,
+    /// Bounding box and primitive tests made to find the first hit, blue for none to red
+    /// for `max_cost` or more. Shown for misses too.
+    TraversalCost { max_cost: u64 },
+}
+
+const CHECKER_SQUARES: f32 = 8.0;
+
+impl DebugView {
+    /// Colour of the camera ray `ray`, tracing at most `depth` surfaces deep.
+    pub fn shade<T: Float>(self, scene: &Scene<T>, ray: Ray<T>, depth: usize) -> Vec3f {
+        let (closest, counters) =
+            stats::measure(|| scene.objects.ray_intersect(ray, T::ZERO, T::INFINITY));
+        stats::record(|counters| counters.primary_rays += 1);
+        if let DebugView::TraversalCost { max_cost } = self {
+            return heat(counters.traversal_cost() as f32 / max_cost.max(1) as f32);
+        }
+        let closest = match closest {
+            Some(closest) => closest,
+            None => return Vec3f::default(),
+        };
+        let intersection = closest.surface(ray).cast::<f32>();
+        match self {
+            DebugView::Normals => (intersection.normal + Vec3f::new(1.0, 1.0, 1.0)) * 0.5,
+            DebugView::Depth { far } => {
+                let grey = (1.0 - intersection.distance / far).clamp(0.0, 1.0);
+                Vec3f::new(grey, grey, grey)
+            }
+            DebugView::UvChecker => {
+                let (u, v) = intersection.uv;
+                let square = (u * CHECKER_SQUARES).floor() + (v * CHECKER_SQUARES).floor();
+                if square as i64 % 2 == 0 {
+                    Vec3f::new(0.9, 0.9, 0.9)
+                } else {
+                    Vec3f::new(0.1, 0.1, 0.1)
+                }
+            }
+            DebugView::MaterialId => false_color(intersection.material.0),
+            DebugView::RecursionDepth => {
+                let rays = ray_tree_depth(scene, ray, depth, T::ZERO);
+                heat((rays as f32 - 1.0) / (depth.max(2) - 1) as f32)
+            }
+            DebugView::TraversalCost { .. } => unreachable!(),
+        }
+    }
+}
+
+/// Number of rays on the longest branch of the Whitted ray tree of `ray`, following only
+/// the reflected and refracted rays whose contribution is weighted by a non-zero albedo.
+fn ray_tree_depth<T: Float>(scene: &Scene<T>, ray: Ray<T>, depth: usize, t_min: T) -> usize {
+    if depth == 0 {
+        return 0;
+    }
+    let closest = match scene.objects.ray_intersect(ray, t_min, T::INFINITY) {
+        Some(closest) => closest,
+        None => return 1,
+    };
+    let intersection = closest.surface(ray);
+    let material = scene.material(intersection.material);
+    let mut deepest = 0;
+    if material.albedo[2] != 0.0 {
+        let direction = reflect(ray.direction, intersection.normal).normalized();
+        let reflected = Ray {
+            origin: intersection.hit,
+            direction,
+        };
+        deepest = deepest.max(ray_tree_depth(
+            scene,
+            reflected,
+            depth - 1,
+            RAY_EPSILON.cast(),
+        ));
+    }
+    if material.albedo[3] != 0.0 {
+        let direction = refract(
+            ray.direction,
+            intersection.normal,
+            material.refractive_index,
+        )
+        .normalized();
+        let refracted = Ray {
+            origin: intersection.hit,
+            direction,
+        };
+        deepest = deepest.max(ray_tree_depth(
+            scene,
+            refracted,
+            depth - 1,
+            RAY_EPSILON.cast(),
+        ));
+    }
+    1 + deepest
+}
+
+/// Blue to cyan, green, yellow and red as `t` goes from 0 to 1.
+fn heat(t: f32) -> Vec3f {
+    let t = t.clamp(0.0, 1.0) * 4.0;
+    let ramp = |x: f32| x.clamp(0.0, 1.0);
+    Vec3f::new(ramp(t - 2.0), ramp(t).min(ramp(4.0 - t)), ramp(2.0 - t))
+}
+
+/// Saturated colour whose hue steps by the golden ratio with `index`, so that consecutive
+/// indices get clearly different colours.
+fn false_color(index: usize) -> Vec3f {
+    let hue = (index as f32 * 0.618_034 + 0.1).fract() * 6.0;
+    let channel = |offset: f32| {
+        let distance = ((hue - offset).rem_euclid(6.0) - 3.0).abs();
+        (distance - 1.0).clamp(0.0, 1.0) * 0.85 + 0.1
+    };
+    Vec3f::new(channel(0.0), channel(2.0), channel(4.0))
+}
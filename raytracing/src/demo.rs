@@ -0,0 +1,163 @@
+use crate::animation::{Animated, Animation, Interpolation, Track};
+use crate::checkerboard::Checkerboard;
+use crate::primitives::{Light, Material, MaterialId};
+use crate::scene::Scene;
+use crate::sphere::Sphere;
+use crate::vec::Vec3f;
+
+/// Builds the scene rendered by the `raytracing` binary.
+pub fn demo_scene() -> Scene {
+    let mut scene = Scene {
+        background_color: Vec3f::new(0.2, 0.7, 0.8),
+        ..Scene::default()
+    };
+    let ivory = scene.add_material(Material {
+        albedo: [0.6, 0.3, 0.1, 0.0],
+        diffuse_color: Vec3f::new(0.4, 0.4, 0.3),
+        spectacular_component: 50.0,
+        refractive_index: 1.0,
+        ..Material::default()
+    });
+    let glass = scene.add_material(Material {
+        albedo: [0.0, 0.5, 0.1, 0.8],
+        diffuse_color: Vec3f::new(0.6, 0.7, 0.8),
+        spectacular_component: 125.0,
+        refractive_index: 1.5,
+        ..Material::default()
+    });
+    let red_rubber = scene.add_material(Material {
+        albedo: [0.9, 0.1, 0.0, 0.0],
+        diffuse_color: Vec3f::new(0.3, 0.1, 0.1),
+        spectacular_component: 10.0,
+        refractive_index: 1.0,
+        ..Material::default()
+    });
+    let mirror = scene.add_material(Material {
+        albedo: [0.0, 10.0, 0.8, 0.0],
+        diffuse_color: Vec3f::new(1.0, 1.0, 1.0),
+        spectacular_component: 1425.0,
+        refractive_index: 1.0,
+        ..Material::default()
+    });
+
+    let board_white = scene.add_material(Material {
+        diffuse_color: Vec3f::new(1.0, 1.0, 1.0) * 0.3,
+        ..Material::default()
+    });
+    let board_orange = scene.add_material(Material {
+        diffuse_color: Vec3f::new(1.0, 0.7, 0.3) * 0.3,
+        ..Material::default()
+    });
+
+    scene.objects = vec![
+        Box::new(Sphere {
+            center: Vec3f::new(-3.0, 0.0, -16.0),
+            radius: 2.0,
+            material: ivory,
+        }),
+        Box::new(Sphere {
+            center: Vec3f::new(-1.0, -1.5, -12.0),
+            radius: 2.0,
+            material: glass,
+        }),
+        Box::new(Sphere {
+            center: Vec3f::new(1.5, -0.5, -18.0),
+            radius: 3.0,
+            material: red_rubber,
+        }),
+        Box::new(Sphere {
+            center: Vec3f::new(7.0, 5.0, -18.0),
+            radius: 4.0,
+            material: mirror,
+        }),
+        Box::new(Checkerboard {
+            even: board_white,
+            odd: board_orange,
+        }),
+    ];
+
+    scene.lights = vec![
+        Light {
+            position: Vec3f::new(-20.0, 20.0, 20.0),
+            intensity: 1.5,
+        },
+        Light {
+            position: Vec3f::new(30.0, 50.0, -25.0),
+            intensity: 1.8,
+        },
+        Light {
+            position: Vec3f::new(30.0, 20.0, 30.0),
+            intensity: 1.7,
+        },
+    ];
+    scene
+}
+
+/// Two second animation of [`demo_scene`]: the camera moves sideways while the glass
+/// sphere bounces, one light dims and the rubber sphere turns blue.
+pub fn demo_animation() -> Animation {
+    let smooth = Interpolation::Smooth;
+    let linear = Interpolation::Linear;
+    let target = Vec3f::new(0.0, 0.0, -16.0);
+    Animation {
+        frame_rate: 24.0,
+        tracks: vec![
+            Animated::CameraPosition(Track::new(
+                smooth,
+                vec![
+                    (0.0, Vec3f::new(-2.0, 1.0, 0.0)),
+                    (2.0, Vec3f::new(2.0, 2.0, 1.0)),
+                ],
+            )),
+            Animated::CameraTarget(Track::new(linear, vec![(0.0, target)])),
+            Animated::ObjectOffset(
+                1,
+                Track::new(
+                    smooth,
+                    vec![
+                        (0.0, Vec3f::new(0.0, 0.0, 0.0)),
+                        (0.5, Vec3f::new(0.0, 2.0, 0.0)),
+                        (1.0, Vec3f::new(0.0, 0.0, 0.0)),
+                        (1.5, Vec3f::new(0.0, 1.0, 0.0)),
+                        (2.0, Vec3f::new(0.0, 0.0, 0.0)),
+                    ],
+                ),
+            ),
+            Animated::LightIntensity(0, Track::new(linear, vec![(0.0, 1.5), (2.0, 0.3)])),
+            Animated::DiffuseColor(
+                MaterialId(2),
+                Track::new(
+                    linear,
+                    vec![
+                        (0.0, Vec3f::new(0.3, 0.1, 0.1)),
+                        (2.0, Vec3f::new(0.1, 0.1, 0.3)),
+                    ],
+                ),
+            ),
+        ],
+    }
+}
+
+/// Camera circling [`demo_scene`] once every `frame_count` frames, for looping previews.
+///
+/// Panics if `frame_count` is 0.
+pub fn demo_turntable(frame_count: usize) -> Animation {
+    assert!(frame_count > 0, "a turntable needs at least one frame");
+    let center = Vec3f::new(1.5, 0.0, -15.0);
+    let (radius, height) = (15.0, 4.0);
+    // A key every 15 degrees keeps the straight segments between keys close to the circle.
+    let keys = (0..=24)
+        .map(|key| {
+            let angle = key as f32 / 24.0 * 2.0 * std::f32::consts::PI;
+            let offset = Vec3f::new(angle.sin() * radius, height, angle.cos() * radius);
+            (key as f32 / 24.0, center + offset)
+        })
+        .collect();
+    Animation {
+        frame_rate: frame_count as f32,
+        tracks: vec![
+            Animated::CameraPosition(Track::new(Interpolation::Linear, keys)),
+            Animated::CameraTarget(Track::new(Interpolation::Linear, vec![(0.0, center)])),
+        ],
+    }
+}
@@ -0,0 +1,100 @@
+use crate::framebuffer::FrameBuffer;
+use crate::vec::Vec3f;
+
+/// Settings of the edge-avoiding à-trous wavelet filter.
+///
+/// `strength` controls how much color difference is smoothed over, the other
+/// sigmas control how strictly edges in the auxiliary buffers are preserved.
+#[derive(Debug, Copy, Clone)]
+pub struct DenoiseParams {
+    pub iterations: usize,
+    pub strength: f32,
+    pub normal_sigma: f32,
+    pub albedo_sigma: f32,
+    pub depth_sigma: f32,
+}
+
+impl Default for DenoiseParams {
+    fn default() -> Self {
+        Self {
+            iterations: 5,
+            strength: 0.5,
+            normal_sigma: 0.3,
+            albedo_sigma: 0.1,
+            depth_sigma: 0.05,
+        }
+    }
+}
+
+const KERNEL: [f32; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+fn gaussian_weight(distance_sqr: f32, sigma: f32) -> f32 {
+    if sigma <= 0.0 {
+        return if distance_sqr == 0.0 { 1.0 } else { 0.0 };
+    }
+    (-distance_sqr / (sigma * sigma)).exp()
+}
+
+fn depth_weight(depth: f32, other: f32, sigma: f32) -> f32 {
+    match (depth.is_finite(), other.is_finite()) {
+        (false, false) => 1.0,
+        (true, true) => {
+            let relative = (depth - other).abs() / depth.min(other).max(1e-3);
+            gaussian_weight(relative * relative, sigma)
+        }
+        _ => 0.0,
+    }
+}
+
+/// Filters `frame.color` in place using normal, albedo and depth as edge-stopping guides.
+pub fn denoise(frame: &mut FrameBuffer, params: DenoiseParams) {
+    let width = frame.width as isize;
+    let height = frame.height as isize;
+    let mut color_sigma = params.strength;
+    let mut output = frame.color.clone();
+
+    for iteration in 0..params.iterations {
+        let step = 1isize << iteration;
+        for j in 0..height {
+            for i in 0..width {
+                let index = (i + j * width) as usize;
+                let color = frame.color[index];
+                let aov = frame.aov(index);
+
+                let mut sum = Vec3f::default();
+                let mut weight_sum = 0.0;
+                for (dy, ky) in KERNEL.iter().enumerate() {
+                    for (dx, kx) in KERNEL.iter().enumerate() {
+                        let x = i + (dx as isize - 2) * step;
+                        let y = j + (dy as isize - 2) * step;
+                        if x < 0 || y < 0 || x >= width || y >= height {
+                            continue;
+                        }
+                        let other_index = (x + y * width) as usize;
+                        let other_color = frame.color[other_index];
+                        let other = frame.aov(other_index);
+
+                        let color_delta = color - other_color;
+                        let normal_delta = aov.normal - other.normal;
+                        let albedo_delta = aov.albedo - other.albedo;
+                        let weight = kx
+                            * ky
+                            * gaussian_weight(color_delta * color_delta, color_sigma)
+                            * gaussian_weight(normal_delta * normal_delta, params.normal_sigma)
+                            * gaussian_weight(albedo_delta * albedo_delta, params.albedo_sigma)
+                            * depth_weight(aov.depth, other.depth, params.depth_sigma);
+                        sum = sum + other_color * weight;
+                        weight_sum += weight;
+                    }
+                }
+                output[index] = if weight_sum > 0.0 {
+                    sum * (1.0 / weight_sum)
+                } else {
+                    color
+                };
+            }
+        }
+        std::mem::swap(&mut frame.color, &mut output);
+        color_sigma *= 0.5;
+    }
+}
@@ -0,0 +1,101 @@
+use crate::aabb::Aabb;
+use crate::error::{check_finite, check_positive, check_unit, GeometryError};
+use crate::primitives::{MaterialId, Ray};
+use crate::sampling::{orthonormal_basis, uniform_disk};
+use crate::scene::{Emitter, Hit, Primitive, RayIntersection, Renderable, SurfaceSample};
+use crate::stats::{self, PrimitiveKind};
+use crate::vec::{Float, Vec3};
+
+/// Flat two-sided disk. `normal` must be of unit length.
+#[derive(Copy, Clone, Debug)]
+pub struct Disk<T = f32> {
+    pub center: Vec3<T>,
+    pub normal: Vec3<T>,
+    pub radius: T,
+    pub material: MaterialId,
+}
+
+impl<T: Float> Renderable<T> for Disk<T> {
+    fn ray_intersect(&self, ray: Ray<T>, t_min: T, t_max: T) -> Option<Hit<'_, T>> {
+        stats::count_intersection_test(PrimitiveKind::Disk);
+        let distance = ((self.center - ray.origin) * self.normal) / (ray.direction * self.normal);
+        if !(distance >= t_min && distance <= t_max) {
+            return None;
+        }
+        let offset = ray.origin + ray.direction * distance - self.center;
+        if offset * offset > self.radius * self.radius {
+            return None;
+        }
+        Some(Hit::new(distance, self))
+    }
+
+    fn bounds(&self) -> Option<Aabb<T>> {
+        let extent = |axis: usize| {
+            self.radius
+                * (T::ONE - self.normal[axis] * self.normal[axis])
+                    .max(T::ZERO)
+                    .sqrt()
+        };
+        let extent = Vec3::new(extent(0), extent(1), extent(2));
+        Some(Aabb::new(self.center - extent, self.center + extent))
+    }
+
+    fn emitter(&self) -> Option<&dyn Emitter<T>> {
+        Some(self)
+    }
+
+    fn materials(&self) -> Vec<MaterialId> {
+        vec![self.material]
+    }
+
+    fn validate(&self) -> Result<(), GeometryError> {
+        check_finite(&[self.center, self.normal])?;
+        check_unit("normal", self.normal)?;
+        check_positive("radius", self.radius)
+    }
+}
+
+impl<T: Float> Primitive<T> for Disk<T> {
+    fn surface(&self, ray: Ray<T>, distance: T) -> RayIntersection<T> {
+        let hit = ray.origin + ray.direction * distance;
+        let normal = if ray.direction * self.normal > T::ZERO {
+            self.normal * -T::ONE
+        } else {
+            self.normal
+        };
+        let (tangent, bitangent) = orthonormal_basis(self.normal);
+        let offset = hit - self.center;
+        let angle = (offset * bitangent).atan2(offset * tangent);
+        RayIntersection {
+            distance,
+            hit,
+            normal,
+            uv: (
+                0.5f32.cast::<T>() + angle / (T::PI + T::PI),
+                (offset.norm() / self.radius).min(T::ONE),
+            ),
+            material: self.material,
+        }
+    }
+}
+
+impl<T: Float> Emitter<T> for Disk<T> {
+    fn material(&self) -> MaterialId {
+        self.material
+    }
+
+    fn area(&self) -> f32 {
+        let radius: f32 = self.radius.cast();
+        std::f32::consts::PI * radius * radius
+    }
+
+    fn sample_surface(&self, u: f32, v: f32) -> SurfaceSample<T> {
+        let (tangent, bitangent) = orthonormal_basis(self.normal);
+        let (x, y) = uniform_disk(u, v);
+        SurfaceSample {
+            point: self.center
+                + (tangent * x.cast::<T>() + bitangent * y.cast::<T>()) * self.radius,
+            normal: self.normal,
+        }
+    }
+}
@@ -0,0 +1,205 @@
+//! Errors reported before and while rendering.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::primitives::MaterialId;
+use crate::vec::{Float, Vec3};
+
+/// Problem with the shape of a scene object.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GeometryError {
+    /// A radius, height or other size that must be positive, named by `quantity`.
+    NonPositiveSize { quantity: &'static str, value: f32 },
+    /// A NaN or infinite coordinate in the position or orientation of the object.
+    NonFiniteCoordinate,
+    /// A direction, named by `quantity`, that must be of unit length.
+    NonUnitLength { quantity: &'static str, length: f32 },
+}
+
+impl fmt::Display for GeometryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeometryError::NonPositiveSize { quantity, value } => {
+                write!(f, "{} must be positive, got {}", quantity, value)
+            }
+            GeometryError::NonFiniteCoordinate => write!(f, "coordinate is NaN or infinite"),
+            GeometryError::NonUnitLength { quantity, length } => {
+                write!(f, "{} must be of unit length, got {}", quantity, length)
+            }
+        }
+    }
+}
+
+pub(crate) fn check_finite<T: Float>(points: &[Vec3<T>]) -> Result<(), GeometryError> {
+    if points
+        .iter()
+        .all(|point| point.coordinates.iter().all(|c| c.is_finite()))
+    {
+        Ok(())
+    } else {
+        Err(GeometryError::NonFiniteCoordinate)
+    }
+}
+
+pub(crate) fn check_positive<T: Float>(
+    quantity: &'static str,
+    value: T,
+) -> Result<(), GeometryError> {
+    // Also rejects NaN.
+    if value > T::ZERO && value.is_finite() {
+        Ok(())
+    } else {
+        Err(GeometryError::NonPositiveSize {
+            quantity,
+            value: value.cast(),
+        })
+    }
+}
+
+pub(crate) fn check_unit<T: Float>(
+    quantity: &'static str,
+    direction: Vec3<T>,
+) -> Result<(), GeometryError> {
+    let length: f32 = direction.norm().cast();
+    // Also rejects NaN.
+    if (length - 1.0).abs() <= UNIT_LENGTH_TOLERANCE {
+        Ok(())
+    } else {
+        Err(GeometryError::NonUnitLength { quantity, length })
+    }
+}
+
+/// Largest difference from 1 of the length of a unit direction, allowing for the rounding
+/// of [`Vec3::normalized`](crate::vec::Vec3::normalized).
+const UNIT_LENGTH_TOLERANCE: f32 = 1e-4;
+
+/// Reason a scene could not be rendered.
+#[derive(Debug)]
+pub enum RenderError {
+    Io(io::Error),
+    /// Image without pixels.
+    EmptyImage {
+        width: usize,
+        height: usize,
+    },
+    /// Field of view outside the range the projection supports.
+    FieldOfView(f32),
+    /// A depth of 0, which traces no rays at all.
+    ZeroDepth,
+    /// Height of the view of an orthographic camera that is not positive.
+    ViewHeight(f32),
+    /// NaN or infinite camera position or target, or a target at the camera position, which
+    /// gives no view direction.
+    InvalidCamera,
+    /// Invalid object at `index` in [`Scene::objects`](crate::scene::Scene::objects).
+    InvalidObject {
+        index: usize,
+        error: GeometryError,
+    },
+    /// Object at index `object` in [`Scene::objects`](crate::scene::Scene::objects)
+    /// referring to a material the scene does not have.
+    UnknownMaterial {
+        object: usize,
+        material: MaterialId,
+    },
+    /// Light at the given index in [`Scene::lights`](crate::scene::Scene::lights) with a NaN
+    /// or infinite position or intensity.
+    InvalidLight(usize),
+    /// Track at the given index in
+    /// [`Animation::tracks`](crate::animation::Animation::tracks) animating an object, light
+    /// or material the scene does not have.
+    UnknownTrackTarget(usize),
+    /// Material with a NaN, infinite or negative color, emission or specular exponent, or a
+    /// refractive index that is not positive.
+    InvalidMaterial(MaterialId),
+    /// Fog with a NaN, infinite or negative absorption or scattering, or an asymmetry
+    /// outside `(-1, 1)`.
+    InvalidFog,
+    /// Volume at the given index in [`Media::volumes`](crate::medium::Media::volumes) with
+    /// coefficients that would be invalid for fog, or an invalid boundary.
+    InvalidVolume(usize),
+    /// Frame rate of an animation that is not positive.
+    FrameRate(f32),
+    /// Material reflecting more light than it receives, see
+    /// [`Material::conserves_energy`](crate::primitives::Material::conserves_energy).
+    AlbedoNotEnergyConserving {
+        material: MaterialId,
+        albedo: [f32; 4],
+    },
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::Io(error) => write!(f, "cannot write image: {}", error),
+            RenderError::EmptyImage { width, height } => {
+                write!(f, "image of {}x{} pixels is empty", width, height)
+            }
+            RenderError::FieldOfView(fov) => {
+                write!(f, "field of view of {} radians is out of range", fov)
+            }
+            RenderError::ZeroDepth => write!(f, "depth must be at least 1"),
+            RenderError::ViewHeight(height) => {
+                write!(f, "orthographic view height of {} is not positive", height)
+            }
+            RenderError::InvalidCamera => {
+                write!(
+                    f,
+                    "camera pose is NaN, infinite or looks at its own position"
+                )
+            }
+            RenderError::InvalidObject { index, error } => write!(f, "object {}: {}", index, error),
+            RenderError::UnknownMaterial { object, material } => {
+                write!(f, "object {} has unknown material {}", object, material.0)
+            }
+            RenderError::InvalidLight(index) => {
+                write!(
+                    f,
+                    "light {} has a NaN or infinite position or intensity",
+                    index
+                )
+            }
+            RenderError::UnknownTrackTarget(index) => {
+                write!(
+                    f,
+                    "track {} animates something the scene does not have",
+                    index
+                )
+            }
+            RenderError::InvalidMaterial(material) => write!(
+                f,
+                "material {} has a NaN, infinite or negative color or index",
+                material.0
+            ),
+            RenderError::InvalidFog => write!(f, "fog has invalid coefficients"),
+            RenderError::InvalidVolume(index) => {
+                write!(f, "volume {} has invalid coefficients or boundary", index)
+            }
+            RenderError::FrameRate(rate) => {
+                write!(f, "frame rate of {} is not positive", rate)
+            }
+            RenderError::AlbedoNotEnergyConserving { material, albedo } => write!(
+                f,
+                "albedo {:?} of material {} does not conserve energy",
+                albedo, material.0
+            ),
+        }
+    }
+}
+
+impl Error for RenderError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RenderError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RenderError {
+    fn from(error: io::Error) -> Self {
+        RenderError::Io(error)
+    }
+}
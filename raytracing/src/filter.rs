@@ -0,0 +1,93 @@
+//! Pixel reconstruction filters.
+
+use std::f32::consts::PI;
+
+/// Reconstruction filter weighting the camera samples around a pixel center.
+///
+/// All filters are separable and vanish beyond `radius` pixels from the center. Mitchell and
+/// Lanczos filters have negative lobes, which sharpen edges at the price of slight ringing.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Filter {
+    /// Equal weights up to `radius`; a radius of 0.5 averages the samples of each pixel.
+    Box { radius: f32 },
+    /// Weights falling linearly to zero at `radius`.
+    Tent { radius: f32 },
+    /// Gaussian `exp(-alpha x²)`, shifted down to reach zero at `radius`.
+    Gaussian { radius: f32, alpha: f32 },
+    /// Mitchell–Netravali cubic, `b = c = 1/3` being the recommended trade-off between
+    /// blurring and ringing.
+    Mitchell { radius: f32, b: f32, c: f32 },
+    /// Sinc windowed by its own first lobe stretched to `radius`.
+    Lanczos { radius: f32 },
+}
+
+impl Filter {
+    /// Gaussian filter of the given radius with `alpha = 2`.
+    pub fn gaussian(radius: f32) -> Self {
+        Filter::Gaussian { radius, alpha: 2.0 }
+    }
+
+    /// Mitchell–Netravali filter of the given radius with `b = c = 1/3`.
+    pub fn mitchell(radius: f32) -> Self {
+        Filter::Mitchell {
+            radius,
+            b: 1.0 / 3.0,
+            c: 1.0 / 3.0,
+        }
+    }
+
+    pub fn radius(&self) -> f32 {
+        match *self {
+            Filter::Box { radius }
+            | Filter::Tent { radius }
+            | Filter::Gaussian { radius, .. }
+            | Filter::Mitchell { radius, .. }
+            | Filter::Lanczos { radius } => radius,
+        }
+    }
+
+    /// Weight of a sample at offset `(x, y)` from a pixel center, in pixels.
+    pub fn evaluate(&self, x: f32, y: f32) -> f32 {
+        self.evaluate_1d(x) * self.evaluate_1d(y)
+    }
+
+    fn evaluate_1d(&self, x: f32) -> f32 {
+        let radius = self.radius();
+        let x = x.abs();
+        if x > radius {
+            return 0.0;
+        }
+        match *self {
+            Filter::Box { .. } => 1.0,
+            Filter::Tent { .. } => radius - x,
+            Filter::Gaussian { alpha, .. } => {
+                ((-alpha * x * x).exp() - (-alpha * radius * radius).exp()).max(0.0)
+            }
+            Filter::Mitchell { b, c, .. } => mitchell_1d(2.0 * x / radius, b, c),
+            Filter::Lanczos { .. } => sinc(x) * sinc(x / radius),
+        }
+    }
+}
+
+/// Mitchell–Netravali cubic on `[0, 2]`.
+fn mitchell_1d(x: f32, b: f32, c: f32) -> f32 {
+    let value = if x > 1.0 {
+        (-b - 6.0 * c) * x * x * x
+            + (6.0 * b + 30.0 * c) * x * x
+            + (-12.0 * b - 48.0 * c) * x
+            + (8.0 * b + 24.0 * c)
+    } else {
+        (12.0 - 9.0 * b - 6.0 * c) * x * x * x
+            + (-18.0 + 12.0 * b + 6.0 * c) * x * x
+            + (6.0 - 2.0 * b)
+    };
+    value / 6.0
+}
+
+fn sinc(x: f32) -> f32 {
+    if x < 1e-5 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
@@ -0,0 +1,170 @@
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::vec::Vec3f;
+
+/// Auxiliary data of the first surface visible through a pixel.
+#[derive(Copy, Clone, Debug)]
+pub struct Aov {
+    pub normal: Vec3f,
+    pub albedo: Vec3f,
+    pub depth: f32,
+}
+
+impl Default for Aov {
+    fn default() -> Self {
+        Self {
+            normal: Vec3f::default(),
+            albedo: Vec3f::default(),
+            depth: f32::INFINITY,
+        }
+    }
+}
+
+/// Rendered image together with its auxiliary buffers.
+///
+/// Pixels that see no surface have zero normal and albedo and infinite depth.
+/// `samples` holds the number of camera samples taken for each pixel.
+#[derive(Clone, Debug)]
+pub struct FrameBuffer {
+    pub width: usize,
+    pub height: usize,
+    pub color: Vec<Vec3f>,
+    pub normal: Vec<Vec3f>,
+    pub albedo: Vec<Vec3f>,
+    pub depth: Vec<f32>,
+    pub samples: Vec<usize>,
+}
+
+impl FrameBuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            color: vec![Vec3f::default(); width * height],
+            normal: vec![Vec3f::default(); width * height],
+            albedo: vec![Vec3f::default(); width * height],
+            depth: vec![f32::INFINITY; width * height],
+            samples: vec![0; width * height],
+        }
+    }
+
+    pub fn set_aov(&mut self, index: usize, aov: Aov) {
+        self.normal[index] = aov.normal;
+        self.albedo[index] = aov.albedo;
+        self.depth[index] = aov.depth;
+    }
+
+    pub fn aov(&self, index: usize) -> Aov {
+        Aov {
+            normal: self.normal[index],
+            albedo: self.albedo[index],
+            depth: self.depth[index],
+        }
+    }
+
+    /// Converts colors to 8-bit RGB, scaling down overexposed pixels.
+    pub fn to_rgb8(&self) -> Vec<u8> {
+        let mut raw_buffer = Vec::with_capacity(self.width * self.height * 3);
+        for &color in &self.color {
+            let mut vector = color;
+            let max_coordinate = vector[0].max(vector[1]).max(vector[2]);
+            if max_coordinate > 1.0 {
+                vector = vector * (1.0 / max_coordinate);
+            }
+            for i in 0..3 {
+                raw_buffer.push((255.0 * vector[i].clamp(0.0, 1.0)) as u8);
+            }
+        }
+        raw_buffer
+    }
+
+    pub fn write_ppm<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "P6\n{} {}\n255", self.width, self.height)?;
+        writer.write_all(&self.to_rgb8())
+    }
+
+    pub fn save_ppm<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let file = std::fs::File::create(path)?;
+        let mut writer = BufWriter::new(file);
+        self.write_ppm(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Writes an 8-bit RGB PNG. The image data is stored without compression.
+    pub fn write_png<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(b"\x89PNG\r\n\x1a\n")?;
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        // Bit depth 8, truecolor, default compression, filtering and no interlacing.
+        header.extend_from_slice(&[8, 2, 0, 0, 0]);
+        write_png_chunk(writer, b"IHDR", &header)?;
+
+        // Every scanline starts with filter type 0 (none).
+        let rgb = self.to_rgb8();
+        let mut scanlines = Vec::with_capacity(rgb.len() + self.height);
+        for row in rgb.chunks(3 * self.width.max(1)) {
+            scanlines.push(0);
+            scanlines.extend_from_slice(row);
+        }
+        write_png_chunk(writer, b"IDAT", &zlib_stored(&scanlines))?;
+        write_png_chunk(writer, b"IEND", &[])
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let file = std::fs::File::create(path)?;
+        let mut writer = BufWriter::new(file);
+        self.write_png(&mut writer)?;
+        writer.flush()
+    }
+}
+
+fn write_png_chunk<W: Write>(writer: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+    let crc = crc32(kind.iter().chain(data));
+    writer.write_all(&crc.to_be_bytes())
+}
+
+/// CRC-32 with the polynomial used by PNG and zlib.
+fn crc32<'a, I: IntoIterator<Item = &'a u8>>(bytes: I) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Zlib stream holding `data` in uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = 0xffff;
+    let blocks = data.len().div_ceil(MAX_BLOCK).max(1);
+    let mut stream = Vec::with_capacity(data.len() + 5 * blocks + 6);
+    stream.extend_from_slice(&[0x78, 0x01]);
+    let mut chunks = data.chunks(MAX_BLOCK).peekable();
+    if chunks.peek().is_none() {
+        stream.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(chunk) = chunks.next() {
+        let last = chunks.peek().is_none();
+        let length = chunk.len() as u16;
+        stream.push(last as u8);
+        stream.extend_from_slice(&length.to_le_bytes());
+        stream.extend_from_slice(&(!length).to_le_bytes());
+        stream.extend_from_slice(chunk);
+    }
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + u32::from(byte)) % 65521;
+        b = (b + a) % 65521;
+    }
+    stream.extend_from_slice(&((b << 16) | a).to_be_bytes());
+    stream
+}
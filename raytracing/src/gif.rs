@@ -0,0 +1,338 @@
+//! Animated GIF output: median-cut colour quantization, Floyd–Steinberg dithering and LZW
+//! compression.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::framebuffer::FrameBuffer;
+
+#[derive(Debug, Copy, Clone)]
+pub struct GifParams {
+    /// Time each frame is shown, in hundredths of a second.
+    pub frame_delay: u16,
+    /// Diffuses the quantization error to neighbouring pixels, trading banding in smooth
+    /// gradients for fine noise.
+    pub dither: bool,
+}
+
+impl Default for GifParams {
+    fn default() -> Self {
+        Self {
+            frame_delay: 4,
+            dither: true,
+        }
+    }
+}
+
+/// Colours an image is reduced to, at most 256.
+#[derive(Debug, Clone)]
+pub struct Palette {
+    pub colors: Vec<[u8; 3]>,
+}
+
+/// Distinct colour of an image and the number of pixels having it.
+#[derive(Debug, Copy, Clone)]
+struct Bucket {
+    color: [u8; 3],
+    count: u32,
+}
+
+impl Palette {
+    /// Palette of at most `max_colors` colours for the 8-bit RGB images `images`, found by
+    /// recursively splitting the box of colours in use at the median of its longest side.
+    pub fn median_cut(images: &[&[u8]], max_colors: usize) -> Self {
+        let mut histogram: HashMap<[u8; 3], u32> = HashMap::new();
+        for image in images {
+            for pixel in image.chunks_exact(3) {
+                *histogram.entry([pixel[0], pixel[1], pixel[2]]).or_insert(0) += 1;
+            }
+        }
+        let mut buckets: Vec<Bucket> = histogram
+            .into_iter()
+            .map(|(color, count)| Bucket { color, count })
+            .collect();
+        // Hash map order is random; splits depend on the order of equal coordinates.
+        buckets.sort_unstable_by_key(|bucket| bucket.color);
+
+        let mut boxes = vec![buckets];
+        while boxes.len() < max_colors {
+            // Split the box spreading the most pixels over the widest range of colours.
+            let (index, axis, priority) = boxes
+                .iter()
+                .enumerate()
+                .map(|(index, colors)| {
+                    let (axis, range) = longest_axis(colors);
+                    let count: u32 = colors.iter().map(|bucket| bucket.count).sum();
+                    (index, axis, range * count as f32)
+                })
+                .fold((0, 0, 0.0), |best, candidate| {
+                    if candidate.2 > best.2 {
+                        candidate
+                    } else {
+                        best
+                    }
+                });
+            if priority <= 0.0 {
+                break;
+            }
+            let mut colors = boxes.swap_remove(index);
+            colors.sort_by_key(|bucket| bucket.color[axis]);
+            let half = colors.iter().map(|bucket| bucket.count).sum::<u32>() / 2;
+            let mut below = 0;
+            let mut split = colors.len() - 1;
+            for (i, bucket) in colors.iter().enumerate() {
+                below += bucket.count;
+                if below >= half {
+                    split = i + 1;
+                    break;
+                }
+            }
+            let upper = colors.split_off(split.clamp(1, colors.len() - 1));
+            boxes.push(colors);
+            boxes.push(upper);
+        }
+
+        let colors = boxes
+            .iter()
+            .filter(|colors| !colors.is_empty())
+            .map(|colors| {
+                let count: u32 = colors.iter().map(|bucket| bucket.count).sum();
+                [0, 1, 2].map(|c| {
+                    let sum: u64 = colors
+                        .iter()
+                        .map(|bucket| u64::from(bucket.color[c]) * u64::from(bucket.count))
+                        .sum();
+                    (sum as f32 / count as f32).round() as u8
+                })
+            })
+            .collect();
+        Self { colors }
+    }
+
+    /// Index of the palette colour closest to `color`.
+    pub fn nearest(&self, color: [f32; 3]) -> u8 {
+        let distance = |entry: &[u8; 3]| {
+            (0..3)
+                .map(|c| (f32::from(entry[c]) - color[c]).powi(2))
+                .sum::<f32>()
+        };
+        let mut best = (0, f32::INFINITY);
+        for (index, entry) in self.colors.iter().enumerate() {
+            let distance = distance(entry);
+            if distance < best.1 {
+                best = (index, distance);
+            }
+        }
+        best.0 as u8
+    }
+
+    /// Palette indices of the 8-bit RGB image `rgb`, `width` pixels wide.
+    pub fn index_image(&self, rgb: &[u8], width: usize, dither: bool) -> Vec<u8> {
+        let mut cache: HashMap<[u8; 3], u8> = HashMap::new();
+        let mut nearest = |color: [f32; 3]| {
+            let key = color.map(|channel| channel.round().clamp(0.0, 255.0) as u8);
+            *cache
+                .entry(key)
+                .or_insert_with(|| self.nearest(key.map(f32::from)))
+        };
+
+        let mut error = vec![[0.0f32; 3]; rgb.len() / 3];
+        let mut indices = Vec::with_capacity(rgb.len() / 3);
+        for (i, pixel) in rgb.chunks_exact(3).enumerate() {
+            let color = [0, 1, 2].map(|c| f32::from(pixel[c]) + error[i][c]);
+            let index = nearest(color);
+            indices.push(index);
+            if !dither {
+                continue;
+            }
+            // Floyd–Steinberg weights to the right and on the next row.
+            let entry = self.colors[index as usize];
+            let residual = [0, 1, 2].map(|c| color[c] - f32::from(entry[c]));
+            let (x, y) = (i % width, i / width);
+            let mut spread = |dx: isize, dy: usize, weight: f32| {
+                let nx = x as isize + dx;
+                if nx < 0 || nx >= width as isize {
+                    return;
+                }
+                let target = (y + dy) * width + nx as usize;
+                if let Some(error) = error.get_mut(target) {
+                    for c in 0..3 {
+                        error[c] += residual[c] * weight;
+                    }
+                }
+            };
+            spread(1, 0, 7.0 / 16.0);
+            spread(-1, 1, 3.0 / 16.0);
+            spread(0, 1, 5.0 / 16.0);
+            spread(1, 1, 1.0 / 16.0);
+        }
+        indices
+    }
+}
+
+/// Channel with the widest spread of bucket colours, and that spread.
+fn longest_axis(colors: &[Bucket]) -> (usize, f32) {
+    let mut best = (0, 0.0);
+    for axis in 0..3 {
+        let (min, max) = colors
+            .iter()
+            .fold((u8::MAX, u8::MIN), |(min, max), bucket| {
+                (min.min(bucket.color[axis]), max.max(bucket.color[axis]))
+            });
+        let range = f32::from(max.saturating_sub(min));
+        if range > best.1 {
+            best = (axis, range);
+        }
+    }
+    best
+}
+
+/// Writes `frames` as an endlessly looping GIF animation sharing one palette.
+///
+/// Panics if the frames differ in size or are larger than 65535 pixels on a side.
+pub fn write_gif<W: Write>(
+    writer: &mut W,
+    frames: &[FrameBuffer],
+    params: GifParams,
+) -> io::Result<()> {
+    let (width, height) = frames.first().map_or((0, 0), |f| (f.width, f.height));
+    assert!(
+        frames
+            .iter()
+            .all(|f| (f.width, f.height) == (width, height)),
+        "GIF frames must all have the same size"
+    );
+    let (width, height) = (dimension(width), dimension(height));
+    let images: Vec<Vec<u8>> = frames.iter().map(FrameBuffer::to_rgb8).collect();
+    let slices: Vec<&[u8]> = images.iter().map(Vec::as_slice).collect();
+    let palette = Palette::median_cut(&slices, 256);
+
+    writer.write_all(b"GIF89a")?;
+    writer.write_all(&width.to_le_bytes())?;
+    writer.write_all(&height.to_le_bytes())?;
+    // Global colour table of 256 entries with 8 bits per channel, no background or aspect.
+    writer.write_all(&[0xf7, 0, 0])?;
+    for index in 0..256 {
+        let color = palette.colors.get(index).copied().unwrap_or_default();
+        writer.write_all(&color)?;
+    }
+    // Netscape extension looping forever.
+    writer.write_all(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00")?;
+
+    for image in &images {
+        let indices = palette.index_image(image, usize::from(width), params.dither);
+        // Graphic control extension: keep the frame until the next one, no transparency.
+        writer.write_all(&[0x21, 0xf9, 0x04, 0x04])?;
+        writer.write_all(&params.frame_delay.to_le_bytes())?;
+        writer.write_all(&[0, 0])?;
+        // Image descriptor covering the whole screen, without local colour table.
+        writer.write_all(&[0x2c, 0, 0, 0, 0])?;
+        writer.write_all(&width.to_le_bytes())?;
+        writer.write_all(&height.to_le_bytes())?;
+        writer.write_all(&[0, 8])?;
+        for block in lzw_compress(&indices).chunks(255) {
+            writer.write_all(&[block.len() as u8])?;
+            writer.write_all(block)?;
+        }
+        writer.write_all(&[0])?;
+    }
+    writer.write_all(&[0x3b])
+}
+
+pub fn save_gif<P: AsRef<Path>>(
+    path: P,
+    frames: &[FrameBuffer],
+    params: GifParams,
+) -> io::Result<()> {
+    let file = std::fs::File::create(path)?;
+    let mut writer = BufWriter::new(file);
+    write_gif(&mut writer, frames, params)?;
+    writer.flush()
+}
+
+fn dimension(size: usize) -> u16 {
+    u16::try_from(size).expect("GIF images are at most 65535 pixels on a side")
+}
+
+const CLEAR_CODE: u16 = 256;
+const END_CODE: u16 = 257;
+const MAX_CODE_SIZE: u32 = 12;
+
+/// Packs variable-width codes least significant bit first.
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u32) {
+        self.buffer |= u32::from(code) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+/// LZW code stream of 8-bit palette indices, as stored in GIF image data.
+pub fn lzw_compress(indices: &[u8]) -> Vec<u8> {
+    let mut output = BitWriter {
+        bytes: Vec::new(),
+        buffer: 0,
+        bits: 0,
+    };
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next_code = END_CODE + 1;
+    let mut code_size = 9;
+    output.write(CLEAR_CODE, code_size);
+
+    let mut symbols = indices.iter();
+    let mut prefix = match symbols.next() {
+        Some(&first) => u16::from(first),
+        None => {
+            output.write(END_CODE, code_size);
+            return output.finish();
+        }
+    };
+    for &symbol in symbols {
+        if let Some(&code) = table.get(&(prefix, symbol)) {
+            prefix = code;
+            continue;
+        }
+        output.write(prefix, code_size);
+        // The decoder adds its table entries one code later, so it widens its codes when
+        // the encoder's table holds one more entry than fits the current width.
+        if next_code == 1 << MAX_CODE_SIZE {
+            output.write(CLEAR_CODE, code_size);
+            table.clear();
+            next_code = END_CODE + 1;
+            code_size = 9;
+        } else {
+            table.insert((prefix, symbol), next_code);
+            next_code += 1;
+            if u32::from(next_code) > 1 << code_size && code_size < MAX_CODE_SIZE {
+                code_size += 1;
+            }
+        }
+        prefix = u16::from(symbol);
+    }
+    output.write(prefix, code_size);
+    // The decoder adds an entry for the last code too, which may widen the end code.
+    if u32::from(next_code) == 1 << code_size && code_size < MAX_CODE_SIZE {
+        code_size += 1;
+    }
+    output.write(END_CODE, code_size);
+    output.finish()
+}
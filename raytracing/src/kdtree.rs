@@ -0,0 +1,157 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::vec::{Float, Vec3};
+
+/// Balanced kd-tree over items placed at points, answering nearest-neighbour queries.
+///
+/// The tree is stored implicitly: the median of every range of `nodes` is the node
+/// splitting that range, along the axis recorded in `axes`. Points are in precision `F`.
+pub struct KdTree<T, F = f32> {
+    nodes: Vec<(Vec3<F>, T)>,
+    axes: Vec<u8>,
+}
+
+/// Item found by [`KdTree::nearest`], ordered by distance.
+struct Neighbour<F> {
+    distance_sqr: F,
+    index: usize,
+}
+
+impl<F: Float> PartialEq for Neighbour<F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.distance_sqr == other.distance_sqr
+    }
+}
+
+impl<F: Float> Eq for Neighbour<F> {}
+
+impl<F: Float> PartialOrd for Neighbour<F> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<F: Float> Ord for Neighbour<F> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance_sqr
+            .partial_cmp(&other.distance_sqr)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl<T, F: Float> KdTree<T, F> {
+    pub fn new(mut nodes: Vec<(Vec3<F>, T)>) -> Self {
+        let mut axes = vec![0; nodes.len()];
+        Self::build(&mut nodes, &mut axes);
+        Self { nodes, axes }
+    }
+
+    fn build(nodes: &mut [(Vec3<F>, T)], axes: &mut [u8]) {
+        if nodes.len() <= 1 {
+            return;
+        }
+        let mut min = nodes[0].0;
+        let mut max = nodes[0].0;
+        for (point, _) in nodes.iter() {
+            for axis in 0..3 {
+                min.coordinates[axis] = min[axis].min(point[axis]);
+                max.coordinates[axis] = max[axis].max(point[axis]);
+            }
+        }
+        let extent = max - min;
+        let axis = if extent[0] >= extent[1] && extent[0] >= extent[2] {
+            0
+        } else if extent[1] >= extent[2] {
+            1
+        } else {
+            2
+        };
+
+        let median = nodes.len() / 2;
+        nodes.select_nth_unstable_by(median, |a, b| {
+            a.0[axis].partial_cmp(&b.0[axis]).unwrap_or(Ordering::Equal)
+        });
+        axes[median] = axis as u8;
+        let (left, right) = nodes.split_at_mut(median);
+        let (left_axes, right_axes) = axes.split_at_mut(median);
+        Self::build(left, left_axes);
+        Self::build(&mut right[1..], &mut right_axes[1..]);
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Up to `count` items closest to `point` and within `max_distance` of it, with their
+    /// squared distances, closest first.
+    pub fn nearest(&self, point: Vec3<F>, count: usize, max_distance: F) -> Vec<(F, &T)> {
+        let mut heap = BinaryHeap::with_capacity(count + 1);
+        if count > 0 {
+            self.search(
+                0,
+                self.nodes.len(),
+                point,
+                count,
+                max_distance * max_distance,
+                &mut heap,
+            );
+        }
+        heap.into_sorted_vec()
+            .into_iter()
+            .map(|neighbour| (neighbour.distance_sqr, &self.nodes[neighbour.index].1))
+            .collect()
+    }
+
+    fn search(
+        &self,
+        start: usize,
+        end: usize,
+        point: Vec3<F>,
+        count: usize,
+        max_distance_sqr: F,
+        heap: &mut BinaryHeap<Neighbour<F>>,
+    ) {
+        if start >= end {
+            return;
+        }
+        let index = start + (end - start) / 2;
+        let (node_point, _) = &self.nodes[index];
+        let offset = point - *node_point;
+        let distance_sqr = offset * offset;
+        if distance_sqr <= max_distance_sqr {
+            heap.push(Neighbour {
+                distance_sqr,
+                index,
+            });
+            if heap.len() > count {
+                heap.pop();
+            }
+        }
+        if end - start == 1 {
+            return;
+        }
+
+        let axis = self.axes[index] as usize;
+        let plane_offset = offset[axis];
+        let (near, far) = if plane_offset < F::ZERO {
+            ((start, index), (index + 1, end))
+        } else {
+            ((index + 1, end), (start, index))
+        };
+        self.search(near.0, near.1, point, count, max_distance_sqr, heap);
+        let radius_sqr = if heap.len() == count {
+            heap.peek()
+                .map_or(max_distance_sqr, |farthest| farthest.distance_sqr)
+        } else {
+            max_distance_sqr
+        };
+        if plane_offset * plane_offset <= radius_sqr {
+            self.search(far.0, far.1, point, count, max_distance_sqr, heap);
+        }
+    }
+}
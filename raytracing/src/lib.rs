@@ -1,6 +1,39 @@
+pub mod aabb;
+pub mod animation;
+pub mod bdpt;
+pub mod bsdf;
+pub mod bvh;
+pub mod camera;
 pub mod checkerboard;
+pub mod cone;
+pub mod csg;
+pub mod cuboid;
+pub mod cylinder;
+pub mod debug;
+pub mod demo;
+pub mod denoise;
+pub mod disk;
+pub mod error;
+pub mod filter;
+pub mod framebuffer;
+pub mod gif;
+pub mod kdtree;
+pub mod medium;
+pub mod path_tracer;
+pub mod photon_map;
+pub mod pick;
+pub mod polynomial;
 pub mod primitives;
+pub mod quad;
+pub mod random;
 pub mod render;
+pub mod sampler;
+pub mod sampling;
 pub mod scene;
+pub mod sdf;
 pub mod sphere;
+pub mod stats;
+pub mod stereo;
+pub mod torus;
+pub mod translated;
 pub mod vec;
@@ -0,0 +1,280 @@
+//! Participating media: fog filling the whole scene and bounded homogeneous volumes.
+//!
+//! Media are rendered with single scattering: camera and secondary rays are marched
+//! through the medium, gathering light scattered towards them from the point lights.
+
+use std::cmp::Ordering;
+
+use crate::primitives::{Light, Ray};
+use crate::scene::Renderable;
+use crate::stats;
+use crate::vec::{Float, Vec3, Vec3f};
+
+/// Henyey-Greenstein phase function; `g` is between -1 (back scattering) and 1 (forward).
+pub fn henyey_greenstein(cos_theta: f32, g: f32) -> f32 {
+    let denominator = 1.0 + g * g - 2.0 * g * cos_theta;
+    (1.0 - g * g) / (4.0 * std::f32::consts::PI * denominator * denominator.sqrt())
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum FogDensity {
+    /// Same density everywhere.
+    Homogeneous,
+    /// Density `exp(-falloff * (y - base_height))`, thinning out with height.
+    Height { base_height: f32, falloff: f32 },
+}
+
+/// Fog filling the whole scene.
+#[derive(Copy, Clone, Debug)]
+pub struct Fog {
+    pub density: FogDensity,
+    pub absorption: f32,
+    pub scattering: f32,
+    pub asymmetry: f32,
+}
+
+impl Fog {
+    /// Whether the coefficients are finite and non-negative and the asymmetry is between
+    /// -1 and 1, exclusive.
+    pub fn is_valid(&self) -> bool {
+        let density = match self.density {
+            FogDensity::Homogeneous => true,
+            FogDensity::Height {
+                base_height,
+                falloff,
+            } => base_height.is_finite() && falloff.is_finite(),
+        };
+        density && valid_coefficients(self.absorption, self.scattering, self.asymmetry)
+    }
+
+    fn density_at<T: Float>(&self, point: Vec3<T>) -> f32 {
+        match self.density {
+            FogDensity::Homogeneous => 1.0,
+            FogDensity::Height {
+                base_height,
+                falloff,
+            } => (-falloff * height_above(point, base_height)).exp(),
+        }
+    }
+
+    /// Integral of the density along the ray between `t0` and `t1`.
+    fn integrated_density<T: Float>(&self, ray: Ray<T>, t0: T, t1: T) -> f32 {
+        let (t0, t1): (f32, f32) = (t0.cast(), t1.cast());
+        match self.density {
+            FogDensity::Homogeneous => t1 - t0,
+            FogDensity::Height {
+                base_height,
+                falloff,
+            } => {
+                let start = (-falloff * height_above(ray.origin, base_height)).exp();
+                let direction: f32 = ray.direction[1].cast();
+                let rate = falloff * direction;
+                if rate.abs() < 1e-6 {
+                    start * (t1 - t0)
+                } else {
+                    start * ((-rate * t0).exp() - (-rate * t1).exp()) / rate
+                }
+            }
+        }
+    }
+}
+
+/// Height of `point` above `base_height`, taken in the precision of the point.
+fn height_above<T: Float>(point: Vec3<T>, base_height: f32) -> f32 {
+    (point[1] - base_height.cast()).cast()
+}
+
+/// Homogeneous medium filling a closed solid, e.g. a smoke-filled sphere.
+///
+/// The boundary is only used to find where rays are inside the volume, so it must report
+/// its interior through [`Renderable::spans`].
+pub struct Volume<T: Float = f32> {
+    pub boundary: Box<dyn Renderable<T>>,
+    pub absorption: f32,
+    pub scattering: f32,
+    pub asymmetry: f32,
+}
+
+impl<T: Float> Volume<T> {
+    /// Whether the coefficients are valid for [`Fog`] and the boundary is valid.
+    pub fn is_valid(&self) -> bool {
+        valid_coefficients(self.absorption, self.scattering, self.asymmetry)
+            && self.boundary.validate().is_ok()
+    }
+
+    /// Parts of the ray within `[t0, t1]` lying inside the volume.
+    fn segments(&self, ray: Ray<T>, t0: T, t1: T) -> Vec<(T, T)> {
+        if let Some(bounds) = self.boundary.bounds() {
+            if !bounds.hit(ray, t0, t1) {
+                return Vec::new();
+            }
+        }
+        self.boundary
+            .spans(ray)
+            .into_iter()
+            .map(|span| (span.enter.distance.max(t0), span.exit.distance.min(t1)))
+            .filter(|(start, end)| start < end)
+            .collect()
+    }
+
+    fn extinction(&self) -> f32 {
+        self.absorption + self.scattering
+    }
+}
+
+fn valid_coefficients(absorption: f32, scattering: f32, asymmetry: f32) -> bool {
+    let non_negative = |value: f32| value >= 0.0 && value.is_finite();
+    non_negative(absorption) && non_negative(scattering) && asymmetry.abs() < 1.0
+}
+
+/// All participating media of a scene.
+pub struct Media<T: Float = f32> {
+    pub fog: Option<Fog>,
+    pub volumes: Vec<Volume<T>>,
+    /// Number of marching steps per ray segment inside a medium.
+    pub steps: usize,
+    /// How far rays that hit nothing are marched through the fog.
+    pub max_distance: f32,
+}
+
+impl<T: Float> Default for Media<T> {
+    fn default() -> Self {
+        Self {
+            fog: None,
+            volumes: Vec::new(),
+            steps: 64,
+            max_distance: 100.0,
+        }
+    }
+}
+
+impl<T: Float> Media<T> {
+    pub fn is_empty(&self) -> bool {
+        self.fog.is_none() && self.volumes.is_empty()
+    }
+
+    /// Fraction of light passing along the ray between `t0` and `t1`.
+    pub fn transmittance(&self, ray: Ray<T>, t0: T, t1: T) -> f32 {
+        if self.is_empty() {
+            return 1.0;
+        }
+        let volume_segments: Vec<_> = self
+            .volumes
+            .iter()
+            .map(|volume| volume.segments(ray, t0, t1))
+            .collect();
+        (-self.optical_depth(ray, t0, t1, &volume_segments)).exp()
+    }
+
+    /// Optical depth along the ray between `t0` and `t1`, given the parts of the ray
+    /// inside each volume.
+    fn optical_depth(&self, ray: Ray<T>, t0: T, t1: T, volume_segments: &[Vec<(T, T)>]) -> f32 {
+        let mut optical_depth = 0.0;
+        if let Some(fog) = &self.fog {
+            optical_depth +=
+                (fog.absorption + fog.scattering) * fog.integrated_density(ray, t0, t1);
+        }
+        for (volume, segments) in self.volumes.iter().zip(volume_segments) {
+            for &(start, end) in segments {
+                let overlap = end.min(t1) - start.max(t0);
+                if overlap > T::ZERO {
+                    optical_depth += volume.extinction() * overlap.cast::<f32>();
+                }
+            }
+        }
+        optical_depth
+    }
+
+    /// Attenuates `color` seen at distance `t_end` along the ray and adds the light
+    /// scattered towards the ray origin by the media on the way.
+    pub fn apply(
+        &self,
+        ray: Ray<T>,
+        t_end: T,
+        color: Vec3f,
+        lights: &[Light<T>],
+        occluders: &dyn Renderable<T>,
+    ) -> Vec3f {
+        let t_end = t_end.min(self.max_distance.cast());
+        let volume_segments: Vec<_> = self
+            .volumes
+            .iter()
+            .map(|volume| volume.segments(ray, T::ZERO, t_end))
+            .collect();
+        let segments = if self.fog.is_some() {
+            vec![(T::ZERO, t_end)]
+        } else {
+            merge_segments(volume_segments.concat())
+        };
+
+        // The marching steps go away from the ray origin, so the transmittance back to it
+        // is accumulated from one step to the next.
+        let mut view_transmittance = 1.0;
+        let mut previous_t = T::ZERO;
+        let mut scattered = 0.0;
+        for (start, end) in segments {
+            let step = (end - start) / (self.steps.max(1) as f32).cast();
+            for i in 0..self.steps {
+                let t = start + (i as f32 + 0.5).cast::<T>() * step;
+                let point = ray.origin + ray.direction * t;
+                view_transmittance *=
+                    (-self.optical_depth(ray, previous_t, t, &volume_segments)).exp();
+                previous_t = t;
+
+                // Scattering coefficients and phase asymmetries of the media present at `t`.
+                let mut components = Vec::new();
+                if let Some(fog) = &self.fog {
+                    components.push((fog.scattering * fog.density_at(point), fog.asymmetry));
+                }
+                for (volume, segments) in self.volumes.iter().zip(&volume_segments) {
+                    if segments.iter().any(|&(s, e)| t >= s && t <= e) {
+                        components.push((volume.scattering, volume.asymmetry));
+                    }
+                }
+                if components.is_empty() {
+                    continue;
+                }
+
+                for light in lights {
+                    let to_light = light.position - point;
+                    let light_distance = to_light.norm();
+                    let light_ray = Ray {
+                        origin: point,
+                        direction: to_light * (T::ONE / light_distance),
+                    };
+                    stats::record(|counters| counters.shadow_rays += 1);
+                    if occluders.occluded(light_ray, light_distance) {
+                        continue;
+                    }
+                    // Direction of propagation is from the light towards the ray origin.
+                    let cos_theta: f32 = (-(light_ray.direction * ray.direction)).cast();
+                    let phase_weighted: f32 = components
+                        .iter()
+                        .map(|&(scattering, g)| scattering * henyey_greenstein(cos_theta, g))
+                        .sum();
+                    scattered += view_transmittance
+                        * phase_weighted
+                        * light.intensity
+                        * self.transmittance(light_ray, T::ZERO, light_distance)
+                        * step.cast::<f32>();
+                }
+            }
+        }
+        let transmittance = (-self.optical_depth(ray, T::ZERO, t_end, &volume_segments)).exp();
+        color * transmittance + Vec3f::new(1.0, 1.0, 1.0) * scattered
+    }
+}
+
+/// Union of `segments`, sorted and without overlaps, so that overlapping volumes are
+/// marched once.
+fn merge_segments<T: Float>(mut segments: Vec<(T, T)>) -> Vec<(T, T)> {
+    segments.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+    let mut merged: Vec<(T, T)> = Vec::with_capacity(segments.len());
+    for (start, end) in segments {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
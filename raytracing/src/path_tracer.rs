@@ -0,0 +1,316 @@
+//! Unidirectional path tracer with next event estimation.
+
+use std::f32::consts::PI;
+
+use crate::bsdf::Bsdf;
+use crate::primitives::Ray;
+use crate::sampler::Sampler;
+use crate::sampling::{uniform_sphere, MisHeuristic, UNIFORM_SPHERE_PDF};
+use crate::scene::{Emitter, Primitive, RayIntersection, Renderable, Scene, RAY_EPSILON};
+use crate::stats;
+use crate::vec::{Float, Vec3, Vec3f};
+
+/// Sampler dimensions of the light sampling at one path vertex.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct LightSamples {
+    emitter: f32,
+    point: (f32, f32),
+    pub(crate) environment: (f32, f32),
+}
+
+impl LightSamples {
+    /// Draws the light dimensions of a vertex. They are drawn at every vertex, specular or
+    /// not, so that a given bounce reads the same dimensions in every sample.
+    pub(crate) fn draw(sampler: &mut dyn Sampler) -> Self {
+        Self {
+            emitter: sampler.next_1d(),
+            point: sampler.next_2d(),
+            environment: sampler.next_2d(),
+        }
+    }
+}
+
+/// Draws the lobe and direction dimensions of a BSDF sample.
+pub(crate) fn bsdf_samples(sampler: &mut dyn Sampler) -> [f32; 3] {
+    let lobe = sampler.next_1d();
+    let (u, v) = sampler.next_2d();
+    [lobe, u, v]
+}
+
+/// Estimates radiance along camera rays by following random paths through the scene.
+///
+/// At every diffuse or glossy vertex the path is connected to the point lights, to one
+/// point on a randomly picked emitter and to one direction of the environment, i.e. the
+/// scene background. Emitters and the environment are also found by the BSDF-sampled
+/// continuation of the path; both estimates are combined with multiple importance sampling.
+///
+/// Point lights are scaled by `PI` so that a diffuse surface facing a light is as bright as
+/// in the Whitted integrator.
+pub struct PathTracer<'a, T: Float = f32> {
+    scene: &'a Scene<T>,
+    emitters: Vec<&'a dyn Emitter<T>>,
+    pub heuristic: MisHeuristic,
+}
+
+impl<'a, T: Float> PathTracer<'a, T> {
+    pub fn new(scene: &'a Scene<T>) -> Self {
+        Self {
+            scene,
+            emitters: scene.emitters(),
+            heuristic: MisHeuristic::Power,
+        }
+    }
+
+    pub(crate) fn sampled_emitter(
+        &self,
+        primitive: &dyn Primitive<T>,
+    ) -> Option<&'a dyn Emitter<T>> {
+        self.emitters
+            .iter()
+            .copied()
+            .find(|emitter| emitter.is_primitive(primitive))
+    }
+
+    /// Solid-angle density of picking `emitter` and sampling its point `point` with
+    /// `normal` to light `reference`.
+    fn emitter_pdf(
+        &self,
+        emitter: &dyn Emitter<T>,
+        reference: Vec3<T>,
+        point: Vec3<T>,
+        normal: Vec3<T>,
+    ) -> f32 {
+        emitter.pdf_from(reference, point, normal) / self.emitters.len() as f32
+    }
+
+    pub(crate) fn has_environment(&self) -> bool {
+        let background = self.scene.background_color;
+        background * background > 0.0
+    }
+
+    /// Fraction of light passing along `ray` up to `distance`, possibly infinite.
+    pub(crate) fn transmittance(&self, ray: Ray<T>, distance: T) -> f32 {
+        let media = &self.scene.media;
+        media.transmittance(ray, T::ZERO, distance.min(media.max_distance.cast()))
+    }
+
+    /// Radiance arriving along `ray`, following paths of at most `depth` segments.
+    pub fn radiance(&self, ray: Ray<T>, depth: usize, sampler: &mut dyn Sampler) -> Vec3f {
+        let scene = self.scene;
+        let mut radiance = Vec3f::default();
+        let mut throughput = Vec3f::new(1.0, 1.0, 1.0);
+        let mut ray = ray;
+        // Density of the BSDF sample that produced `ray`, `None` if it could not have been
+        // produced by light sampling.
+        let mut bsdf_pdf: Option<f32> = None;
+        for bounce in 0..depth {
+            stats::record(|counters| {
+                if bounce == 0 {
+                    counters.primary_rays += 1;
+                } else {
+                    counters.secondary_rays += 1;
+                }
+                counters.max_depth = counters.max_depth.max(bounce);
+            });
+            let t_min = if bounce == 0 {
+                T::ZERO
+            } else {
+                RAY_EPSILON.cast()
+            };
+            let closest = scene.objects.ray_intersect(ray, t_min, T::INFINITY);
+
+            if !scene.media.is_empty() {
+                let distance = closest.map_or(T::INFINITY, |hit| hit.distance);
+                let scattered = scene.media.apply(
+                    ray,
+                    distance,
+                    Vec3f::default(),
+                    &scene.lights,
+                    &scene.objects,
+                );
+                radiance = radiance + throughput.component_mul(scattered);
+                throughput = throughput * self.transmittance(ray, distance);
+            }
+
+            let closest = match closest {
+                Some(closest) => closest,
+                None => {
+                    radiance = radiance
+                        + throughput.component_mul(scene.background_color)
+                            * self.environment_weight(bsdf_pdf);
+                    break;
+                }
+            };
+            let RayIntersection {
+                hit,
+                normal,
+                material,
+                ..
+            } = closest.surface(ray);
+            let material = scene.material(material);
+            let weight = match (bsdf_pdf, self.sampled_emitter(closest.primitive)) {
+                (Some(pdf), Some(emitter)) => {
+                    let light_pdf = self.emitter_pdf(emitter, ray.origin, hit, normal);
+                    self.heuristic.weight(pdf, light_pdf)
+                }
+                _ => 1.0,
+            };
+            radiance = radiance + throughput.component_mul(material.emission) * weight;
+
+            let bsdf = Bsdf::new(material, normal.cast(), ray.direction.cast());
+            let light = LightSamples::draw(sampler);
+            if !bsdf.is_specular() {
+                let continued = bounce + 1 < depth;
+                let direct = self.direct_light(&bsdf, hit, light, continued);
+                radiance = radiance + throughput.component_mul(direct);
+            }
+            let sample = match bsdf.sample(bsdf_samples(sampler)) {
+                Some(sample) => sample,
+                None => break,
+            };
+            throughput = throughput.component_mul(sample.weight);
+            bsdf_pdf = if sample.specular {
+                None
+            } else {
+                Some(sample.pdf)
+            };
+            ray = Ray {
+                origin: hit,
+                direction: sample.direction.cast(),
+            };
+        }
+        radiance
+    }
+
+    /// Light reaching `point` directly from the point lights, one sampled emitter and one
+    /// environment direction, scattered by `bsdf`. `continued` tells whether the path goes
+    /// on with a BSDF sample, which finds the emitters and the environment too.
+    fn direct_light(
+        &self,
+        bsdf: &Bsdf,
+        point: Vec3<T>,
+        light: LightSamples,
+        continued: bool,
+    ) -> Vec3f {
+        let mut radiance = self.point_lights(bsdf, point);
+        if !self.emitters.is_empty() {
+            radiance = radiance + self.sample_emitter(bsdf, point, light, continued);
+        }
+        if self.has_environment() {
+            radiance =
+                radiance + self.sample_environment(bsdf, point, light.environment, continued);
+        }
+        radiance
+    }
+
+    /// Light of all point lights reaching `point`, scattered by `bsdf`.
+    pub(crate) fn point_lights(&self, bsdf: &Bsdf, point: Vec3<T>) -> Vec3f {
+        let scene = self.scene;
+        let mut radiance = Vec3f::default();
+        for light in &scene.lights {
+            let to_light = light.position - point;
+            let distance = to_light.norm();
+            let direction = to_light * (T::ONE / distance);
+            let scattered = bsdf.eval(direction.cast());
+            if scattered * scattered == 0.0 {
+                continue;
+            }
+            let shadow_ray = Ray {
+                origin: point,
+                direction,
+            };
+            stats::record(|counters| counters.shadow_rays += 1);
+            if scene.objects.occluded(shadow_ray, distance) {
+                continue;
+            }
+            let intensity = PI * light.intensity * self.transmittance(shadow_ray, distance);
+            radiance = radiance + scattered * intensity;
+        }
+        radiance
+    }
+
+    /// MIS weight of the environment seen by a ray leaving the scene, which was sampled
+    /// from a BSDF with density `bsdf_pdf`, or `None` for camera rays and specular bounces.
+    pub(crate) fn environment_weight(&self, bsdf_pdf: Option<f32>) -> f32 {
+        match bsdf_pdf {
+            Some(pdf) if self.has_environment() => self.heuristic.weight(pdf, UNIFORM_SPHERE_PDF),
+            _ => 1.0,
+        }
+    }
+
+    /// MIS weight of a light sample drawn with density `light_pdf`. The whole contribution
+    /// if the path is not `continued`, since no BSDF sample then finds the same light.
+    fn light_weight(&self, light_pdf: f32, bsdf_pdf: f32, continued: bool) -> f32 {
+        if continued {
+            self.heuristic.weight(light_pdf, bsdf_pdf)
+        } else {
+            1.0
+        }
+    }
+
+    fn sample_emitter(
+        &self,
+        bsdf: &Bsdf,
+        point: Vec3<T>,
+        light: LightSamples,
+        continued: bool,
+    ) -> Vec3f {
+        let count = self.emitters.len();
+        let emitter = self.emitters[((light.emitter * count as f32) as usize).min(count - 1)];
+        let (sample, pdf) = match emitter.sample_from(point, light.point.0, light.point.1) {
+            Some(sample) => sample,
+            None => return Vec3f::default(),
+        };
+        let to_light = sample.point - point;
+        let distance = to_light.norm();
+        let direction = to_light * (T::ONE / distance);
+        let scattered = bsdf.eval(direction.cast());
+        if scattered * scattered == 0.0 {
+            return Vec3f::default();
+        }
+        let shadow_ray = Ray {
+            origin: point,
+            direction,
+        };
+        stats::record(|counters| counters.shadow_rays += 1);
+        if self
+            .scene
+            .objects
+            .occluded(shadow_ray, distance - RAY_EPSILON.cast())
+        {
+            return Vec3f::default();
+        }
+        let light_pdf = pdf / self.emitters.len() as f32;
+        let weight = self.light_weight(light_pdf, bsdf.pdf(direction.cast()), continued)
+            / light_pdf
+            * self.transmittance(shadow_ray, distance);
+        let emission = self.scene.material(emitter.material()).emission;
+        scattered.component_mul(emission) * weight
+    }
+
+    pub(crate) fn sample_environment(
+        &self,
+        bsdf: &Bsdf,
+        point: Vec3<T>,
+        uv: (f32, f32),
+        continued: bool,
+    ) -> Vec3f {
+        let direction = uniform_sphere(uv.0, uv.1);
+        let scattered = bsdf.eval(direction);
+        if scattered * scattered == 0.0 {
+            return Vec3f::default();
+        }
+        let shadow_ray = Ray {
+            origin: point,
+            direction: direction.cast(),
+        };
+        stats::record(|counters| counters.shadow_rays += 1);
+        if self.scene.objects.occluded(shadow_ray, T::INFINITY) {
+            return Vec3f::default();
+        }
+        let weight = self.light_weight(UNIFORM_SPHERE_PDF, bsdf.pdf(direction), continued)
+            / UNIFORM_SPHERE_PDF
+            * self.transmittance(shadow_ray, T::INFINITY);
+        scattered.component_mul(self.scene.background_color) * weight
+    }
+}
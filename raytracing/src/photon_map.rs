@@ -0,0 +1,236 @@
+//! Photon map storing light focused by mirrors and refractive objects, i.e. caustics.
+
+use std::f32::consts::PI;
+
+use crate::bsdf::Bsdf;
+use crate::kdtree::KdTree;
+use crate::primitives::Ray;
+use crate::random::Rng;
+use crate::sampling::{around_axis, uniform_sphere};
+use crate::scene::{RayIntersection, Renderable, Scene, RAY_EPSILON};
+use crate::vec::{Float, Vec3, Vec3f};
+
+#[derive(Debug, Copy, Clone)]
+pub struct PhotonMapParams {
+    /// Number of photons emitted from all lights together, spread evenly over the pairs of
+    /// a light and a bounded object. Every pair gets at least one photon, so more are
+    /// emitted if there are more pairs than photons.
+    pub photons: usize,
+    /// Largest number of specular bounces a photon may take.
+    pub max_bounces: usize,
+    /// Number of nearest photons used for a radiance estimate.
+    pub gather_count: usize,
+    /// Largest distance from the shaded point to a photon used in an estimate.
+    pub max_radius: f32,
+}
+
+impl Default for PhotonMapParams {
+    fn default() -> Self {
+        Self {
+            photons: 200_000,
+            max_bounces: 8,
+            gather_count: 64,
+            max_radius: 0.5,
+        }
+    }
+}
+
+/// Light carried to a surface point after at least one specular bounce.
+#[derive(Copy, Clone, Debug)]
+pub struct Photon {
+    pub power: Vec3f,
+    /// Direction the photon was travelling in when it was stored.
+    pub direction: Vec3f,
+}
+
+/// Cone of directions from a light towards the bounding sphere of an object.
+#[derive(Copy, Clone, Debug)]
+struct Cone {
+    axis: Vec3f,
+    cos_angle: f32,
+}
+
+impl Cone {
+    fn solid_angle(&self) -> f32 {
+        2.0 * PI * (1.0 - self.cos_angle)
+    }
+
+    fn contains(&self, direction: Vec3f) -> bool {
+        direction * self.axis >= self.cos_angle
+    }
+
+    fn sample(&self, rng: &mut Rng) -> Vec3f {
+        if self.cos_angle <= -1.0 {
+            return uniform_sphere(rng.next_f32(), rng.next_f32());
+        }
+        let cos_theta = 1.0 - rng.next_f32() * (1.0 - self.cos_angle);
+        around_axis(self.axis, cos_theta, 2.0 * PI * rng.next_f32())
+    }
+}
+
+/// Caustic photons of a scene, built before rendering.
+///
+/// Photons are only shot towards the bounding spheres of bounded top-level objects, since
+/// only those can start a caustic path. Scene lights do not fall off with distance, so the
+/// power of a photon is scaled by the squared length of its first segment; the caustics are
+/// then as bright as the direct light of the Whitted integrator.
+pub struct PhotonMap<T = f32> {
+    photons: KdTree<Photon, T>,
+    params: PhotonMapParams,
+}
+
+impl<T: Float> PhotonMap<T> {
+    pub fn new(scene: &Scene<T>, params: PhotonMapParams) -> Self {
+        let mut rng = Rng::with_stream(0, 1);
+        let mut photons = Vec::new();
+        let bounds: Vec<_> = scene
+            .objects
+            .iter()
+            .filter_map(|object| object.bounds())
+            .collect();
+        let pairs = (scene.lights.len() * bounds.len()).max(1);
+        // The first `params.photons % pairs` cones get one photon of the remainder.
+        let photons_per_cone = |pair: usize| {
+            (params.photons / pairs + usize::from(pair < params.photons % pairs)).max(1)
+        };
+
+        for (light_index, light) in scene.lights.iter().enumerate() {
+            let first_pair = light_index * bounds.len();
+            let cones: Vec<Cone> = bounds
+                .iter()
+                .map(|bounds| {
+                    let to_center = bounds.centroid() - light.position;
+                    let distance = to_center.norm();
+                    let radius = (bounds.max - bounds.min).norm() * 0.5f32.cast();
+                    if distance <= radius {
+                        Cone {
+                            axis: Vec3f::new(0.0, 1.0, 0.0),
+                            cos_angle: -1.0,
+                        }
+                    } else {
+                        let ratio: f32 = (radius / distance).cast();
+                        Cone {
+                            axis: (to_center * (T::ONE / distance)).cast(),
+                            cos_angle: (1.0 - ratio.powi(2)).sqrt(),
+                        }
+                    }
+                })
+                .collect();
+
+            for (index, cone) in cones.iter().enumerate() {
+                for _ in 0..photons_per_cone(first_pair + index) {
+                    let direction = cone.sample(&mut rng);
+                    // Directions covered by several cones are sampled by each of them.
+                    let density: f32 = cones
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, cone)| cone.contains(direction))
+                        .map(|(k, cone)| {
+                            photons_per_cone(first_pair + k) as f32 / cone.solid_angle()
+                        })
+                        .sum();
+                    let power = PI * light.intensity / density;
+                    let ray = Ray {
+                        origin: light.position,
+                        direction: direction.cast(),
+                    };
+                    trace_photon(scene, ray, power, params, &mut rng, &mut photons);
+                }
+            }
+        }
+
+        Self {
+            photons: KdTree::new(photons),
+            params,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.photons.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.photons.is_empty()
+    }
+
+    /// Caustic irradiance arriving at `point` on the side of the surface `normal` faces.
+    pub fn irradiance(&self, point: Vec3<T>, normal: Vec3<T>) -> Vec3f {
+        let neighbours = self.photons.nearest(
+            point,
+            self.params.gather_count,
+            self.params.max_radius.cast(),
+        );
+        let normal: Vec3f = normal.cast();
+        let radius_sqr = match neighbours.last() {
+            Some(&(distance_sqr, _)) if neighbours.len() == self.params.gather_count => {
+                distance_sqr.cast()
+            }
+            Some(_) => self.params.max_radius * self.params.max_radius,
+            None => return Vec3f::default(),
+        };
+        let power = neighbours
+            .iter()
+            .filter(|(_, photon)| photon.direction * normal < 0.0)
+            .fold(Vec3f::default(), |sum, (_, photon)| sum + photon.power);
+        power * (1.0 / (PI * radius_sqr.max(1e-8)))
+    }
+}
+
+/// Follows a photon through specular bounces, storing it at every diffuse surface it
+/// reaches after at least one of them.
+fn trace_photon<T: Float>(
+    scene: &Scene<T>,
+    ray: Ray<T>,
+    power: f32,
+    params: PhotonMapParams,
+    rng: &mut Rng,
+    photons: &mut Vec<(Vec3<T>, Photon)>,
+) {
+    let mut ray = ray;
+    let mut power = Vec3f::new(power, power, power);
+    let mut first_segment = true;
+    for _ in 0..=params.max_bounces {
+        let closest = match scene
+            .objects
+            .ray_intersect(ray, RAY_EPSILON.cast(), T::INFINITY)
+        {
+            Some(closest) => closest,
+            None => return,
+        };
+        let RayIntersection {
+            hit,
+            normal,
+            material,
+            ..
+        } = closest.surface(ray);
+        if first_segment {
+            power = power * (closest.distance * closest.distance).cast::<f32>();
+        } else if scene.material(material).albedo[0] > 0.0 {
+            photons.push((
+                hit,
+                Photon {
+                    power: power * scene.media.transmittance(ray, T::ZERO, closest.distance),
+                    direction: ray.direction.cast(),
+                },
+            ));
+        }
+
+        let bsdf = Bsdf::new(
+            scene.material(material),
+            normal.cast(),
+            ray.direction.cast(),
+        );
+        match bsdf.sample([rng.next_f32(), rng.next_f32(), rng.next_f32()]) {
+            Some(sample) if sample.specular => {
+                power = power.component_mul(sample.weight)
+                    * scene.media.transmittance(ray, T::ZERO, closest.distance);
+                ray = Ray {
+                    origin: hit,
+                    direction: sample.direction.cast(),
+                };
+                first_segment = false;
+            }
+            _ => return,
+        }
+    }
+}
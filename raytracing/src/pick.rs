@@ -0,0 +1,134 @@
+//! Inspection of what the camera sees through a pixel, for scene tooling.
+
+use crate::primitives::{Material, Ray};
+use crate::render::RenderParams;
+use crate::scene::{secondary_rays, RayIntersection, Scene, RAY_EPSILON};
+use crate::vec::{Float, Vec3f};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RayKind {
+    Camera,
+    Reflected,
+    Refracted,
+}
+
+/// Surface hit by a ray.
+#[derive(Debug, Copy, Clone)]
+pub struct PickedHit<T = f32> {
+    pub intersection: RayIntersection<T>,
+    /// Index in [`Scene::objects`] of the object that was hit.
+    pub object: usize,
+    pub material: Material,
+}
+
+/// Ray traced by the Whitted integrator, with the rays it spawned at its hit.
+#[derive(Debug, Clone)]
+pub struct RayTree<T = f32> {
+    pub kind: RayKind,
+    pub ray: Ray<T>,
+    /// First surface along the ray, `None` if it leaves the scene.
+    pub hit: Option<PickedHit<T>>,
+    /// Light the Whitted integrator finds along the ray, including the contribution of the
+    /// children weighted by the albedo of the material.
+    pub radiance: Vec3f,
+    /// Reflected and refracted rays, empty for misses and at the last level.
+    pub children: Vec<RayTree<T>>,
+}
+
+/// Ray tree of the pixel `(x, y)` of the central view of `params`, traced `params.depth`
+/// rays deep as by the Whitted integrator. `None` outside the image or if the camera sees
+/// nothing through the pixel, e.g. outside the image circle of a fisheye camera.
+pub fn pick<T: Float>(
+    scene: &Scene<T>,
+    params: &RenderParams<T>,
+    x: usize,
+    y: usize,
+) -> Option<RayTree<T>> {
+    if x >= params.width || y >= params.height {
+        return None;
+    }
+    let ray = params
+        .camera()
+        .generate_ray(x as f32 + 0.5, y as f32 + 0.5)?;
+    Some(ray_tree(scene, RayKind::Camera, ray, params.depth, 0))
+}
+
+/// Ray tree of `ray`, whose radiance is shaded from the radiance of its children.
+fn ray_tree<T: Float>(
+    scene: &Scene<T>,
+    kind: RayKind,
+    ray: Ray<T>,
+    depth: usize,
+    level: usize,
+) -> RayTree<T> {
+    if depth == 0 {
+        return RayTree {
+            kind,
+            ray,
+            hit: None,
+            radiance: scene.background_color,
+            children: Vec::new(),
+        };
+    }
+    let t_min = if level == 0 {
+        T::ZERO
+    } else {
+        RAY_EPSILON.cast()
+    };
+    let hit = closest_object(scene, ray, t_min);
+    let mut children = Vec::new();
+    let radiance = match &hit {
+        Some(hit) => {
+            let (reflected, refracted) = secondary_rays(ray, &hit.intersection, &hit.material);
+            // Rays of the last level are not kept, they only see the background.
+            let (reflect_color, refract_color) = if depth > 1 {
+                children.push(ray_tree(
+                    scene,
+                    RayKind::Reflected,
+                    reflected,
+                    depth - 1,
+                    level + 1,
+                ));
+                children.push(ray_tree(
+                    scene,
+                    RayKind::Refracted,
+                    refracted,
+                    depth - 1,
+                    level + 1,
+                ));
+                (children[0].radiance, children[1].radiance)
+            } else {
+                (scene.background_color, scene.background_color)
+            };
+            let color = scene.shade(ray, &hit.intersection, reflect_color, refract_color, None);
+            scene.through_media(ray, hit.intersection.distance, color)
+        }
+        None => scene.through_media(ray, T::INFINITY, scene.background_color),
+    };
+    RayTree {
+        kind,
+        ray,
+        hit,
+        radiance,
+        children,
+    }
+}
+
+/// Closest hit along `ray` and the index of the scene object it belongs to.
+fn closest_object<T: Float>(scene: &Scene<T>, ray: Ray<T>, t_min: T) -> Option<PickedHit<T>> {
+    let mut closest = None;
+    let mut t_max = T::INFINITY;
+    for (index, object) in scene.objects.iter().enumerate() {
+        if let Some(hit) = object.ray_intersect(ray, t_min, t_max) {
+            t_max = hit.distance;
+            closest = Some((index, hit));
+        }
+    }
+    let (object, hit) = closest?;
+    let intersection = hit.surface(ray);
+    Some(PickedHit {
+        intersection,
+        object,
+        material: *scene.material(intersection.material),
+    })
+}
@@ -0,0 +1,106 @@
+//! Real roots of low-degree polynomials, used by the analytic primitives.
+
+use crate::vec::Float;
+
+/// Roots of `a x^2 + b x + c` in ascending order.
+pub fn solve_quadratic<T: Float>(a: T, b: T, c: T) -> Option<(T, T)> {
+    if a == T::ZERO {
+        if b == T::ZERO {
+            return None;
+        }
+        let root = -c / b;
+        return Some((root, root));
+    }
+    let discriminant = b * b - 4.0f32.cast::<T>() * a * c;
+    if discriminant < T::ZERO {
+        return None;
+    }
+    // Avoids cancellation when `b` is close to the square root of the discriminant.
+    let q = -0.5f32.cast::<T>() * (b + b.signum() * discriminant.sqrt());
+    let (x0, x1) = if q == T::ZERO {
+        (T::ZERO, T::ZERO)
+    } else {
+        (q / a, c / q)
+    };
+    Some((x0.min(x1), x0.max(x1)))
+}
+
+/// Largest real root of `x^3 + a x^2 + b x + c`.
+fn largest_cubic_root(a: f64, b: f64, c: f64) -> f64 {
+    let p = b - a * a / 3.0;
+    let q = 2.0 * a * a * a / 27.0 - a * b / 3.0 + c;
+    let discriminant = q * q / 4.0 + p * p * p / 27.0;
+    let t = if discriminant > 0.0 {
+        let sqrt_discriminant = discriminant.sqrt();
+        (-q / 2.0 + sqrt_discriminant).cbrt() + (-q / 2.0 - sqrt_discriminant).cbrt()
+    } else if p == 0.0 {
+        0.0
+    } else {
+        let cos_arg = (3.0 * q / (2.0 * p) * (-3.0 / p).sqrt()).clamp(-1.0, 1.0);
+        2.0 * (-p / 3.0).sqrt() * (cos_arg.acos() / 3.0).cos()
+    };
+    let mut x = t - a / 3.0;
+    for _ in 0..2 {
+        let value = ((x + a) * x + b) * x + c;
+        let derivative = (3.0 * x + 2.0 * a) * x + b;
+        if derivative != 0.0 {
+            x -= value / derivative;
+        }
+    }
+    x
+}
+
+fn push_quadratic_roots(roots: &mut Vec<f64>, b: f64, c: f64) {
+    let discriminant = b * b - 4.0 * c;
+    if discriminant >= 0.0 {
+        let sqrt_discriminant = discriminant.sqrt();
+        roots.push((-b - sqrt_discriminant) / 2.0);
+        roots.push((-b + sqrt_discriminant) / 2.0);
+    }
+}
+
+/// Real roots of `x^4 + a x^3 + b x^2 + c x + d` in ascending order.
+///
+/// Computed in double precision with Ferrari's method and polished by Newton iterations,
+/// since single precision is not enough for thin tori.
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+    // Depressed quartic y^4 + p y^2 + q y + r with x = y - a / 4.
+    let a2 = a * a;
+    let p = b - 3.0 * a2 / 8.0;
+    let q = c - a * b / 2.0 + a2 * a / 8.0;
+    let r = d - a * c / 4.0 + a2 * b / 16.0 - 3.0 * a2 * a2 / 256.0;
+
+    let mut roots = Vec::with_capacity(4);
+    if q.abs() < 1e-12 {
+        let mut squares = Vec::with_capacity(2);
+        push_quadratic_roots(&mut squares, p, r);
+        for square in squares {
+            if square >= 0.0 {
+                roots.push(square.sqrt());
+                roots.push(-square.sqrt());
+            }
+        }
+    } else {
+        let m = largest_cubic_root(p, p * p / 4.0 - r, -q * q / 8.0);
+        if m <= 0.0 {
+            return roots;
+        }
+        let s = (2.0 * m).sqrt();
+        push_quadratic_roots(&mut roots, s, p / 2.0 + m - q / (2.0 * s));
+        push_quadratic_roots(&mut roots, -s, p / 2.0 + m + q / (2.0 * s));
+    }
+
+    for root in &mut roots {
+        let mut x = *root - a / 4.0;
+        for _ in 0..2 {
+            let value = (((x + a) * x + b) * x + c) * x + d;
+            let derivative = ((4.0 * x + 3.0 * a) * x + 2.0 * b) * x + c;
+            if derivative != 0.0 {
+                x -= value / derivative;
+            }
+        }
+        *root = x;
+    }
+    roots.sort_by(|x, y| x.partial_cmp(y).unwrap_or(std::cmp::Ordering::Equal));
+    roots
+}
@@ -1,4 +1,4 @@
-use crate::vec::Vec3f;
+use crate::vec::{Float, Vec3, Vec3f};
 
 #[derive(Copy, Clone, Debug)]
 pub struct Material {
@@ -6,6 +6,9 @@ pub struct Material {
     pub diffuse_color: Vec3f,
     pub spectacular_component: f32,
     pub refractive_index: f32,
+    /// Radiance emitted by the surface. The Whitted integrator only shows it to the rays
+    /// hitting the surface, the path tracer also uses it to light other objects.
+    pub emission: Vec3f,
 }
 
 impl Default for Material {
@@ -15,18 +18,76 @@ impl Default for Material {
             diffuse_color: Vec3f::new(0.0, 0.0, 0.0),
             spectacular_component: 0.0,
             refractive_index: 1.0,
+            emission: Vec3f::new(0.0, 0.0, 0.0),
         }
     }
 }
 
-#[derive(Copy, Clone, Debug, Default)]
-pub struct Ray {
-    pub origin: Vec3f,
-    pub direction: Vec3f,
+impl Material {
+    /// Whether the albedo weights are non-negative and the diffuse, reflected and refracted
+    /// ones add up to at most one. The specular weight only scales the highlights of point
+    /// lights and may exceed one.
+    pub fn conserves_energy(&self) -> bool {
+        let [diffuse, _, reflected, refracted] = self.albedo;
+        self.albedo.iter().all(|&weight| weight >= 0.0)
+            && diffuse + reflected + refracted <= 1.0 + 1e-6
+    }
+
+    /// Whether the colors, emission and specular exponent are finite and non-negative and
+    /// the refractive index is positive and finite.
+    pub fn is_valid(&self) -> bool {
+        let non_negative = |value: f32| value >= 0.0 && value.is_finite();
+        self.diffuse_color
+            .coordinates
+            .iter()
+            .chain(self.emission.coordinates.iter())
+            .all(|&value| non_negative(value))
+            && non_negative(self.spectacular_component)
+            && self.refractive_index > 0.0
+            && self.refractive_index.is_finite()
+    }
+}
+
+/// Index of a material in [`Scene::materials`](crate::scene::Scene::materials).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MaterialId(pub usize);
+
+#[derive(Copy, Clone, Debug)]
+pub struct Ray<T = f32> {
+    pub origin: Vec3<T>,
+    pub direction: Vec3<T>,
+}
+
+impl<T: Float> Default for Ray<T> {
+    fn default() -> Self {
+        Self {
+            origin: Vec3::default(),
+            direction: Vec3::default(),
+        }
+    }
 }
 
-#[derive(Copy, Clone, Debug, Default)]
-pub struct Light {
-    pub position: Vec3f,
+impl<T: Float> Ray<T> {
+    /// The same ray in another precision.
+    pub fn cast<U: Float>(self) -> Ray<U> {
+        Ray {
+            origin: self.origin.cast(),
+            direction: self.direction.cast(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Light<T = f32> {
+    pub position: Vec3<T>,
     pub intensity: f32,
-}
\ No newline at end of file
+}
+
+impl<T: Float> Default for Light<T> {
+    fn default() -> Self {
+        Self {
+            position: Vec3::default(),
+            intensity: 0.0,
+        }
+    }
+}
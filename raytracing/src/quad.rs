@@ -0,0 +1,112 @@
+use crate::aabb::Aabb;
+use crate::error::{check_finite, check_positive, GeometryError};
+use crate::primitives::{MaterialId, Ray};
+use crate::scene::{Emitter, Hit, Primitive, RayIntersection, Renderable, SurfaceSample};
+use crate::stats::{self, PrimitiveKind};
+use crate::vec::{Float, Vec3};
+
+/// Flat two-sided parallelogram spanned by `edge_u` and `edge_v` from `corner`.
+#[derive(Copy, Clone, Debug)]
+pub struct Quad<T = f32> {
+    pub corner: Vec3<T>,
+    pub edge_u: Vec3<T>,
+    pub edge_v: Vec3<T>,
+    pub material: MaterialId,
+}
+
+impl<T: Float> Quad<T> {
+    /// Unnormalized normal, of length equal to the area.
+    fn area_normal(&self) -> Vec3<T> {
+        self.edge_u.cross(self.edge_v)
+    }
+
+    /// Coordinates of `point`, lying in the quad plane, along both edges.
+    fn local_coordinates(&self, point: Vec3<T>) -> (T, T) {
+        let normal = self.area_normal();
+        let scale = T::ONE / (normal * normal);
+        let offset = point - self.corner;
+        (
+            offset.cross(self.edge_v) * normal * scale,
+            self.edge_u.cross(offset) * normal * scale,
+        )
+    }
+}
+
+impl<T: Float> Renderable<T> for Quad<T> {
+    fn ray_intersect(&self, ray: Ray<T>, t_min: T, t_max: T) -> Option<Hit<'_, T>> {
+        stats::count_intersection_test(PrimitiveKind::Quad);
+        let normal = self.area_normal();
+        let distance = ((self.corner - ray.origin) * normal) / (ray.direction * normal);
+        if !(distance >= t_min && distance <= t_max) {
+            return None;
+        }
+        let (u, v) = self.local_coordinates(ray.origin + ray.direction * distance);
+        if !((T::ZERO..=T::ONE).contains(&u) && (T::ZERO..=T::ONE).contains(&v)) {
+            return None;
+        }
+        Some(Hit::new(distance, self))
+    }
+
+    fn bounds(&self) -> Option<Aabb<T>> {
+        let corners = [
+            self.corner + self.edge_u,
+            self.corner + self.edge_v,
+            self.corner + self.edge_u + self.edge_v,
+        ];
+        Some(
+            corners
+                .iter()
+                .fold(Aabb::new(self.corner, self.corner), |bounds, &corner| {
+                    bounds.union(&Aabb::new(corner, corner))
+                }),
+        )
+    }
+
+    fn emitter(&self) -> Option<&dyn Emitter<T>> {
+        Some(self)
+    }
+
+    fn materials(&self) -> Vec<MaterialId> {
+        vec![self.material]
+    }
+
+    fn validate(&self) -> Result<(), GeometryError> {
+        check_finite(&[self.corner, self.edge_u, self.edge_v])?;
+        check_positive("area", self.edge_u.cross(self.edge_v).norm())
+    }
+}
+
+impl<T: Float> Primitive<T> for Quad<T> {
+    fn surface(&self, ray: Ray<T>, distance: T) -> RayIntersection<T> {
+        let hit = ray.origin + ray.direction * distance;
+        let normal = self.area_normal().normalized();
+        RayIntersection {
+            distance,
+            hit,
+            normal: if ray.direction * normal > T::ZERO {
+                normal * -T::ONE
+            } else {
+                normal
+            },
+            uv: self.local_coordinates(hit),
+            material: self.material,
+        }
+    }
+}
+
+impl<T: Float> Emitter<T> for Quad<T> {
+    fn material(&self) -> MaterialId {
+        self.material
+    }
+
+    fn area(&self) -> f32 {
+        self.area_normal().norm().cast()
+    }
+
+    fn sample_surface(&self, u: f32, v: f32) -> SurfaceSample<T> {
+        SurfaceSample {
+            point: self.corner + self.edge_u * u.cast::<T>() + self.edge_v * v.cast::<T>(),
+            normal: self.area_normal().normalized(),
+        }
+    }
+}
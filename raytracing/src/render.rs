@@ -1,55 +1,559 @@
-use std::io::{self, BufWriter, Write};
+use std::f32::consts::PI;
+use std::ops::RangeInclusive;
+use std::time::Instant;
 
+use crate::animation::Animation;
+use crate::bdpt::BidirectionalPathTracer;
+use crate::camera::{Camera, CameraPose, PosedCamera, Projection};
+use crate::debug::DebugView;
+use crate::denoise::{denoise, DenoiseParams};
+use crate::error::{check_finite, RenderError};
+use crate::filter::Filter;
+use crate::framebuffer::{Aov, FrameBuffer};
+use crate::path_tracer::PathTracer;
+use crate::photon_map::{PhotonMap, PhotonMapParams};
 use crate::primitives::Ray;
-use crate::scene::Scene;
-use crate::vec::Vec3f;
+use crate::sampler::{Sampler, SamplerKind};
+use crate::scene::{Renderable, Scene};
+use crate::stats::{self, RenderStats, TileStats};
+use crate::stereo::{Eye, StereoCamera, StereoParams};
+use crate::vec::{Float, Vec3f};
 
+/// Per-pixel adaptive sampling settings.
+///
+/// A pixel stops being sampled once the relative standard error of its luminance
+/// drops below `threshold`, or after `max_samples` samples.
 #[derive(Debug, Copy, Clone)]
-pub struct RenderParams {
+pub struct AdaptiveParams {
+    pub threshold: f32,
+    pub min_samples: usize,
+    pub max_samples: usize,
+}
+
+impl Default for AdaptiveParams {
+    fn default() -> Self {
+        Self {
+            threshold: 0.02,
+            min_samples: 4,
+            max_samples: 64,
+        }
+    }
+}
+
+/// Algorithm estimating the light arriving along camera rays.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Integrator {
+    /// Deterministic recursive ray tracing, lit by point lights only.
+    Whitted,
+    /// Monte Carlo path tracing, see [`PathTracer`].
+    PathTracing,
+    /// Bidirectional path tracing, see [`BidirectionalPathTracer`].
+    Bidirectional,
+}
+
+/// Settings of a render. The camera pose is in the precision `T` of the scene geometry.
+#[derive(Debug, Copy, Clone)]
+pub struct RenderParams<T = f32> {
+    /// Vertical field of view of perspective cameras, and the angle across the image
+    /// circle of fisheye cameras.
     pub field_of_view: f32,
+    pub projection: Projection,
+    pub pose: CameraPose<T>,
     pub width: usize,
     pub height: usize,
     pub depth: usize,
+    pub samples_per_pixel: usize,
+    pub adaptive: Option<AdaptiveParams>,
+    /// Filter splatting camera samples into nearby pixels, `None` to average the samples
+    /// taken inside each pixel.
+    pub filter: Option<Filter>,
+    pub denoise: Option<DenoiseParams>,
+    pub integrator: Integrator,
+    /// Source of the pixel positions and of the random decisions of stochastic integrators.
+    pub sampler: SamplerKind,
+    /// Renders a left and a right view instead of a single one, `None` for mono images.
+    pub stereo: Option<StereoParams>,
+    /// Photon mapping of caustics for the Whitted integrator, `None` to disable.
+    pub caustics: Option<PhotonMapParams>,
+    /// Visualization replacing the integrator and all lighting, `None` for a normal render.
+    pub debug_view: Option<DebugView>,
+}
+
+impl<T: Float> Default for RenderParams<T> {
+    fn default() -> Self {
+        Self {
+            field_of_view: std::f32::consts::PI / 2.0,
+            projection: Projection::Perspective,
+            pose: CameraPose::default(),
+            width: 1024,
+            height: 768,
+            depth: 4,
+            samples_per_pixel: 1,
+            adaptive: None,
+            filter: None,
+            denoise: None,
+            integrator: Integrator::Whitted,
+            sampler: SamplerKind::Independent,
+            stereo: None,
+            caustics: None,
+            debug_view: None,
+        }
+    }
 }
 
-pub fn render(scene: &Scene, params: RenderParams) -> io::Result<()> {
+impl<T: Float> RenderParams<T> {
+    /// Checks the image size, depth and camera, returning the first problem found.
+    ///
+    /// Perspective views need a field of view in `(0, π)`; fisheye images may cover up to
+    /// a full turn across their image circle.
+    pub fn validate(&self) -> Result<(), RenderError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(RenderError::EmptyImage {
+                width: self.width,
+                height: self.height,
+            });
+        }
+        if self.depth == 0 {
+            return Err(RenderError::ZeroDepth);
+        }
+        let fov = self.field_of_view;
+        let fov_valid = match self.projection {
+            Projection::Perspective => fov > 0.0 && fov < PI,
+            Projection::Fisheye => fov > 0.0 && fov <= 2.0 * PI,
+            Projection::Orthographic { .. } | Projection::Equirectangular => true,
+        };
+        if !fov_valid {
+            return Err(RenderError::FieldOfView(fov));
+        }
+        if let Projection::Orthographic { height } = self.projection {
+            if !(height > 0.0 && height.is_finite()) {
+                return Err(RenderError::ViewHeight(height));
+            }
+        }
+        let view = self.pose.target - self.pose.position;
+        if check_finite(&[self.pose.position, self.pose.target]).is_err() || view * view == T::ZERO
+        {
+            return Err(RenderError::InvalidCamera);
+        }
+        Ok(())
+    }
+
+    /// Camera of the configured projection and image size.
+    pub fn camera(&self) -> Box<dyn Camera<T>> {
+        self.view_camera(None)
+    }
+
+    /// Camera of `eye` when rendering in stereo, the central camera otherwise.
+    pub fn view_camera(&self, eye: Option<Eye>) -> Box<dyn Camera<T>> {
+        let camera = self
+            .projection
+            .camera(self.field_of_view, self.width, self.height);
+        let camera = match (eye, self.stereo) {
+            (Some(eye), Some(stereo)) => Box::new(StereoCamera::new(camera, eye, stereo)),
+            _ => camera,
+        };
+        Box::new(PosedCamera::new(camera, self.pose))
+    }
+}
+
+/// Surface seen by the camera `ray`. Not counted in the statistics, being made in addition
+/// to the rays the integrators trace.
+fn primary_aov<T: Float>(scene: &Scene<T>, ray: Ray<T>) -> Aov {
+    let closest = stats::unrecorded(|| scene.objects.ray_intersect(ray, T::ZERO, T::INFINITY));
+    match closest {
+        Some(closest) => {
+            let intersection = closest.surface(ray);
+            Aov {
+                normal: intersection.normal.cast(),
+                albedo: scene.material(intersection.material).diffuse_color,
+                depth: intersection.distance.cast(),
+            }
+        }
+        None => Aov::default(),
+    }
+}
+
+fn luminance(color: Vec3f) -> f32 {
+    0.2126 * color[0] + 0.7152 * color[1] + 0.0722 * color[2]
+}
+
+/// Running mean and variance of the samples taken for one pixel.
+#[derive(Copy, Clone, Debug, Default)]
+struct PixelEstimate {
+    count: usize,
+    mean: Vec3f,
+    luminance_mean: f32,
+    luminance_m2: f32,
+}
+
+impl PixelEstimate {
+    fn add(&mut self, sample: Vec3f) {
+        self.count += 1;
+        let weight = 1.0 / self.count as f32;
+        self.mean = self.mean + (sample - self.mean) * weight;
+        let lum = luminance(sample);
+        let delta = lum - self.luminance_mean;
+        self.luminance_mean += delta * weight;
+        self.luminance_m2 += delta * (lum - self.luminance_mean);
+    }
+
+    fn relative_error(&self) -> f32 {
+        if self.count < 2 {
+            return f32::INFINITY;
+        }
+        let variance = self.luminance_m2 / (self.count - 1) as f32;
+        (variance / self.count as f32).sqrt() / self.luminance_mean.abs().max(1e-2)
+    }
+}
+
+/// Camera samples weighted by a reconstruction filter and splatted into every pixel whose
+/// center lies within its radius.
+struct FilteredFilm {
+    filter: Filter,
+    width: usize,
+    height: usize,
+    weighted_sum: Vec<Vec3f>,
+    weight_sum: Vec<f32>,
+}
+
+impl FilteredFilm {
+    fn new(filter: Filter, width: usize, height: usize) -> Self {
+        Self {
+            filter,
+            width,
+            height,
+            weighted_sum: vec![Vec3f::default(); width * height],
+            weight_sum: vec![0.0; width * height],
+        }
+    }
+
+    /// Adds `color` seen at the point `(x, y)` of the image plane, measured in pixels.
+    fn add_sample(&mut self, x: f32, y: f32, color: Vec3f) {
+        let radius = self.filter.radius();
+        // Range of pixels whose center `index + 0.5` is within the radius along one axis.
+        let range = |center: f32, size: usize| {
+            let first = (center - 0.5 - radius).ceil().max(0.0) as usize;
+            let last = ((center - 0.5 + radius).floor() as isize).min(size as isize - 1);
+            first..(last + 1).max(0) as usize
+        };
+        for j in range(y, self.height) {
+            for i in range(x, self.width) {
+                let weight = self.filter.evaluate(i as f32 + 0.5 - x, j as f32 + 0.5 - y);
+                if weight != 0.0 {
+                    let index = j * self.width + i;
+                    self.weighted_sum[index] = self.weighted_sum[index] + color * weight;
+                    self.weight_sum[index] += weight;
+                }
+            }
+        }
+    }
+
+    /// Filtered color of a pixel, `None` if the weights of the samples around it cancel out.
+    fn resolve(&self, index: usize) -> Option<Vec3f> {
+        let weight = self.weight_sum[index];
+        if weight.abs() < 1e-6 {
+            return None;
+        }
+        Some(self.weighted_sum[index] * (1.0 / weight))
+    }
+}
+
+/// Integrators of a frame, with the data they precompute for the scene.
+struct Integrators<'a, T: Float> {
+    scene: &'a Scene<T>,
+    camera: Box<dyn Camera<T>>,
+    path_tracer: PathTracer<'a, T>,
+    bidirectional: BidirectionalPathTracer<'a, T>,
+    photons: Option<&'a PhotonMap<T>>,
+}
+
+impl<'a, T: Float> Integrators<'a, T> {
+    /// Integrators of the view of `eye`, or of the central view for `None`.
+    fn new(
+        scene: &'a Scene<T>,
+        params: &RenderParams<T>,
+        eye: Option<Eye>,
+        photons: Option<&'a PhotonMap<T>>,
+    ) -> Self {
+        let camera = params.view_camera(eye);
+        let bidirectional = BidirectionalPathTracer::new(
+            scene,
+            params.view_camera(eye),
+            params.width,
+            params.height,
+        );
+        Self {
+            scene,
+            camera,
+            path_tracer: PathTracer::new(scene),
+            bidirectional,
+            photons,
+        }
+    }
+
+    /// Light along the camera `ray`. Light found for other pixels is added to `splats`.
+    fn radiance(
+        &self,
+        params: &RenderParams<T>,
+        ray: Ray<T>,
+        sampler: &mut dyn Sampler,
+        splats: &mut Vec<(usize, Vec3f)>,
+    ) -> Vec3f {
+        if let Some(view) = params.debug_view {
+            return view.shade(self.scene, ray, params.depth);
+        }
+        match (params.integrator, self.photons) {
+            (Integrator::Whitted, None) => self.scene.cast_ray(ray, params.depth),
+            (Integrator::Whitted, Some(photons)) => {
+                self.scene
+                    .cast_ray_with_caustics(ray, params.depth, photons)
+            }
+            (Integrator::PathTracing, _) => self.path_tracer.radiance(ray, params.depth, sampler),
+            (Integrator::Bidirectional, _) => {
+                self.bidirectional
+                    .radiance(ray, params.depth, sampler, splats)
+            }
+        }
+    }
+
+    /// Light seen at the point `(x, y)` of the image, black where the camera sees nothing.
+    fn radiance_at(
+        &self,
+        params: &RenderParams<T>,
+        (x, y): (f32, f32),
+        sampler: &mut dyn Sampler,
+        splats: &mut Vec<(usize, Vec3f)>,
+    ) -> Vec3f {
+        match self.camera.generate_ray(x, y) {
+            Some(ray) => self.radiance(params, ray, sampler, splats),
+            None => Vec3f::default(),
+        }
+    }
+}
+
+fn render_pixel<T: Float>(
+    integrators: &Integrators<T>,
+    params: &RenderParams<T>,
+    i: usize,
+    j: usize,
+    splats: &mut Vec<(usize, Vec3f)>,
+    mut film: Option<&mut FilteredFilm>,
+) -> (Vec3f, usize) {
+    let (min_samples, max_samples, threshold) = match params.adaptive {
+        Some(adaptive) => (
+            adaptive.min_samples.max(2),
+            adaptive.max_samples.max(adaptive.min_samples.max(2)),
+            adaptive.threshold,
+        ),
+        None => (params.samples_per_pixel, params.samples_per_pixel, 0.0),
+    };
+    let mut sampler = params.sampler.create((i, j), params.width, max_samples);
+    if max_samples <= 1 {
+        sampler.start_sample(0);
+        let (x, y) = (i as f32 + 0.5, j as f32 + 0.5);
+        let color = integrators.radiance_at(params, (x, y), &mut *sampler, splats);
+        if let Some(film) = film {
+            film.add_sample(x, y, color);
+        }
+        return (color, 1);
+    }
+
+    let mut estimate = PixelEstimate::default();
+    while estimate.count < max_samples {
+        if estimate.count >= min_samples && estimate.relative_error() < threshold {
+            break;
+        }
+        sampler.start_sample(estimate.count);
+        let (dx, dy) = sampler.next_2d();
+        let (x, y) = (i as f32 + dx, j as f32 + dy);
+        let color = integrators.radiance_at(params, (x, y), &mut *sampler, splats);
+        if let Some(film) = film.as_deref_mut() {
+            film.add_sample(x, y, color);
+        }
+        estimate.add(color);
+    }
+    (estimate.mean, estimate.count)
+}
+
+const TILE_SIZE: usize = 32;
+
+fn render_tile<T: Float>(
+    integrators: &Integrators<T>,
+    params: &RenderParams<T>,
+    frame: &mut FrameBuffer,
+    splats: &mut Vec<(usize, Vec3f)>,
+    mut film: Option<&mut FilteredFilm>,
+    x0: usize,
+    y0: usize,
+) {
+    let width = params.width;
+    for j in y0..(y0 + TILE_SIZE).min(params.height) {
+        for i in x0..(x0 + TILE_SIZE).min(width) {
+            let (color, samples) =
+                render_pixel(integrators, params, i, j, splats, film.as_deref_mut());
+            frame.color[i + j * width] = color;
+            frame.samples[i + j * width] = samples;
+            let aov = match integrators
+                .camera
+                .generate_ray(i as f32 + 0.5, j as f32 + 0.5)
+            {
+                Some(ray) => primary_aov(integrators.scene, ray),
+                None => Aov::default(),
+            };
+            frame.set_aov(i + j * width, aov);
+        }
+    }
+}
+
+/// Renders the view of `integrators` into a frame buffer. Statistics of its tiles are
+/// recorded at `offset` in the final image.
+fn render_view<T: Float>(
+    integrators: &Integrators<T>,
+    params: &RenderParams<T>,
+    offset: (usize, usize),
+    render_stats: &mut RenderStats,
+) -> FrameBuffer {
     let width = params.width;
     let height = params.height;
-    let fov = params.field_of_view;
-
-    let mut frame_buffer = vec![Vec3f::default(); width * height];
-    for j in 0..height {
-        for i in 0..width {
-            let x =
-                (2.0 * (i as f32 + 0.5) / width as f32 - 1.0) * (fov / 2.0).tan() * width as f32
-                    / height as f32;
-            let y = -(2.0 * (j as f32 + 0.5) / height as f32 - 1.0) * (fov / 2.0).tan();
-            let mut direction = Vec3f::new(x, y, -1.0);
-            direction.normalize();
-            frame_buffer[i + j * width] = scene.cast_ray(
-                Ray {
-                    origin: Vec3f::new(0.0, 0.0, 0.0),
-                    direction,
-                },
-                params.depth,
+    let mut frame = FrameBuffer::new(width, height);
+    let mut splats = Vec::new();
+    let mut film = params
+        .filter
+        .map(|filter| FilteredFilm::new(filter, width, height));
+    for y in (0..height).step_by(TILE_SIZE) {
+        for x in (0..width).step_by(TILE_SIZE) {
+            let tile_start = Instant::now();
+            render_tile(
+                integrators,
+                params,
+                &mut frame,
+                &mut splats,
+                film.as_mut(),
+                x,
+                y,
             );
+            let counters = stats::take_counters();
+            render_stats.tiles.push(TileStats {
+                x: x + offset.0,
+                y: y + offset.1,
+                width: TILE_SIZE.min(width - x),
+                height: TILE_SIZE.min(height - y),
+                time: tile_start.elapsed(),
+                rays: counters.total_rays(),
+            });
+            render_stats.counters.merge(&counters);
         }
     }
 
-    let mut raw_buffer = Vec::with_capacity(width * height * 3);
-    for mut vector in frame_buffer {
-        let max_coordinate = vector[0].max(vector[1]).max(vector[2]);
-        if max_coordinate > 1.0 {
-            vector = vector * (1.0 / max_coordinate);
+    if let Some(film) = &film {
+        for (index, color) in frame.color.iter_mut().enumerate() {
+            if let Some(filtered) = film.resolve(index) {
+                *color = filtered;
+            }
         }
-        for i in 0..3 {
-            raw_buffer.push((255.0 * vector[i].clamp(0.0, 1.0)) as u8);
+    }
+    // Every camera sample contributed one estimate of the whole image through its splats.
+    if !splats.is_empty() {
+        let scale = 1.0 / frame.samples.iter().sum::<usize>() as f32;
+        for (pixel, splat) in splats {
+            frame.color[pixel] = frame.color[pixel] + splat * scale;
         }
     }
+    if let Some(denoise_params) = params.denoise {
+        denoise(&mut frame, denoise_params);
+    }
+    frame
+}
+
+/// Renders the scene into a frame buffer, collecting ray and timing statistics.
+///
+/// Stereo renders trace both views over the same scene and photon map, then combine them
+/// according to the layout.
+pub fn render_frame_with_stats<T: Float>(
+    scene: &Scene<T>,
+    params: RenderParams<T>,
+) -> (FrameBuffer, RenderStats) {
+    let start = Instant::now();
+    stats::take_counters();
 
-    let file = std::fs::File::create("./out.ppm")?;
-    let mut writer = BufWriter::new(file);
-    writeln!(&mut writer, "P6\n{} {}\n255", width, height)?;
-    writer.write_all(&raw_buffer)?;
-    Ok(())
+    let mut render_stats = RenderStats::default();
+    // Work done before the first tile, e.g. tracing photons, is not part of any tile.
+    let photons = match (params.integrator, params.caustics, params.debug_view) {
+        (Integrator::Whitted, Some(caustics), None) => Some(PhotonMap::new(scene, caustics)),
+        _ => None,
+    };
+    render_stats.counters.merge(&stats::take_counters());
+    let frame = match params.stereo {
+        None => {
+            let integrators = Integrators::new(scene, &params, None, photons.as_ref());
+            render_view(&integrators, &params, (0, 0), &mut render_stats)
+        }
+        Some(stereo) => {
+            let left = Integrators::new(scene, &params, Some(Eye::Left), photons.as_ref());
+            let left = render_view(&left, &params, (0, 0), &mut render_stats);
+            let right = Integrators::new(scene, &params, Some(Eye::Right), photons.as_ref());
+            let offset = stereo.layout.right_offset(params.width, params.height);
+            let right = render_view(&right, &params, offset, &mut render_stats);
+            stereo.layout.combine(&left, &right)
+        }
+    };
+    render_stats.total_time = start.elapsed();
+    (frame, render_stats)
+}
+
+pub fn render_frame<T: Float>(scene: &Scene<T>, params: RenderParams<T>) -> FrameBuffer {
+    render_frame_with_stats(scene, params).0
+}
+
+/// Renders the scene to `out.ppm` after checking it and the parameters.
+pub fn render<T: Float>(
+    scene: &Scene<T>,
+    params: RenderParams<T>,
+) -> Result<RenderStats, RenderError> {
+    scene.validate()?;
+    params.validate()?;
+    let (frame, render_stats) = render_frame_with_stats(scene, params);
+    frame.save_ppm("./out.ppm")?;
+    Ok(render_stats)
+}
+
+/// Renders frame `frame_index` of `animation`, checking the animated scene and parameters
+/// first.
+///
+/// The scene is rebuilt by `build_scene` before the animation is applied, so that animated
+/// objects are placed relative to their initial position.
+pub fn render_animation_frame<F: Fn() -> Scene>(
+    build_scene: F,
+    animation: &Animation,
+    params: RenderParams,
+    frame_index: usize,
+) -> Result<(FrameBuffer, RenderStats), RenderError> {
+    let mut scene = build_scene();
+    let mut frame_params = params;
+    animation.apply(
+        animation.frame_time(frame_index),
+        &mut scene,
+        &mut frame_params,
+    )?;
+    scene.validate()?;
+    frame_params.validate()?;
+    Ok(render_frame_with_stats(&scene, frame_params))
+}
+
+/// Renders frames `frames` of `animation` to the PNG files `{prefix}_0001.png` and so on.
+pub fn render_sequence<F: Fn() -> Scene>(
+    build_scene: F,
+    animation: &Animation,
+    params: RenderParams,
+    frames: RangeInclusive<usize>,
+    prefix: &str,
+) -> Result<Vec<RenderStats>, RenderError> {
+    let mut sequence_stats = Vec::new();
+    for frame_index in frames {
+        let (frame, render_stats) =
+            render_animation_frame(&build_scene, animation, params, frame_index)?;
+        frame.save_png(format!("{}_{:04}.png", prefix, frame_index))?;
+        sequence_stats.push(render_stats);
+    }
+    Ok(sequence_stats)
 }
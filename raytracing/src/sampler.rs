@@ -0,0 +1,514 @@
+//! Sample generators feeding the stochastic integrators.
+//!
+//! Every sample of a pixel draws its dimensions in the same order: the 2D position in the
+//! pixel first, then for each path vertex the dimensions of light sampling (a 1D emitter
+//! pick followed by a 2D point on it) and of BSDF sampling (a 1D lobe pick followed by a 2D
+//! direction). Low-discrepancy samplers therefore stratify the same decision with the same
+//! dimension in every sample of a pixel.
+
+use std::sync::OnceLock;
+
+use crate::random::Rng;
+
+/// Source of the random numbers of one pixel.
+pub trait Sampler {
+    /// Starts the sample `index` of the pixel, going back to its first dimension.
+    fn start_sample(&mut self, index: usize);
+
+    /// Returns the next dimension of the current sample, in `[0, 1)`.
+    fn next_1d(&mut self) -> f32;
+
+    /// Returns the next two dimensions of the current sample, in `[0, 1)`.
+    fn next_2d(&mut self) -> (f32, f32);
+}
+
+/// Kind of sampler used by the stochastic integrators.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SamplerKind {
+    /// Uncorrelated pseudo-random numbers.
+    Independent,
+    /// Jittered strata, shuffled independently for each dimension.
+    Stratified,
+    /// Halton sequence with a random rotation per pixel.
+    Halton,
+    /// Sobol sequence with hash-based Owen scrambling per pixel.
+    Sobol,
+    /// Sobol points shifted by a blue-noise texture, so that the error is blue noise across
+    /// pixels.
+    BlueNoise,
+}
+
+impl SamplerKind {
+    /// Creates the sampler of `pixel`, which takes at most `samples` samples.
+    pub fn create(self, pixel: (usize, usize), width: usize, samples: usize) -> Box<dyn Sampler> {
+        let index = pixel.1 * width + pixel.0;
+        match self {
+            SamplerKind::Independent => Box::new(IndependentSampler::new(index as u64)),
+            SamplerKind::Stratified => Box::new(StratifiedSampler::new(index as u32, samples)),
+            SamplerKind::Halton => Box::new(HaltonSampler::new(index as u32)),
+            SamplerKind::Sobol => Box::new(SobolSampler::new(index as u32)),
+            SamplerKind::BlueNoise => Box::new(BlueNoiseSampler::new(pixel)),
+        }
+    }
+}
+
+/// Converts 32 random bits to a number in `[0, 1)`.
+fn to_unit(bits: u32) -> f32 {
+    (bits >> 8) as f32 * (1.0 / (1u32 << 24) as f32)
+}
+
+/// Integer hash with good avalanche (lowbias32 by Chris Wellons).
+fn hash(mut x: u32) -> u32 {
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7feb_352d);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846c_a68b);
+    x ^= x >> 16;
+    x
+}
+
+fn hash_combine(seed: u32, value: u32) -> u32 {
+    hash(seed ^ value.wrapping_mul(0x9e37_79b9))
+}
+
+/// Pseudo-random permutation of `[0, count)` selected by `seed` (Kensler, "Correlated
+/// Multi-Jittered Sampling").
+fn permute(mut index: u32, count: u32, seed: u32) -> u32 {
+    if count <= 1 {
+        return 0;
+    }
+    let mut mask = count - 1;
+    mask |= mask >> 1;
+    mask |= mask >> 2;
+    mask |= mask >> 4;
+    mask |= mask >> 8;
+    mask |= mask >> 16;
+    // Cycle walking: permute within the next power of two until the index falls in range.
+    loop {
+        index ^= seed;
+        index = index.wrapping_mul(0xe170_893d);
+        index ^= seed >> 16;
+        index ^= (index & mask) >> 4;
+        index ^= seed >> 8;
+        index = index.wrapping_mul(0x0929_eb3f);
+        index ^= seed >> 23;
+        index ^= (index & mask) >> 1;
+        index = index.wrapping_mul(1 | seed >> 27);
+        index = index.wrapping_mul(0x6935_fa69);
+        index ^= (index & mask) >> 11;
+        index = index.wrapping_mul(0x74dc_b303);
+        index ^= (index & mask) >> 2;
+        index = index.wrapping_mul(0x9e50_1cc3);
+        index ^= (index & mask) >> 2;
+        index = index.wrapping_mul(0xc860_a3df);
+        index &= mask;
+        index ^= index >> 5;
+        if index < count {
+            return index.wrapping_add(seed) % count;
+        }
+    }
+}
+
+/// Pseudo-random numbers from a PCG stream seeded with the pixel index.
+pub struct IndependentSampler {
+    rng: Rng,
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_sample(&mut self, _index: usize) {}
+
+    fn next_1d(&mut self) -> f32 {
+        self.rng.next_f32()
+    }
+
+    fn next_2d(&mut self) -> (f32, f32) {
+        let u = self.rng.next_f32();
+        (u, self.rng.next_f32())
+    }
+}
+
+/// Jittered stratification of each dimension into one stratum per sample.
+///
+/// 1D dimensions are split into `samples` intervals and 2D dimensions into a grid with at
+/// least `samples` cells. Each dimension visits its strata in its own random order, which
+/// decorrelates the dimensions of a sample.
+pub struct StratifiedSampler {
+    seed: u32,
+    samples: u32,
+    grid: (u32, u32),
+    index: u32,
+    dimension: u32,
+}
+
+impl StratifiedSampler {
+    pub fn new(seed: u32, samples: usize) -> Self {
+        let samples = samples.max(1) as u32;
+        let columns = (samples as f32).sqrt().ceil() as u32;
+        let rows = samples.div_ceil(columns);
+        Self {
+            seed: hash(seed),
+            samples,
+            grid: (columns, rows),
+            index: 0,
+            dimension: 0,
+        }
+    }
+
+    /// Seed of the next dimension, also used for its jitter.
+    fn next_seed(&mut self) -> u32 {
+        self.dimension += 1;
+        hash_combine(self.seed, self.dimension)
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_sample(&mut self, index: usize) {
+        self.index = index as u32;
+        self.dimension = 0;
+    }
+
+    fn next_1d(&mut self) -> f32 {
+        let seed = self.next_seed();
+        let stratum = permute(self.index % self.samples, self.samples, seed);
+        let jitter = to_unit(hash_combine(seed, self.index));
+        ((stratum as f32 + jitter) / self.samples as f32).min(1.0 - f32::EPSILON)
+    }
+
+    fn next_2d(&mut self) -> (f32, f32) {
+        let seed = self.next_seed();
+        let (columns, rows) = self.grid;
+        let cell = permute(self.index % (columns * rows), columns * rows, seed);
+        let jitter_x = to_unit(hash_combine(seed, 2 * self.index));
+        let jitter_y = to_unit(hash_combine(seed, 2 * self.index + 1));
+        (
+            (((cell % columns) as f32 + jitter_x) / columns as f32).min(1.0 - f32::EPSILON),
+            (((cell / columns) as f32 + jitter_y) / rows as f32).min(1.0 - f32::EPSILON),
+        )
+    }
+}
+
+const PRIMES: [u32; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+/// Radical inverse of `index` in `base`: its digits mirrored around the decimal point.
+fn radical_inverse(mut index: u32, base: u32) -> f32 {
+    let inverse_base = 1.0 / base as f64;
+    let mut scale = inverse_base;
+    let mut result = 0.0;
+    while index > 0 {
+        result += (index % base) as f64 * scale;
+        index /= base;
+        scale *= inverse_base;
+    }
+    (result as f32).min(1.0 - f32::EPSILON)
+}
+
+/// Halton sequence, using the `d`-th prime as the base of dimension `d`.
+///
+/// Each pixel rotates the sequence by its own random offsets (Cranley-Patterson rotation)
+/// so that neighbouring pixels do not repeat the same pattern. Dimensions past the prime
+/// table fall back to pseudo-random numbers.
+pub struct HaltonSampler {
+    seed: u32,
+    index: u32,
+    dimension: u32,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            seed: hash(seed),
+            index: 0,
+            dimension: 0,
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_sample(&mut self, index: usize) {
+        self.index = index as u32;
+        self.dimension = 0;
+    }
+
+    fn next_1d(&mut self) -> f32 {
+        let dimension = self.dimension;
+        self.dimension += 1;
+        let offset = to_unit(hash_combine(self.seed, dimension));
+        let value = match PRIMES.get(dimension as usize) {
+            Some(&base) => radical_inverse(self.index, base),
+            None => to_unit(hash_combine(offset.to_bits(), self.index)),
+        };
+        rotate(value, offset)
+    }
+
+    fn next_2d(&mut self) -> (f32, f32) {
+        let u = self.next_1d();
+        (u, self.next_1d())
+    }
+}
+
+/// First two dimensions of the Sobol sequence, as 32-bit fixed point numbers.
+fn sobol_2d(index: u32) -> (u32, u32) {
+    let mut direction = 1u32 << 31;
+    let mut y = 0;
+    let mut bits = index;
+    while bits > 0 {
+        if bits & 1 == 1 {
+            y ^= direction;
+        }
+        bits >>= 1;
+        direction ^= direction >> 1;
+    }
+    (index.reverse_bits(), y)
+}
+
+/// Owen scrambling of the bits of `x`: each bit is flipped depending on the bits above it
+/// (Burley, "Practical Hash-based Owen Scrambling").
+fn owen_scramble(x: u32, seed: u32) -> u32 {
+    let mut x = x.reverse_bits();
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50_b47c);
+    x ^= x.wrapping_mul(0xb82f_1e52);
+    x ^= x.wrapping_mul(0xc7af_e638);
+    x ^= x.wrapping_mul(0x8d22_f6e6);
+    x.reverse_bits()
+}
+
+/// Point `index` of the 2D Sobol sequence, with its order shuffled and its coordinates
+/// Owen-scrambled according to `seed`.
+fn scrambled_sobol_2d(index: u32, seed: u32) -> (u32, u32) {
+    let (x, y) = sobol_2d(owen_scramble(index, seed));
+    (
+        owen_scramble(x, hash_combine(seed, 1)),
+        owen_scramble(y, hash_combine(seed, 2)),
+    )
+}
+
+/// Owen-scrambled Sobol sequence.
+///
+/// Every 1D or 2D draw uses the first dimensions of the sequence with its own scrambling
+/// and its own shuffled sample order, following Burley. The draws stay stratified
+/// individually while being decorrelated from each other.
+pub struct SobolSampler {
+    seed: u32,
+    index: u32,
+    dimension: u32,
+}
+
+impl SobolSampler {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            seed: hash(seed),
+            index: 0,
+            dimension: 0,
+        }
+    }
+
+    /// Scrambled point of the current sample for the next draw.
+    fn next_point(&mut self) -> (u32, u32) {
+        self.dimension += 1;
+        scrambled_sobol_2d(self.index, hash_combine(self.seed, self.dimension))
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_sample(&mut self, index: usize) {
+        self.index = index as u32;
+        self.dimension = 0;
+    }
+
+    fn next_1d(&mut self) -> f32 {
+        to_unit(self.next_point().0)
+    }
+
+    fn next_2d(&mut self) -> (f32, f32) {
+        let (x, y) = self.next_point();
+        (to_unit(x), to_unit(y))
+    }
+}
+
+/// Side of the tiled blue-noise texture.
+pub const BLUE_NOISE_SIZE: usize = 64;
+
+/// Blue-noise texture of [`BLUE_NOISE_SIZE`]² ranks, each appearing once, computed with the
+/// void-and-cluster method (Ulichney) on first use.
+pub fn blue_noise_ranks() -> &'static [u32] {
+    static RANKS: OnceLock<Vec<u32>> = OnceLock::new();
+    RANKS.get_or_init(|| void_and_cluster(BLUE_NOISE_SIZE, 1.5))
+}
+
+/// Energy of every cell of a toroidal binary pattern, as a sum of Gaussians centred on its
+/// points.
+struct PatternEnergy {
+    size: usize,
+    kernel: Vec<f32>,
+    points: Vec<bool>,
+    energy: Vec<f32>,
+}
+
+impl PatternEnergy {
+    fn new(size: usize, sigma: f32) -> Self {
+        let mut kernel = vec![0.0; size * size];
+        for y in 0..size {
+            for x in 0..size {
+                let dx = x.min(size - x) as f32;
+                let dy = y.min(size - y) as f32;
+                kernel[y * size + x] = (-(dx * dx + dy * dy) / (2.0 * sigma * sigma)).exp();
+            }
+        }
+        Self {
+            size,
+            kernel,
+            points: vec![false; size * size],
+            energy: vec![0.0; size * size],
+        }
+    }
+
+    fn toggle(&mut self, cell: usize) {
+        let size = self.size;
+        self.points[cell] = !self.points[cell];
+        let sign = if self.points[cell] { 1.0 } else { -1.0 };
+        let (cx, cy) = (cell % size, cell / size);
+        for y in 0..size {
+            let ky = (y + size - cy) % size;
+            for x in 0..size {
+                let kx = (x + size - cx) % size;
+                self.energy[y * size + x] += sign * self.kernel[ky * size + kx];
+            }
+        }
+    }
+
+    /// Point with the most energy around it.
+    fn tightest_cluster(&self) -> usize {
+        (0..self.points.len())
+            .filter(|&cell| self.points[cell])
+            .max_by(|&a, &b| self.energy[a].partial_cmp(&self.energy[b]).unwrap())
+            .unwrap()
+    }
+
+    /// Empty cell with the least energy around it.
+    fn largest_void(&self) -> usize {
+        (0..self.points.len())
+            .filter(|&cell| !self.points[cell])
+            .min_by(|&a, &b| self.energy[a].partial_cmp(&self.energy[b]).unwrap())
+            .unwrap()
+    }
+}
+
+fn void_and_cluster(size: usize, sigma: f32) -> Vec<u32> {
+    let count = size * size;
+    let mut pattern = PatternEnergy::new(size, sigma);
+    let mut rng = Rng::new(0);
+    let initial = count / 10;
+    let mut placed = 0;
+    while placed < initial {
+        let cell = (rng.next_u32() as usize) % count;
+        if !pattern.points[cell] {
+            pattern.toggle(cell);
+            placed += 1;
+        }
+    }
+    // Spread the initial points by moving the tightest cluster into the largest void.
+    loop {
+        let cluster = pattern.tightest_cluster();
+        pattern.toggle(cluster);
+        let void = pattern.largest_void();
+        pattern.toggle(void);
+        if void == cluster {
+            break;
+        }
+    }
+
+    let mut ranks = vec![0; count];
+    let initial_points = pattern.points.clone();
+    let initial_energy = pattern.energy.clone();
+    for rank in (0..initial).rev() {
+        let cluster = pattern.tightest_cluster();
+        pattern.toggle(cluster);
+        ranks[cluster] = rank as u32;
+    }
+    pattern.points = initial_points;
+    pattern.energy = initial_energy;
+    for rank in initial..count {
+        let void = pattern.largest_void();
+        pattern.toggle(void);
+        ranks[void] = rank as u32;
+    }
+    ranks
+}
+
+/// Sobol points rotated per pixel by a blue-noise texture (Georgiev and Fajardo,
+/// "Blue-noise Dithered Sampling").
+///
+/// Every pixel uses the same scrambled Sobol points, toroidally shifted by values read from
+/// the texture. Each draw reads the texture at its own offset, so the shifts of neighbouring
+/// pixels differ as much as possible and their errors are distributed as blue noise.
+pub struct BlueNoiseSampler {
+    pixel: (usize, usize),
+    index: u32,
+    draw: u32,
+}
+
+impl BlueNoiseSampler {
+    pub fn new(pixel: (usize, usize)) -> Self {
+        Self {
+            pixel,
+            index: 0,
+            draw: 0,
+        }
+    }
+
+    /// Point of the current sample for the next draw.
+    fn next_point(&mut self) -> (f32, f32) {
+        self.draw += 1;
+        let (x, y) = scrambled_sobol_2d(self.index, hash(self.draw));
+        (
+            rotate(to_unit(x), self.offset(0)),
+            rotate(to_unit(y), self.offset(1)),
+        )
+    }
+
+    /// Blue-noise shift of coordinate `axis` of the current draw.
+    fn offset(&self, axis: u32) -> f32 {
+        let shift = hash_combine(self.draw, axis) as usize;
+        let x = (self.pixel.0 + shift) % BLUE_NOISE_SIZE;
+        let y = (self.pixel.1 + (shift >> 16)) % BLUE_NOISE_SIZE;
+        let rank = blue_noise_ranks()[y * BLUE_NOISE_SIZE + x];
+        (rank as f32 + 0.5) / (BLUE_NOISE_SIZE * BLUE_NOISE_SIZE) as f32
+    }
+}
+
+/// Adds `offset` to `value` modulo 1.
+fn rotate(value: f32, offset: f32) -> f32 {
+    let rotated = value + offset;
+    if rotated >= 1.0 {
+        (rotated - 1.0).min(1.0 - f32::EPSILON)
+    } else {
+        rotated
+    }
+}
+
+impl Sampler for BlueNoiseSampler {
+    fn start_sample(&mut self, index: usize) {
+        self.index = index as u32;
+        self.draw = 0;
+    }
+
+    fn next_1d(&mut self) -> f32 {
+        self.next_point().0
+    }
+
+    fn next_2d(&mut self) -> (f32, f32) {
+        self.next_point()
+    }
+}
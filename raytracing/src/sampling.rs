@@ -0,0 +1,67 @@
+//! Warping of uniform random numbers to the distributions used by the path tracer.
+
+use std::f32::consts::PI;
+
+use crate::vec::{Float, Vec3, Vec3f};
+
+/// Two unit vectors completing the unit vector `normal` to an orthonormal basis.
+pub fn orthonormal_basis<T: Float>(normal: Vec3<T>) -> (Vec3<T>, Vec3<T>) {
+    let helper = if normal[0].abs() > 0.9f32.cast() {
+        Vec3::new(T::ZERO, T::ONE, T::ZERO)
+    } else {
+        Vec3::new(T::ONE, T::ZERO, T::ZERO)
+    };
+    let tangent = normal.cross(helper).normalized();
+    (tangent, normal.cross(tangent))
+}
+
+/// Direction at angle `acos(cos_theta)` from `axis`, rotated by `phi` around it.
+pub fn around_axis(axis: Vec3f, cos_theta: f32, phi: f32) -> Vec3f {
+    let (tangent, bitangent) = orthonormal_basis(axis);
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + axis * cos_theta
+}
+
+/// Direction in the hemisphere around `normal`, with density `cos(theta) / PI`.
+pub fn cosine_hemisphere(normal: Vec3f, u: f32, v: f32) -> Vec3f {
+    around_axis(normal, (1.0 - u).sqrt(), 2.0 * PI * v)
+}
+
+/// Direction uniformly distributed over the unit sphere.
+pub fn uniform_sphere(u: f32, v: f32) -> Vec3f {
+    around_axis(Vec3f::new(0.0, 1.0, 0.0), 1.0 - 2.0 * u, 2.0 * PI * v)
+}
+
+/// Point uniformly distributed over the unit disk, as `(x, y)`.
+pub fn uniform_disk(u: f32, v: f32) -> (f32, f32) {
+    let radius = u.sqrt();
+    let phi = 2.0 * PI * v;
+    (radius * phi.cos(), radius * phi.sin())
+}
+
+/// Rule weighting the estimates of two sampling strategies in multiple importance sampling.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MisHeuristic {
+    Balance,
+    /// Power heuristic with exponent 2, usually the lower-variance choice.
+    Power,
+}
+
+impl MisHeuristic {
+    /// Weight of a sample drawn with density `pdf`, when the other strategy would have
+    /// drawn it with density `other_pdf`.
+    pub fn weight(self, pdf: f32, other_pdf: f32) -> f32 {
+        let (a, b) = match self {
+            MisHeuristic::Balance => (pdf, other_pdf),
+            MisHeuristic::Power => (pdf * pdf, other_pdf * other_pdf),
+        };
+        if a + b > 0.0 {
+            a / (a + b)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Density of [`uniform_sphere`] directions.
+pub const UNIFORM_SPHERE_PDF: f32 = 1.0 / (4.0 * PI);
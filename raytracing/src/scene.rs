@@ -1,132 +1,558 @@
 use std::ops::Mul;
 
-use crate::primitives::{Light, Material, Ray};
-use crate::vec::Vec3f;
+use crate::aabb::Aabb;
+use crate::error::{check_finite, GeometryError, RenderError};
+use crate::medium::Media;
+use crate::photon_map::PhotonMap;
+use crate::primitives::{Light, Material, MaterialId, Ray};
+use crate::stats;
+use crate::vec::{Float, Vec3, Vec3f};
 
-#[derive(Copy, Clone, Debug, Default)]
-pub struct RayIntersection {
-    pub distance: f32,
-    pub hit: Vec3f,
-    pub normal: Vec3f,
-    pub material: Material,
+/// Surface data at the point where a ray hits a primitive.
+#[derive(Copy, Clone, Debug)]
+pub struct RayIntersection<T = f32> {
+    pub distance: T,
+    pub hit: Vec3<T>,
+    pub normal: Vec3<T>,
+    /// Surface coordinates of the hit, both in `[0, 1]`.
+    pub uv: (T, T),
+    pub material: MaterialId,
 }
 
-pub trait Renderable {
-    fn ray_intersect(&self, ray: Ray) -> Option<RayIntersection>;
+impl<T: Float> Default for RayIntersection<T> {
+    fn default() -> Self {
+        Self {
+            distance: T::ZERO,
+            hit: Vec3::default(),
+            normal: Vec3::default(),
+            uv: (T::ZERO, T::ZERO),
+            material: MaterialId::default(),
+        }
+    }
+}
+
+impl<T: Float> RayIntersection<T> {
+    /// The same intersection in another precision.
+    pub fn cast<U: Float>(self) -> RayIntersection<U> {
+        RayIntersection {
+            distance: self.distance.cast(),
+            hit: self.hit.cast(),
+            normal: self.normal.cast(),
+            uv: (self.uv.0.cast(), self.uv.1.cast()),
+            material: self.material,
+        }
+    }
+}
+
+/// Object that can describe its surface at a given point along a ray.
+pub trait Primitive<T: Float = f32> {
+    fn surface(&self, ray: Ray<T>, distance: T) -> RayIntersection<T>;
 }
 
-impl<I: AsRef<[Box<dyn Renderable>]>> Renderable for I {
-    fn ray_intersect(&self, ray: Ray) -> Option<RayIntersection> {
-        self.as_ref().iter()
-            .map(|obj| obj.ray_intersect(ray))
-            .flatten()
-            .min_by(|ri1, ri2| ri1.distance.partial_cmp(&ri2.distance).unwrap())
+/// Point on the surface of an [`Emitter`].
+#[derive(Copy, Clone, Debug)]
+pub struct SurfaceSample<T = f32> {
+    pub point: Vec3<T>,
+    pub normal: Vec3<T>,
+}
+
+impl<T: Float> Default for SurfaceSample<T> {
+    fn default() -> Self {
+        Self {
+            point: Vec3::default(),
+            normal: Vec3::default(),
+        }
     }
 }
 
-pub struct Scene {
-    pub objects: Vec<Box<dyn Renderable>>,
-    pub lights: Vec<Light>,
+/// Primitive whose surface can be sampled, so that it can act as an area light.
+pub trait Emitter<T: Float = f32>: Primitive<T> {
+    fn material(&self) -> MaterialId;
+
+    fn area(&self) -> f32;
+
+    /// Maps `u` and `v`, uniform in `[0, 1)`, to a point uniformly distributed over the surface.
+    fn sample_surface(&self, u: f32, v: f32) -> SurfaceSample<T>;
+
+    /// Maps `u` and `v` to a point of the surface for lighting `reference`, together with
+    /// the solid-angle density of the direction from `reference` to it. `None` if the
+    /// point cannot light `reference`.
+    ///
+    /// Samples the whole surface uniformly by default; emitters that can do better, e.g.
+    /// by only sampling the part visible from `reference`, override this and
+    /// [`pdf_from`](Emitter::pdf_from) together.
+    fn sample_from(&self, reference: Vec3<T>, u: f32, v: f32) -> Option<(SurfaceSample<T>, f32)> {
+        let sample = self.sample_surface(u, v);
+        let pdf = self.pdf_from(reference, sample.point, sample.normal);
+        if pdf > 0.0 && pdf.is_finite() {
+            Some((sample, pdf))
+        } else {
+            None
+        }
+    }
+
+    /// Whether hits of `primitive` are hits of this emitter.
+    fn is_primitive(&self, primitive: &dyn Primitive<T>) -> bool {
+        std::ptr::addr_eq(self, primitive)
+    }
+
+    /// Solid-angle density with which [`sample_from`](Emitter::sample_from) picks the
+    /// direction from `reference` to `point`, a point of the surface with `normal`.
+    fn pdf_from(&self, reference: Vec3<T>, point: Vec3<T>, normal: Vec3<T>) -> f32 {
+        let to_point = point - reference;
+        let distance_squared = to_point * to_point;
+        let cosine = (normal * to_point).abs() / distance_squared.sqrt();
+        distance_squared.cast::<f32>() / (cosine.cast::<f32>() * self.area())
+    }
+}
+
+/// Result of the intersection phase: the distance to the nearest hit and the primitive
+/// that was hit. Surface data is computed only on demand, see [`Hit::surface`].
+#[derive(Copy, Clone)]
+pub struct Hit<'a, T: Float = f32> {
+    pub distance: T,
+    pub primitive: &'a dyn Primitive<T>,
+    /// Whether the primitive's normal must be reversed, e.g. for surfaces carved out by CSG.
+    pub flip_normal: bool,
+    /// Translation from the space the primitive is defined in to the scene, e.g. for
+    /// [`Translated`](crate::translated::Translated) objects.
+    pub offset: Vec3<T>,
+}
+
+impl<'a, T: Float> Hit<'a, T> {
+    pub fn new(distance: T, primitive: &'a dyn Primitive<T>) -> Self {
+        Self {
+            distance,
+            primitive,
+            flip_normal: false,
+            offset: Vec3::default(),
+        }
+    }
+
+    pub fn surface(&self, ray: Ray<T>) -> RayIntersection<T> {
+        let local = Ray {
+            origin: ray.origin - self.offset,
+            direction: ray.direction,
+        };
+        let mut intersection = self.primitive.surface(local, self.distance);
+        intersection.hit = intersection.hit + self.offset;
+        if self.flip_normal {
+            intersection.normal = intersection.normal * -T::ONE;
+        }
+        intersection
+    }
+}
+
+/// Part of a ray's line lying inside a solid, between the `enter` and `exit` boundary hits.
+#[derive(Copy, Clone)]
+pub struct Span<'a, T: Float = f32> {
+    pub enter: Hit<'a, T>,
+    pub exit: Hit<'a, T>,
+}
+
+/// Spans of a closed surface from the sorted distances at which the line of `ray` crosses
+/// it. Near-equal distances are merged, an even number of them being a tangent touch, and
+/// each remaining crossing enters or leaves the solid by the side the ray meets the surface
+/// from. Unlike pairing crossings by parity, a root returned twice or dropped by a solver
+/// at a tangent point cannot shift the pairing of the later ones.
+pub(crate) fn spans_from_crossings<'a, T: Float>(
+    ray: Ray<T>,
+    crossings: &[T],
+    primitive: &'a dyn Primitive<T>,
+) -> Vec<Span<'a, T>> {
+    let mut spans = Vec::new();
+    let mut enter = None;
+    let mut i = 0;
+    while i < crossings.len() {
+        let t = crossings[i];
+        let mut multiplicity = 1;
+        while i + multiplicity < crossings.len()
+            && crossings[i + multiplicity] - t <= CROSSING_EPSILON.cast::<T>() * t.abs().max(T::ONE)
+        {
+            multiplicity += 1;
+        }
+        i += multiplicity;
+        if multiplicity % 2 == 0 {
+            continue;
+        }
+        let cosine = primitive.surface(ray, t).normal * ray.direction;
+        match enter {
+            None if cosine < T::ZERO => enter = Some(t),
+            Some(start) if cosine > T::ZERO => {
+                spans.push(Span {
+                    enter: Hit::new(start, primitive),
+                    exit: Hit::new(t, primitive),
+                });
+                enter = None;
+            }
+            _ => {}
+        }
+    }
+    spans
+}
+
+/// Relative difference below which two crossings are taken as the same.
+const CROSSING_EPSILON: f32 = 1e-5;
+
+/// Span of a convex solid: everything between the first and the last crossing.
+pub(crate) fn convex_span<'a, T: Float>(
+    crossings: &[T],
+    primitive: &'a dyn Primitive<T>,
+) -> Vec<Span<'a, T>> {
+    match (crossings.first(), crossings.last()) {
+        (Some(&enter), Some(&exit)) if crossings.len() >= 2 => vec![Span {
+            enter: Hit::new(enter, primitive),
+            exit: Hit::new(exit, primitive),
+        }],
+        _ => Vec::new(),
+    }
+}
+
+/// Minimal distance along secondary rays, so that they do not hit the surface they start from.
+pub const RAY_EPSILON: f32 = 1e-3;
+
+/// Picks the smallest of `candidates` lying in `[t_min, t_max]`.
+pub(crate) fn nearest_in_range<T: Float, I: IntoIterator<Item = T>>(
+    candidates: I,
+    t_min: T,
+    t_max: T,
+) -> Option<T> {
+    candidates
+        .into_iter()
+        .filter(|&t| t >= t_min && t <= t_max)
+        .fold(None, |nearest: Option<T>, t| {
+            Some(nearest.map_or(t, |nearest| nearest.min(t)))
+        })
+}
+
+/// Object of a scene, its geometry computed in precision `T`.
+pub trait Renderable<T: Float = f32> {
+    /// Finds the nearest intersection with distance along the ray in `[t_min, t_max]`.
+    fn ray_intersect(&self, ray: Ray<T>, t_min: T, t_max: T) -> Option<Hit<'_, T>>;
+
+    /// Checks whether anything blocks the ray within `[RAY_EPSILON, max_dist]`.
+    ///
+    /// Unlike `ray_intersect`, implementations may stop at the first blocker found
+    /// and should not compute any surface data.
+    fn occluded(&self, ray: Ray<T>, max_dist: T) -> bool {
+        self.ray_intersect(ray, RAY_EPSILON.cast(), max_dist)
+            .is_some()
+    }
+
+    /// Box enclosing the object, `None` if it is unbounded.
+    fn bounds(&self) -> Option<Aabb<T>> {
+        None
+    }
+
+    /// All parts of the ray's whole line (any distance, including negative) that lie
+    /// inside the object, in ascending order. Empty for objects that are not closed solids.
+    fn spans(&self, _ray: Ray<T>) -> Vec<Span<'_, T>> {
+        Vec::new()
+    }
+
+    /// The object as an area light, if its surface can be sampled.
+    fn emitter(&self) -> Option<&dyn Emitter<T>> {
+        None
+    }
+
+    /// Materials the surfaces of the object refer to.
+    fn materials(&self) -> Vec<MaterialId> {
+        Vec::new()
+    }
+
+    /// Checks that the object is well defined, e.g. that its sizes are positive.
+    fn validate(&self) -> Result<(), GeometryError> {
+        Ok(())
+    }
+}
+
+impl<T: Float, I: AsRef<[Box<dyn Renderable<T>>]>> Renderable<T> for I {
+    fn ray_intersect(&self, ray: Ray<T>, t_min: T, t_max: T) -> Option<Hit<'_, T>> {
+        let mut closest = None;
+        let mut t_max = t_max;
+        for obj in self.as_ref() {
+            if let Some(hit) = obj.ray_intersect(ray, t_min, t_max) {
+                t_max = hit.distance;
+                closest = Some(hit);
+            }
+        }
+        closest
+    }
+
+    fn occluded(&self, ray: Ray<T>, max_dist: T) -> bool {
+        self.as_ref().iter().any(|obj| obj.occluded(ray, max_dist))
+    }
+
+    fn materials(&self) -> Vec<MaterialId> {
+        self.as_ref()
+            .iter()
+            .flat_map(|obj| obj.materials())
+            .collect()
+    }
+
+    fn validate(&self) -> Result<(), GeometryError> {
+        self.as_ref().iter().try_for_each(|obj| obj.validate())
+    }
+
+    fn bounds(&self) -> Option<Aabb<T>> {
+        let mut objects = self.as_ref().iter();
+        let first = objects.next()?.bounds()?;
+        objects.try_fold(first, |acc, obj| Some(acc.union(&obj.bounds()?)))
+    }
+}
+
+/// Objects, materials, lights and media to render, with positions and distances in
+/// precision `T`.
+pub struct Scene<T: Float = f32> {
+    pub objects: Vec<Box<dyn Renderable<T>>>,
+    pub materials: Vec<Material>,
+    pub lights: Vec<Light<T>>,
+    pub media: Media<T>,
     pub background_color: Vec3f,
 }
 
-fn reflect(v: Vec3f, norm: Vec3f) -> Vec3f {
-    v - norm * 2.0 * (v * norm)
+impl<T: Float> Default for Scene<T> {
+    fn default() -> Self {
+        Self {
+            objects: Vec::new(),
+            materials: Vec::new(),
+            lights: Vec::new(),
+            media: Media::default(),
+            background_color: Vec3f::default(),
+        }
+    }
+}
+
+pub(crate) fn reflect<T: Float>(v: Vec3<T>, norm: Vec3<T>) -> Vec3<T> {
+    v - norm * 2.0f32.cast::<T>() * (v * norm)
 }
 
-fn refract(v: Vec3f, norm: Vec3f, refractive_index: f32) -> Vec3f {
-    let mut cosi = -(v * norm).clamp(-1.0, 1.0);
-    let mut etai = 1.0;
-    let mut etat = refractive_index;
+pub(crate) fn refract<T: Float>(v: Vec3<T>, norm: Vec3<T>, refractive_index: f32) -> Vec3<T> {
+    let mut cosi = -(v * norm).clamp(-T::ONE, T::ONE);
+    let mut etai = T::ONE;
+    let mut etat = refractive_index.cast();
     let mut n = norm;
-    if cosi < 0.0 {
+    if cosi < T::ZERO {
         cosi = -cosi;
         std::mem::swap(&mut etai, &mut etat);
-        n = Vec3f::default() - norm;
+        n = Vec3::default() - norm;
     }
     let eta = etai / etat;
-    let k = 1.0 - eta * eta * (1.0 - cosi * cosi);
-    if k < 0.0 {
-        Vec3f::new(1.0, 0.0, 0.0)
+    let k = T::ONE - eta * eta * (T::ONE - cosi * cosi);
+    if k < T::ZERO {
+        Vec3::new(T::ONE, T::ZERO, T::ZERO)
     } else {
         v * eta + n * (eta * cosi - k.sqrt())
     }
 }
 
-impl Scene {
-    pub fn cast_ray(&self, ray: Ray, depth: usize) -> Vec3f {
+/// Reflected and refracted rays the Whitted integrator traces from the surface hit by `ray`.
+pub(crate) fn secondary_rays<T: Float>(
+    ray: Ray<T>,
+    intersection: &RayIntersection<T>,
+    material: &Material,
+) -> (Ray<T>, Ray<T>) {
+    let RayIntersection { hit, normal, .. } = *intersection;
+    let reflected = Ray {
+        origin: hit,
+        direction: reflect(ray.direction, normal).normalized(),
+    };
+    let refracted = Ray {
+        origin: hit,
+        direction: refract(ray.direction, normal, material.refractive_index).normalized(),
+    };
+    (reflected, refracted)
+}
+
+impl<T: Float> Scene<T> {
+    /// Stores `material` in the scene and returns the id objects can refer to it by.
+    pub fn add_material(&mut self, material: Material) -> MaterialId {
+        self.materials.push(material);
+        MaterialId(self.materials.len() - 1)
+    }
+
+    /// Checks the objects, lights and materials, returning the first problem found.
+    pub fn validate(&self) -> Result<(), RenderError> {
+        for (index, object) in self.objects.iter().enumerate() {
+            object
+                .validate()
+                .map_err(|error| RenderError::InvalidObject { index, error })?;
+            if let Some(&material) = object
+                .materials()
+                .iter()
+                .find(|material| material.0 >= self.materials.len())
+            {
+                return Err(RenderError::UnknownMaterial {
+                    object: index,
+                    material,
+                });
+            }
+        }
+        for (index, light) in self.lights.iter().enumerate() {
+            if check_finite(&[light.position]).is_err() || !light.intensity.is_finite() {
+                return Err(RenderError::InvalidLight(index));
+            }
+        }
+        if let Some(fog) = &self.media.fog {
+            if !fog.is_valid() {
+                return Err(RenderError::InvalidFog);
+            }
+        }
+        if let Some(index) = self
+            .media
+            .volumes
+            .iter()
+            .position(|volume| !volume.is_valid())
+        {
+            return Err(RenderError::InvalidVolume(index));
+        }
+        for (index, material) in self.materials.iter().enumerate() {
+            if !material.is_valid() {
+                return Err(RenderError::InvalidMaterial(MaterialId(index)));
+            }
+            if !material.conserves_energy() {
+                return Err(RenderError::AlbedoNotEnergyConserving {
+                    material: MaterialId(index),
+                    albedo: material.albedo,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn material(&self, id: MaterialId) -> &Material {
+        &self.materials[id.0]
+    }
+
+    /// Top-level objects with an emissive material that can be sampled as area lights.
+    ///
+    /// Emissive objects nested in aggregates such as a [`Bvh`](crate::bvh::Bvh) or a CSG
+    /// tree are still visible, but only found by the path tracer through random bounces.
+    pub fn emitters(&self) -> Vec<&dyn Emitter<T>> {
+        self.objects
+            .iter()
+            .filter_map(|object| object.emitter())
+            .filter(|emitter| {
+                let emission = self.material(emitter.material()).emission;
+                emission[0] > 0.0 || emission[1] > 0.0 || emission[2] > 0.0
+            })
+            .collect()
+    }
+
+    pub fn cast_ray(&self, ray: Ray<T>, depth: usize) -> Vec3f {
+        self.trace(ray, depth, 0, None)
+    }
+
+    /// Like [`cast_ray`](Self::cast_ray), also lighting diffuse surfaces by the caustics
+    /// stored in `photons`.
+    pub fn cast_ray_with_caustics(
+        &self,
+        ray: Ray<T>,
+        depth: usize,
+        photons: &PhotonMap<T>,
+    ) -> Vec3f {
+        self.trace(ray, depth, 0, Some(photons))
+    }
+
+    /// Traces `ray`, ignoring hits closer than `RAY_EPSILON` for every ray but the camera one.
+    pub(crate) fn trace(
+        &self,
+        ray: Ray<T>,
+        depth: usize,
+        level: usize,
+        photons: Option<&PhotonMap<T>>,
+    ) -> Vec3f {
         if depth == 0 {
             return self.background_color;
         }
-        if let Some(RayIntersection {
-                        distance: _,
-                        hit,
-                        normal,
-                        material,
-                    }) = self.objects.ray_intersect(ray)
-        {
-            let reflect_direction = reflect(ray.direction, normal).normalized();
-            let refract_direction =
-                refract(ray.direction, normal, material.refractive_index).normalized();
-            let eps = 1e-3;
-            let reflect_orig = if reflect_direction * normal < 0.0 {
-                hit - normal * eps
-            } else {
-                hit + normal * eps
-            };
-            let refract_orig = if refract_direction * normal < 0.0 {
-                hit - normal * eps
+        let t_min = if level == 0 {
+            T::ZERO
+        } else {
+            RAY_EPSILON.cast()
+        };
+        stats::record(|counters| {
+            if level == 0 {
+                counters.primary_rays += 1;
             } else {
-                hit + normal * eps
-            };
-
-            let reflect_color = self.cast_ray(
-                Ray {
-                    origin: reflect_orig,
-                    direction: reflect_direction,
-                },
-                depth - 1,
-            );
-            let refract_color = self.cast_ray(
-                Ray {
-                    origin: refract_orig,
-                    direction: refract_direction,
-                },
-                depth - 1,
-            );
-
-            let mut diffuse_light_intensity = 0.0;
-            let mut spectacular_light_intensity = 0.0;
-            for light in &self.lights {
-                let light_direction = (light.position - hit).normalized();
-                let light_distance = (light.position - hit).norm();
-
-                let shadow_orig = if light_direction * normal < 0.0 {
-                    hit - normal * 1e-3
-                } else {
-                    hit + normal * 1e-3
-                };
-                if let Some(intersection) = self.objects.ray_intersect(Ray {
-                    origin: shadow_orig,
-                    direction: light_direction,
-                }) {
-                    if (intersection.hit - shadow_orig).norm() < light_distance {
-                        continue;
-                    }
-                }
-
-                diffuse_light_intensity += light.intensity * 0.0f32.max(light_direction * normal);
-                spectacular_light_intensity += 0.0f32
-                    .max(reflect(light_direction, normal) * ray.direction)
-                    .powf(material.spectacular_component)
-                    .mul(light.intensity);
+                counters.secondary_rays += 1;
             }
-            material.diffuse_color * diffuse_light_intensity * material.albedo[0]
-                + Vec3f::new(1.0, 1.0, 1.0) * spectacular_light_intensity * material.albedo[1]
-                + reflect_color * material.albedo[2]
-                + refract_color * material.albedo[3]
+            counters.max_depth = counters.max_depth.max(level);
+        });
+        let closest = self.objects.ray_intersect(ray, t_min, T::INFINITY);
+        let color = if let Some(closest) = closest {
+            let intersection = closest.surface(ray);
+            let material = self.material(intersection.material);
+            let (reflected, refracted) = secondary_rays(ray, &intersection, material);
+            let reflect_color = self.trace(reflected, depth - 1, level + 1, photons);
+            let refract_color = self.trace(refracted, depth - 1, level + 1, photons);
+            self.shade(ray, &intersection, reflect_color, refract_color, photons)
         } else {
             self.background_color
+        };
+        let distance = closest.map_or(T::INFINITY, |hit| hit.distance);
+        self.through_media(ray, distance, color)
+    }
+
+    /// Light leaving the surface hit by `ray` towards its origin, given the light
+    /// `reflect_color` and `refract_color` arriving along the rays of [`secondary_rays`].
+    pub(crate) fn shade(
+        &self,
+        ray: Ray<T>,
+        intersection: &RayIntersection<T>,
+        reflect_color: Vec3f,
+        refract_color: Vec3f,
+        photons: Option<&PhotonMap<T>>,
+    ) -> Vec3f {
+        let RayIntersection {
+            hit,
+            normal,
+            material,
+            ..
+        } = *intersection;
+        let material = self.material(material);
+        let mut diffuse_light_intensity = 0.0;
+        let mut spectacular_light_intensity = 0.0;
+        for light in &self.lights {
+            let light_direction = (light.position - hit).normalized();
+            let light_distance = (light.position - hit).norm();
+
+            stats::record(|counters| counters.shadow_rays += 1);
+            let shadow_ray = Ray {
+                origin: hit,
+                direction: light_direction,
+            };
+            if self.objects.occluded(shadow_ray, light_distance) {
+                continue;
+            }
+            let intensity = light.intensity
+                * self
+                    .media
+                    .transmittance(shadow_ray, T::ZERO, light_distance);
+
+            diffuse_light_intensity += intensity * 0.0f32.max((light_direction * normal).cast());
+            spectacular_light_intensity += 0.0f32
+                .max((reflect(light_direction, normal) * ray.direction).cast())
+                .powf(material.spectacular_component)
+                .mul(intensity);
+        }
+        let mut diffuse_light = Vec3f::new(1.0, 1.0, 1.0) * diffuse_light_intensity;
+        if let Some(photons) = photons {
+            diffuse_light =
+                diffuse_light + photons.irradiance(hit, normal) * std::f32::consts::FRAC_1_PI;
+        }
+        material.emission
+            + material.diffuse_color.component_mul(diffuse_light) * material.albedo[0]
+            + Vec3f::new(1.0, 1.0, 1.0) * spectacular_light_intensity * material.albedo[1]
+            + reflect_color * material.albedo[2]
+            + refract_color * material.albedo[3]
+    }
+
+    /// `color` seen at `distance` along `ray` through the media of the scene.
+    pub(crate) fn through_media(&self, ray: Ray<T>, distance: T, color: Vec3f) -> Vec3f {
+        if self.media.is_empty() {
+            return color;
         }
+        self.media
+            .apply(ray, distance, color, &self.lights, &self.objects)
     }
 }
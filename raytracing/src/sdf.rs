@@ -0,0 +1,213 @@
+//! Implicit surfaces given by signed distance functions, rendered by sphere tracing.
+
+use crate::aabb::Aabb;
+use crate::error::{check_finite, check_positive, GeometryError};
+use crate::primitives::{MaterialId, Ray};
+use crate::scene::{Hit, Primitive, RayIntersection, Renderable};
+use crate::stats::{self, PrimitiveKind};
+use crate::vec::{Float, Vec3, Vec3f};
+
+/// Surface where `distance` changes sign, restricted to `bounds`.
+///
+/// `distance` should not overestimate the distance to the surface. For fields that may,
+/// like twisted ones, lower `step_scale` to take proportionally shorter steps. The field
+/// functions of this module are in `f32`; fields of other precisions are written directly.
+pub struct Sdf<T: Float = f32> {
+    pub distance: Box<dyn Fn(Vec3<T>) -> T>,
+    pub bounds: Aabb<T>,
+    pub material: MaterialId,
+    pub step_scale: f32,
+    pub max_steps: usize,
+    pub epsilon: f32,
+}
+
+impl<T: Float> Sdf<T> {
+    pub fn new<F: Fn(Vec3<T>) -> T + 'static>(
+        distance: F,
+        bounds: Aabb<T>,
+        material: MaterialId,
+    ) -> Self {
+        Self {
+            distance: Box::new(distance),
+            bounds,
+            material,
+            step_scale: 1.0,
+            max_steps: 256,
+            epsilon: 1e-4,
+        }
+    }
+
+    /// Gradient of the distance field by central differences.
+    fn gradient(&self, point: Vec3<T>) -> Vec3<T> {
+        let h = (self.epsilon * 10.0).cast();
+        let axis_delta = |axis: usize| {
+            let mut offset = Vec3::default();
+            offset.coordinates[axis] = h;
+            (self.distance)(point + offset) - (self.distance)(point - offset)
+        };
+        Vec3::new(axis_delta(0), axis_delta(1), axis_delta(2))
+    }
+}
+
+impl<T: Float> Renderable<T> for Sdf<T> {
+    fn ray_intersect(&self, ray: Ray<T>, t_min: T, t_max: T) -> Option<Hit<'_, T>> {
+        stats::count_intersection_test(PrimitiveKind::Sdf);
+        let (start, end) = self.bounds.ray_range(ray, t_min, t_max)?;
+        let (epsilon, step_scale) = (self.epsilon.cast::<T>(), self.step_scale.cast::<T>());
+        let mut t = start;
+        for _ in 0..self.max_steps {
+            let distance = (self.distance)(ray.origin + ray.direction * t).abs();
+            if distance < epsilon * t.max(T::ONE) {
+                return Some(Hit::new(t, self));
+            }
+            t += distance * step_scale;
+            if t > end {
+                return None;
+            }
+        }
+        None
+    }
+
+    fn bounds(&self) -> Option<Aabb<T>> {
+        Some(self.bounds)
+    }
+
+    fn materials(&self) -> Vec<MaterialId> {
+        vec![self.material]
+    }
+
+    fn validate(&self) -> Result<(), GeometryError> {
+        check_finite(&[self.bounds.min, self.bounds.max])?;
+        check_positive("step scale", self.step_scale)?;
+        check_positive("epsilon", self.epsilon)
+    }
+}
+
+impl<T: Float> Primitive<T> for Sdf<T> {
+    fn surface(&self, ray: Ray<T>, distance: T) -> RayIntersection<T> {
+        let hit = ray.origin + ray.direction * distance;
+        let normal = self.gradient(hit).normalized();
+        RayIntersection {
+            distance,
+            hit,
+            normal,
+            uv: (
+                0.5f32.cast::<T>() + normal[2].atan2(normal[0]) / (T::PI + T::PI),
+                0.5f32.cast::<T>() - normal[1].clamp(-T::ONE, T::ONE).asin() / T::PI,
+            ),
+            material: self.material,
+        }
+    }
+}
+
+pub fn sphere(center: Vec3f, radius: f32) -> impl Fn(Vec3f) -> f32 {
+    move |p| (p - center).norm() - radius
+}
+
+/// Box with the given half-size whose edges are rounded by `rounding`.
+pub fn rounded_box(center: Vec3f, half_size: Vec3f, rounding: f32) -> impl Fn(Vec3f) -> f32 {
+    move |p| {
+        let local = p - center;
+        let q = Vec3f::new(
+            local[0].abs() - half_size[0] + rounding,
+            local[1].abs() - half_size[1] + rounding,
+            local[2].abs() - half_size[2] + rounding,
+        );
+        let outside = Vec3f::new(q[0].max(0.0), q[1].max(0.0), q[2].max(0.0));
+        outside.norm() + q[0].max(q[1]).max(q[2]).min(0.0) - rounding
+    }
+}
+
+/// Torus lying in the horizontal plane, as in [`Torus`](crate::torus::Torus).
+pub fn torus(center: Vec3f, major_radius: f32, minor_radius: f32) -> impl Fn(Vec3f) -> f32 {
+    move |p| {
+        let local = p - center;
+        let ring = (local[0] * local[0] + local[2] * local[2]).sqrt() - major_radius;
+        (ring * ring + local[1] * local[1]).sqrt() - minor_radius
+    }
+}
+
+/// Distance estimator of the Mandelbulb fractal of the given `power`, centered at `center`
+/// and scaled by `scale`.
+pub fn mandelbulb(
+    center: Vec3f,
+    scale: f32,
+    power: f32,
+    iterations: usize,
+) -> impl Fn(Vec3f) -> f32 {
+    move |p| {
+        let c = (p - center) * (1.0 / scale);
+        let mut z = c;
+        let mut dr = 1.0;
+        let mut r = 0.0;
+        for _ in 0..iterations {
+            r = z.norm();
+            if r > 2.0 {
+                break;
+            }
+            // The y axis is the polar one, so that the bulb stands upright.
+            let theta = (z[1] / r).clamp(-1.0, 1.0).acos() * power;
+            let phi = z[2].atan2(z[0]) * power;
+            dr = r.powf(power - 1.0) * power * dr + 1.0;
+            let zr = r.powf(power);
+            z = Vec3f::new(
+                theta.sin() * phi.cos(),
+                theta.cos(),
+                theta.sin() * phi.sin(),
+            ) * zr
+                + c;
+        }
+        if r == 0.0 {
+            return 0.0;
+        }
+        0.5 * r.ln() * r / dr * scale
+    }
+}
+
+/// Blends two fields together; `k` sets the size of the blending region.
+pub fn smooth_union<A, B>(a: A, b: B, k: f32) -> impl Fn(Vec3f) -> f32
+where
+    A: Fn(Vec3f) -> f32,
+    B: Fn(Vec3f) -> f32,
+{
+    move |p| {
+        let da = a(p);
+        let db = b(p);
+        let h = (0.5 + 0.5 * (db - da) / k).clamp(0.0, 1.0);
+        db + (da - db) * h - k * h * (1.0 - h)
+    }
+}
+
+pub fn translate<F: Fn(Vec3f) -> f32>(field: F, offset: Vec3f) -> impl Fn(Vec3f) -> f32 {
+    move |p| field(p - offset)
+}
+
+/// Repeats the field infinitely with the given period along each axis.
+/// A zero period component leaves that axis unrepeated.
+pub fn repeat<F: Fn(Vec3f) -> f32>(field: F, period: Vec3f) -> impl Fn(Vec3f) -> f32 {
+    move |p| {
+        let wrap = |axis: usize| {
+            if period[axis] == 0.0 {
+                p[axis]
+            } else {
+                p[axis] - period[axis] * (p[axis] / period[axis]).round()
+            }
+        };
+        field(Vec3f::new(wrap(0), wrap(1), wrap(2)))
+    }
+}
+
+/// Twists the field around the y axis by `rate` radians per unit of height.
+///
+/// The result overestimates distances, so pair it with a `step_scale` below one.
+pub fn twist<F: Fn(Vec3f) -> f32>(field: F, rate: f32) -> impl Fn(Vec3f) -> f32 {
+    move |p| {
+        let angle = rate * p[1];
+        let (sin, cos) = angle.sin_cos();
+        field(Vec3f::new(
+            cos * p[0] - sin * p[2],
+            p[1],
+            sin * p[0] + cos * p[2],
+        ))
+    }
+}
@@ -1,19 +1,31 @@
-use crate::primitives::{Material, Ray};
-use crate::scene::{RayIntersection, Renderable};
-use crate::vec::Vec3f;
+use std::f32::consts::PI;
 
+use crate::aabb::Aabb;
+use crate::error::{check_finite, check_positive, GeometryError};
+use crate::primitives::{MaterialId, Ray};
+use crate::sampling::{around_axis, uniform_sphere};
+use crate::scene::{
+    convex_span, Emitter, Hit, Primitive, RayIntersection, Renderable, Span, SurfaceSample,
+    RAY_EPSILON,
+};
+use crate::stats::{self, PrimitiveKind};
+use crate::vec::{Float, Vec3, Vec3f};
+
+/// Sphere in precision `T`.
+///
+/// In `f64` the roots of huge spheres, e.g. a ground sphere thousands of units wide, stay
+/// within `RAY_EPSILON` of the surface, so that secondary rays do not hit it again.
 #[derive(Copy, Clone, Debug)]
-pub struct Sphere {
-    pub center: Vec3f,
-    pub radius: f32,
-    pub material: Material,
+pub struct Sphere<T = f32> {
+    pub center: Vec3<T>,
+    pub radius: T,
+    pub material: MaterialId,
 }
 
-impl Renderable for Sphere {
-    /// Check if given ray with such `origin` and `direction` intersects sphere.
-    ///
-    /// Returns `None` if not, else returns `Some(dist)` where `dist` is distance from `origin` to sphere.
-    fn ray_intersect(&self, ray: Ray) -> Option<RayIntersection> {
+impl<T: Float> Sphere<T> {
+    /// Distances from ray origin to both points where the ray's line crosses the sphere.
+    fn roots(&self, ray: Ray<T>) -> Option<(T, T)> {
+        stats::count_intersection_test(PrimitiveKind::Sphere);
         let origin_to_center = self.center - ray.origin;
         let otc_ray_projection = origin_to_center * ray.direction;
         let center_to_ray_distance_sqr =
@@ -23,24 +35,142 @@ impl Renderable for Sphere {
         }
         let radius_to_ray_projection =
             (self.radius * self.radius - center_to_ray_distance_sqr).sqrt();
-        let origin_to_sphere_distance = {
-            let t0 = otc_ray_projection - radius_to_ray_projection;
-            let t1 = otc_ray_projection + radius_to_ray_projection;
-            if t0 >= 0.0 {
-                t0
-            } else if t1 >= 0.0 {
-                t1
-            } else {
-                return None;
-            }
+        Some((
+            otc_ray_projection - radius_to_ray_projection,
+            otc_ray_projection + radius_to_ray_projection,
+        ))
+    }
+}
+
+impl<T: Float> Renderable<T> for Sphere<T> {
+    /// Check if given ray with such `origin` and `direction` intersects sphere.
+    ///
+    /// Returns `None` if not, else returns the distance from `origin` to the nearest sphere
+    /// point within `[t_min, t_max]`.
+    fn ray_intersect(&self, ray: Ray<T>, t_min: T, t_max: T) -> Option<Hit<'_, T>> {
+        let (t0, t1) = self.roots(ray)?;
+        let distance = if t0 >= t_min && t0 <= t_max {
+            t0
+        } else if t1 >= t_min && t1 <= t_max {
+            t1
+        } else {
+            return None;
         };
-        let hit = ray.origin + ray.direction * origin_to_sphere_distance;
+        Some(Hit::new(distance, self))
+    }
+
+    fn occluded(&self, ray: Ray<T>, max_dist: T) -> bool {
+        let epsilon = RAY_EPSILON.cast();
+        match self.roots(ray) {
+            Some((t0, t1)) => {
+                (t0 >= epsilon && t0 <= max_dist) || (t1 >= epsilon && t1 <= max_dist)
+            }
+            None => false,
+        }
+    }
+
+    fn spans(&self, ray: Ray<T>) -> Vec<Span<'_, T>> {
+        match self.roots(ray) {
+            Some((t0, t1)) => convex_span(&[t0, t1], self),
+            None => Vec::new(),
+        }
+    }
+
+    fn bounds(&self) -> Option<Aabb<T>> {
+        let extent = Vec3::new(self.radius, self.radius, self.radius);
+        Some(Aabb::new(self.center - extent, self.center + extent))
+    }
+
+    fn emitter(&self) -> Option<&dyn Emitter<T>> {
+        Some(self)
+    }
+
+    fn materials(&self) -> Vec<MaterialId> {
+        vec![self.material]
+    }
+
+    fn validate(&self) -> Result<(), GeometryError> {
+        check_finite(&[self.center])?;
+        check_positive("radius", self.radius)
+    }
+}
+
+impl<T: Float> Primitive<T> for Sphere<T> {
+    fn surface(&self, ray: Ray<T>, distance: T) -> RayIntersection<T> {
+        let hit = ray.origin + ray.direction * distance;
         let normal = (hit - self.center).normalized();
-        Some(RayIntersection {
-            distance: origin_to_sphere_distance,
+        let half = 0.5f32.cast::<T>();
+        RayIntersection {
+            distance,
             hit,
             normal,
+            uv: (
+                half + normal[2].atan2(normal[0]) / (T::PI + T::PI),
+                half - normal[1].clamp(-T::ONE, T::ONE).asin() / T::PI,
+            ),
             material: self.material,
-        })
+        }
+    }
+}
+
+impl<T: Float> Emitter<T> for Sphere<T> {
+    fn material(&self) -> MaterialId {
+        self.material
+    }
+
+    fn area(&self) -> f32 {
+        let radius: f32 = self.radius.cast();
+        4.0 * std::f32::consts::PI * radius * radius
+    }
+
+    fn sample_surface(&self, u: f32, v: f32) -> SurfaceSample<T> {
+        let normal = uniform_sphere(u, v).cast();
+        SurfaceSample {
+            point: self.center + normal * self.radius,
+            normal,
+        }
+    }
+
+    /// Samples the cone of directions under which `reference` sees the sphere, so that
+    /// no shadow ray is spent on its far side. Uniform over the surface from inside.
+    fn sample_from(&self, reference: Vec3<T>, u: f32, v: f32) -> Option<(SurfaceSample<T>, f32)> {
+        let to_center = self.center - reference;
+        let distance = to_center.norm();
+        let sin_squared_max: f32 = (self.radius / distance).powi(2).cast();
+        if sin_squared_max >= 1.0 {
+            let sample = self.sample_surface(u, v);
+            return Some((
+                sample,
+                self.pdf_from(reference, sample.point, sample.normal),
+            ));
+        }
+        let cos_max = (1.0 - sin_squared_max).sqrt();
+        // 1 - cos_max without cancellation for small cones.
+        let solid_angle = 2.0 * PI * sin_squared_max / (1.0 + cos_max);
+        let cos_theta = 1.0 - u * (1.0 - cos_max);
+        let sin_squared = (1.0 - cos_theta * cos_theta).max(0.0);
+        let axis: Vec3f = (to_center * (T::ONE / distance)).cast();
+        let direction = around_axis(axis, cos_theta, 2.0 * PI * v).cast();
+        // Nearer root of the ray from `reference` along `direction`.
+        let along = distance * cos_theta.cast()
+            - (self.radius * self.radius - distance * distance * sin_squared.cast())
+                .max(T::ZERO)
+                .sqrt();
+        let point = reference + direction * along;
+        let normal = (point - self.center).normalized();
+        Some((SurfaceSample { point, normal }, 1.0 / solid_angle))
+    }
+
+    fn pdf_from(&self, reference: Vec3<T>, point: Vec3<T>, normal: Vec3<T>) -> f32 {
+        let to_center = self.center - reference;
+        let sin_squared_max: f32 = ((self.radius * self.radius) / (to_center * to_center)).cast();
+        if sin_squared_max >= 1.0 {
+            let to_point = point - reference;
+            let distance_squared = to_point * to_point;
+            let cosine = (normal * to_point).abs() / distance_squared.sqrt();
+            return distance_squared.cast::<f32>() / (cosine.cast::<f32>() * self.area());
+        }
+        let cos_max = (1.0 - sin_squared_max).sqrt();
+        (1.0 + cos_max) / (2.0 * PI * sin_squared_max)
     }
 }
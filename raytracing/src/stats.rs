@@ -0,0 +1,204 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PrimitiveKind {
+    Sphere,
+    Plane,
+    Cuboid,
+    Cylinder,
+    Cone,
+    Disk,
+    Quad,
+    Torus,
+    Sdf,
+}
+
+impl PrimitiveKind {
+    pub const ALL: [PrimitiveKind; 9] = [
+        PrimitiveKind::Sphere,
+        PrimitiveKind::Plane,
+        PrimitiveKind::Cuboid,
+        PrimitiveKind::Cylinder,
+        PrimitiveKind::Cone,
+        PrimitiveKind::Disk,
+        PrimitiveKind::Quad,
+        PrimitiveKind::Torus,
+        PrimitiveKind::Sdf,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PrimitiveKind::Sphere => "sphere",
+            PrimitiveKind::Plane => "plane",
+            PrimitiveKind::Cuboid => "box",
+            PrimitiveKind::Cylinder => "cylinder",
+            PrimitiveKind::Cone => "cone",
+            PrimitiveKind::Disk => "disk",
+            PrimitiveKind::Quad => "quad",
+            PrimitiveKind::Torus => "torus",
+            PrimitiveKind::Sdf => "sdf",
+        }
+    }
+}
+
+/// Event counters gathered while tracing rays.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RayCounters {
+    pub primary_rays: u64,
+    pub secondary_rays: u64,
+    pub shadow_rays: u64,
+    pub intersection_tests: [u64; PrimitiveKind::ALL.len()],
+    /// Bounding boxes tested while traversing acceleration structures.
+    pub bounds_tests: u64,
+    pub max_depth: usize,
+}
+
+impl RayCounters {
+    pub fn total_rays(&self) -> u64 {
+        self.primary_rays + self.secondary_rays + self.shadow_rays
+    }
+
+    pub fn intersection_tests(&self, kind: PrimitiveKind) -> u64 {
+        self.intersection_tests[kind as usize]
+    }
+
+    /// Bounding box and primitive intersection tests, the work of finding hits.
+    pub fn traversal_cost(&self) -> u64 {
+        self.bounds_tests + self.intersection_tests.iter().sum::<u64>()
+    }
+
+    pub fn merge(&mut self, other: &RayCounters) {
+        self.primary_rays += other.primary_rays;
+        self.secondary_rays += other.secondary_rays;
+        self.shadow_rays += other.shadow_rays;
+        for (total, tests) in self
+            .intersection_tests
+            .iter_mut()
+            .zip(other.intersection_tests.iter())
+        {
+            *total += tests;
+        }
+        self.bounds_tests += other.bounds_tests;
+        self.max_depth = self.max_depth.max(other.max_depth);
+    }
+}
+
+thread_local! {
+    static COUNTERS: RefCell<RayCounters> = RefCell::new(RayCounters::default());
+}
+
+pub(crate) fn record<F: FnOnce(&mut RayCounters)>(f: F) {
+    COUNTERS.with(|counters| f(&mut counters.borrow_mut()));
+}
+
+pub(crate) fn count_intersection_test(kind: PrimitiveKind) {
+    record(|counters| counters.intersection_tests[kind as usize] += 1);
+}
+
+/// Returns the counters collected on the current thread and resets them.
+pub fn take_counters() -> RayCounters {
+    COUNTERS.with(|counters| std::mem::take(&mut *counters.borrow_mut()))
+}
+
+/// Runs `f` and returns the counters it recorded, which are also kept in the running
+/// totals of the thread.
+pub(crate) fn measure<R, F: FnOnce() -> R>(f: F) -> (R, RayCounters) {
+    let mut before = take_counters();
+    let result = f();
+    let during = take_counters();
+    before.merge(&during);
+    record(|counters| *counters = before);
+    (result, during)
+}
+
+/// Runs `f` without keeping anything it records, for bookkeeping that is not part of
+/// the render, e.g. the hits for the AOVs.
+pub(crate) fn unrecorded<R, F: FnOnce() -> R>(f: F) -> R {
+    let before = take_counters();
+    let result = f();
+    record(|counters| *counters = before);
+    result
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct TileStats {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    pub time: Duration,
+    pub rays: u64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RenderStats {
+    pub counters: RayCounters,
+    pub tiles: Vec<TileStats>,
+    pub total_time: Duration,
+}
+
+impl RenderStats {
+    pub fn rays_per_second(&self) -> f64 {
+        let seconds = self.total_time.as_secs_f64();
+        if seconds > 0.0 {
+            self.counters.total_rays() as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+
+    pub fn slowest_tile(&self) -> Option<&TileStats> {
+        self.tiles.iter().max_by_key(|tile| tile.time)
+    }
+}
+
+impl fmt::Display for RenderStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let counters = &self.counters;
+        writeln!(
+            f,
+            "Render time:        {:.3} s",
+            self.total_time.as_secs_f64()
+        )?;
+        writeln!(f, "Primary rays:       {}", counters.primary_rays)?;
+        writeln!(f, "Secondary rays:     {}", counters.secondary_rays)?;
+        writeln!(f, "Shadow rays:        {}", counters.shadow_rays)?;
+        writeln!(f, "Rays per second:    {:.0}", self.rays_per_second())?;
+        writeln!(f, "Max depth reached:  {}", counters.max_depth)?;
+        writeln!(f, "Intersection tests:")?;
+        for kind in PrimitiveKind::ALL.iter() {
+            writeln!(
+                f,
+                "  {:<16}  {}",
+                kind.name(),
+                counters.intersection_tests(*kind)
+            )?;
+        }
+        writeln!(f, "Bounds tests:       {}", counters.bounds_tests)?;
+        if !self.tiles.is_empty() {
+            let tile_time: Duration = self.tiles.iter().map(|tile| tile.time).sum();
+            let average = tile_time / self.tiles.len() as u32;
+            writeln!(
+                f,
+                "Tiles:              {} (average {:.2} ms)",
+                self.tiles.len(),
+                average.as_secs_f64() * 1e3
+            )?;
+        }
+        if let Some(tile) = self.slowest_tile() {
+            write!(
+                f,
+                "Slowest tile:       {}x{} at ({}, {}), {:.2} ms, {} rays",
+                tile.width,
+                tile.height,
+                tile.x,
+                tile.y,
+                tile.time.as_secs_f64() * 1e3,
+                tile.rays
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+//! Stereoscopic rendering: a camera per eye and the layouts combining both views.
+
+use crate::camera::Camera;
+use crate::framebuffer::FrameBuffer;
+use crate::primitives::Ray;
+use crate::vec::Vec3f;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Eye {
+    Left,
+    Right,
+}
+
+/// Arrangement of the left and right views in the rendered image.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StereoLayout {
+    /// Left view on the left half of an image twice as wide.
+    SideBySide,
+    /// Left view on the top half of an image twice as tall.
+    TopBottom,
+    /// One image with the red channel of the left view and the green and blue channels of
+    /// the right one, for red/cyan glasses.
+    Anaglyph,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct StereoParams {
+    /// Distance between the two eyes, along the x axis of the camera.
+    pub interocular_distance: f32,
+    /// Distance from the camera at which both views coincide. Closer objects appear in
+    /// front of the screen and farther ones behind it.
+    pub convergence_distance: f32,
+    pub layout: StereoLayout,
+}
+
+impl Default for StereoParams {
+    fn default() -> Self {
+        Self {
+            interocular_distance: 0.4,
+            convergence_distance: 14.0,
+            layout: StereoLayout::SideBySide,
+        }
+    }
+}
+
+/// View of one eye, displaced sideways from a central camera and aimed so that its rays
+/// meet the rays of the central camera at the convergence distance.
+///
+/// Light paths cannot be connected to a stereo camera; see [`Camera::center`].
+pub struct StereoCamera {
+    camera: Box<dyn Camera>,
+    offset: f32,
+    convergence_distance: f32,
+}
+
+impl StereoCamera {
+    pub fn new(camera: Box<dyn Camera>, eye: Eye, params: StereoParams) -> Self {
+        let offset = match eye {
+            Eye::Left => -0.5 * params.interocular_distance,
+            Eye::Right => 0.5 * params.interocular_distance,
+        };
+        Self {
+            camera,
+            offset,
+            convergence_distance: params.convergence_distance,
+        }
+    }
+}
+
+impl Camera for StereoCamera {
+    fn generate_ray(&self, x: f32, y: f32) -> Option<Ray> {
+        let central = self.camera.generate_ray(x, y)?;
+        let target = central.origin + central.direction * self.convergence_distance;
+        let origin = central.origin + Vec3f::new(self.offset, 0.0, 0.0);
+        Some(Ray {
+            origin,
+            direction: (target - origin).normalized(),
+        })
+    }
+}
+
+impl StereoLayout {
+    /// Offset of the right view in the combined image, for views of `width` by `height`.
+    pub fn right_offset(self, width: usize, height: usize) -> (usize, usize) {
+        match self {
+            StereoLayout::SideBySide => (width, 0),
+            StereoLayout::TopBottom => (0, height),
+            StereoLayout::Anaglyph => (0, 0),
+        }
+    }
+
+    /// Combines two views of the same size into one image. Auxiliary buffers of an
+    /// anaglyph are those of the left view.
+    pub fn combine(self, left: &FrameBuffer, right: &FrameBuffer) -> FrameBuffer {
+        let (width, height) = (left.width, left.height);
+        if self == StereoLayout::Anaglyph {
+            let mut frame = left.clone();
+            for (color, right) in frame.color.iter_mut().zip(&right.color) {
+                *color = Vec3f::new(color[0], right[1], right[2]);
+            }
+            return frame;
+        }
+        let (dx, dy) = self.right_offset(width, height);
+        let mut frame = FrameBuffer::new(width + dx, height + dy);
+        copy_view(&mut frame, left, 0, 0);
+        copy_view(&mut frame, right, dx, dy);
+        frame
+    }
+}
+
+/// Copies every buffer of `view` into `frame` with its top-left corner at `(x0, y0)`.
+fn copy_view(frame: &mut FrameBuffer, view: &FrameBuffer, x0: usize, y0: usize) {
+    for y in 0..view.height {
+        let source = y * view.width;
+        let target = (y + y0) * frame.width + x0;
+        let row = source..source + view.width;
+        let target_row = target..target + view.width;
+        frame.color[target_row.clone()].copy_from_slice(&view.color[row.clone()]);
+        frame.normal[target_row.clone()].copy_from_slice(&view.normal[row.clone()]);
+        frame.albedo[target_row.clone()].copy_from_slice(&view.albedo[row.clone()]);
+        frame.depth[target_row.clone()].copy_from_slice(&view.depth[row.clone()]);
+        frame.samples[target_row].copy_from_slice(&view.samples[row]);
+    }
+}
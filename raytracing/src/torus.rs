@@ -0,0 +1,107 @@
+use crate::aabb::Aabb;
+use crate::error::{check_finite, check_positive, GeometryError};
+use crate::polynomial::solve_quartic;
+use crate::primitives::{MaterialId, Ray};
+use crate::scene::{
+    nearest_in_range, spans_from_crossings, Hit, Primitive, RayIntersection, Renderable, Span,
+};
+use crate::stats::{self, PrimitiveKind};
+use crate::vec::{Float, Vec3};
+
+/// Torus lying in the horizontal plane through `center`, with the tube of `minor_radius`
+/// going around a circle of `major_radius`.
+#[derive(Copy, Clone, Debug)]
+pub struct Torus<T = f32> {
+    pub center: Vec3<T>,
+    pub major_radius: T,
+    pub minor_radius: T,
+    pub material: MaterialId,
+}
+
+impl<T: Float> Torus<T> {
+    /// Sorted distances at which the ray's line crosses the torus, if the line passes
+    /// through the bounding box within `[t_min, t_max]`.
+    fn crossings(&self, ray: Ray<T>, t_min: T, t_max: T) -> Vec<T> {
+        stats::count_intersection_test(PrimitiveKind::Torus);
+        let bounds = self.bounds().unwrap();
+        if !bounds.hit(ray, t_min, t_max) {
+            return Vec::new();
+        }
+
+        let o = (ray.origin - self.center).cast::<f64>();
+        let d = ray.direction.cast::<f64>();
+        let (ox, oy, oz) = (o[0], o[1], o[2]);
+        let (dx, dy, dz) = (d[0], d[1], d[2]);
+        let major_sqr = self.major_radius.cast::<f64>().powi(2);
+        let minor_sqr = self.minor_radius.cast::<f64>().powi(2);
+
+        // (|p|^2 + R^2 - r^2)^2 = 4 R^2 (x^2 + z^2), expanded in t.
+        let dd = dx * dx + dy * dy + dz * dz;
+        let od = ox * dx + oy * dy + oz * dz;
+        let e = ox * ox + oy * oy + oz * oz - major_sqr - minor_sqr;
+        let c4 = dd * dd;
+        let c3 = 4.0 * dd * od;
+        let c2 = 2.0 * dd * e + 4.0 * od * od + 4.0 * major_sqr * dy * dy;
+        let c1 = 4.0 * od * e + 8.0 * major_sqr * oy * dy;
+        let c0 = e * e - 4.0 * major_sqr * (minor_sqr - oy * oy);
+        solve_quartic(c3 / c4, c2 / c4, c1 / c4, c0 / c4)
+            .into_iter()
+            .map(|t| t.cast())
+            .collect()
+    }
+}
+
+impl<T: Float> Renderable<T> for Torus<T> {
+    fn ray_intersect(&self, ray: Ray<T>, t_min: T, t_max: T) -> Option<Hit<'_, T>> {
+        nearest_in_range(self.crossings(ray, t_min, t_max), t_min, t_max)
+            .map(|distance| Hit::new(distance, self))
+    }
+
+    fn spans(&self, ray: Ray<T>) -> Vec<Span<'_, T>> {
+        spans_from_crossings(
+            ray,
+            &self.crossings(ray, T::NEG_INFINITY, T::INFINITY),
+            self,
+        )
+    }
+
+    fn bounds(&self) -> Option<Aabb<T>> {
+        let outer = self.major_radius + self.minor_radius;
+        let extent = Vec3::new(outer, self.minor_radius, outer);
+        Some(Aabb::new(self.center - extent, self.center + extent))
+    }
+
+    fn materials(&self) -> Vec<MaterialId> {
+        vec![self.material]
+    }
+
+    fn validate(&self) -> Result<(), GeometryError> {
+        check_finite(&[self.center])?;
+        check_positive("major radius", self.major_radius)?;
+        check_positive("minor radius", self.minor_radius)
+    }
+}
+
+impl<T: Float> Primitive<T> for Torus<T> {
+    fn surface(&self, ray: Ray<T>, distance: T) -> RayIntersection<T> {
+        let hit = ray.origin + ray.direction * distance;
+        let local = hit - self.center;
+        let radial = (local[0] * local[0] + local[2] * local[2]).sqrt();
+        let ring_point = if radial > T::ZERO {
+            Vec3::new(local[0], T::ZERO, local[2]) * (self.major_radius / radial)
+        } else {
+            Vec3::new(self.major_radius, T::ZERO, T::ZERO)
+        };
+        let (half, two_pi) = (0.5f32.cast::<T>(), T::PI + T::PI);
+        RayIntersection {
+            distance,
+            hit,
+            normal: (local - ring_point).normalized(),
+            uv: (
+                half + local[2].atan2(local[0]) / two_pi,
+                half + local[1].atan2(radial - self.major_radius) / two_pi,
+            ),
+            material: self.material,
+        }
+    }
+}
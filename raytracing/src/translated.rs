@@ -0,0 +1,150 @@
+use crate::aabb::Aabb;
+use crate::error::{check_finite, GeometryError};
+use crate::primitives::{MaterialId, Ray};
+use crate::scene::{Emitter, Hit, Primitive, RayIntersection, Renderable, Span, SurfaceSample};
+use crate::stats;
+use crate::vec::{Float, Vec3};
+
+/// Object moved by `offset` from where it is defined, e.g. to animate its position.
+///
+/// If the object is an area light, so is the translated object, with its samples moved
+/// along.
+pub struct Translated<T: Float = f32> {
+    pub object: Box<dyn Renderable<T>>,
+    pub offset: Vec3<T>,
+}
+
+impl<T: Float> Translated<T> {
+    fn local_ray(&self, ray: Ray<T>) -> Ray<T> {
+        Ray {
+            origin: ray.origin - self.offset,
+            direction: ray.direction,
+        }
+    }
+
+    fn translate_hit<'a>(&self, hit: Hit<'a, T>) -> Hit<'a, T> {
+        Hit {
+            offset: hit.offset + self.offset,
+            ..hit
+        }
+    }
+
+    /// The object as an area light, for the [`Emitter`] implementation, which is only
+    /// handed out by [`Renderable::emitter`] if the object has one.
+    fn inner_emitter(&self) -> &dyn Emitter<T> {
+        self.object
+            .emitter()
+            .expect("translated object is not an emitter")
+    }
+
+    fn translate_sample(&self, sample: SurfaceSample<T>) -> SurfaceSample<T> {
+        SurfaceSample {
+            point: sample.point + self.offset,
+            ..sample
+        }
+    }
+}
+
+impl<T: Float> Renderable<T> for Translated<T> {
+    fn ray_intersect(&self, ray: Ray<T>, t_min: T, t_max: T) -> Option<Hit<'_, T>> {
+        let hit = self
+            .object
+            .ray_intersect(self.local_ray(ray), t_min, t_max)?;
+        Some(self.translate_hit(hit))
+    }
+
+    fn occluded(&self, ray: Ray<T>, max_dist: T) -> bool {
+        self.object.occluded(self.local_ray(ray), max_dist)
+    }
+
+    fn bounds(&self) -> Option<Aabb<T>> {
+        let bounds = self.object.bounds()?;
+        Some(Aabb::new(
+            bounds.min + self.offset,
+            bounds.max + self.offset,
+        ))
+    }
+
+    fn spans(&self, ray: Ray<T>) -> Vec<Span<'_, T>> {
+        self.object
+            .spans(self.local_ray(ray))
+            .into_iter()
+            .map(|span| Span {
+                enter: self.translate_hit(span.enter),
+                exit: self.translate_hit(span.exit),
+            })
+            .collect()
+    }
+
+    fn emitter(&self) -> Option<&dyn Emitter<T>> {
+        self.object.emitter().map(|_| self as &dyn Emitter<T>)
+    }
+
+    fn materials(&self) -> Vec<MaterialId> {
+        self.object.materials()
+    }
+
+    fn validate(&self) -> Result<(), GeometryError> {
+        check_finite(&[self.offset])?;
+        self.object.validate()
+    }
+}
+
+impl<T: Float> Primitive<T> for Translated<T> {
+    /// Surface of the primitive of the object found again at `distance`, since hits of the
+    /// object only carry it in their [`Hit`]. The point on the ray if nothing is found there.
+    fn surface(&self, ray: Ray<T>, distance: T) -> RayIntersection<T> {
+        let local = self.local_ray(ray);
+        let slack = SURFACE_SLACK.cast::<T>() * distance.abs().max(T::ONE);
+        // Finding the primitive again is not one of the rays of the render.
+        let hit = stats::unrecorded(|| {
+            self.object
+                .ray_intersect(local, distance - slack, T::INFINITY)
+        });
+        let mut intersection = match hit {
+            Some(hit) => Hit { distance, ..hit }.surface(local),
+            None => RayIntersection {
+                distance,
+                hit: local.origin + local.direction * distance,
+                ..RayIntersection::default()
+            },
+        };
+        intersection.hit = intersection.hit + self.offset;
+        intersection
+    }
+}
+
+/// Relative distance before the given one at which [`Translated::surface`] looks for the
+/// primitive, so that the hit is found despite rounding.
+const SURFACE_SLACK: f32 = 1e-4;
+
+impl<T: Float> Emitter<T> for Translated<T> {
+    fn material(&self) -> MaterialId {
+        self.inner_emitter().material()
+    }
+
+    fn area(&self) -> f32 {
+        self.inner_emitter().area()
+    }
+
+    fn sample_surface(&self, u: f32, v: f32) -> SurfaceSample<T> {
+        self.translate_sample(self.inner_emitter().sample_surface(u, v))
+    }
+
+    fn sample_from(&self, reference: Vec3<T>, u: f32, v: f32) -> Option<(SurfaceSample<T>, f32)> {
+        let (sample, pdf) = self
+            .inner_emitter()
+            .sample_from(reference - self.offset, u, v)?;
+        Some((self.translate_sample(sample), pdf))
+    }
+
+    fn is_primitive(&self, primitive: &dyn Primitive<T>) -> bool {
+        // Hits of the object keep the primitive, the offset is carried by `Hit`.
+        self.inner_emitter().is_primitive(primitive)
+    }
+
+    fn pdf_from(&self, reference: Vec3<T>, point: Vec3<T>, normal: Vec3<T>) -> f32 {
+        self.inner_emitter()
+            .pdf_from(reference - self.offset, point - self.offset, normal)
+    }
+}
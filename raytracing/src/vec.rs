@@ -1,27 +1,159 @@
-use std::ops::{Add, Index, Mul, Sub};
+use std::fmt::Debug;
+use std::ops::{Add, AddAssign, Div, DivAssign, Index, Mul, MulAssign, Neg, Sub, SubAssign};
+
+mod private {
+    /// Conversions [`Float::cast`](super::Float::cast) is built on. Sealing them away also
+    /// keeps `f32` and `f64` the only precisions.
+    pub trait Sealed {
+        fn from_f32(value: f32) -> Self;
+        fn from_f64(value: f64) -> Self;
+    }
+}
+
+/// Floating point type the geometry of a scene is computed in.
+///
+/// Rays, hits, primitives, scenes and cameras are generic over it and default to `f32`. In
+/// `f64` points far from the origin keep enough precision for secondary rays to leave the
+/// surfaces they start from. Colors, materials and scattering stay in `f32`; integrators
+/// round directions and normals to it for shading only.
+pub trait Float:
+    private::Sealed
+    + Copy
+    + Default
+    + Debug
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + DivAssign
+    + 'static
+{
+    const ZERO: Self;
+    const ONE: Self;
+    const PI: Self;
+    const INFINITY: Self;
+    const NEG_INFINITY: Self;
+
+    /// The same value in precision `U`, rounded if it is a lower one.
+    fn cast<U: Float>(self) -> U;
+
+    fn abs(self) -> Self;
+    fn min(self, other: Self) -> Self;
+    fn max(self, other: Self) -> Self;
+    fn clamp(self, min: Self, max: Self) -> Self;
+    fn floor(self) -> Self;
+    fn signum(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn powi(self, exponent: i32) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn asin(self) -> Self;
+    fn acos(self) -> Self;
+    fn atan2(self, other: Self) -> Self;
+    fn is_finite(self) -> bool;
+}
+
+macro_rules! impl_float {
+    ($float:ident, $from:ident) => {
+        impl private::Sealed for $float {
+            fn from_f32(value: f32) -> Self {
+                value as $float
+            }
+            fn from_f64(value: f64) -> Self {
+                value as $float
+            }
+        }
+
+        impl Float for $float {
+            const ZERO: Self = 0.0;
+            const ONE: Self = 1.0;
+            const PI: Self = std::$float::consts::PI;
+            const INFINITY: Self = $float::INFINITY;
+            const NEG_INFINITY: Self = $float::NEG_INFINITY;
+
+            fn cast<U: Float>(self) -> U {
+                U::$from(self)
+            }
+
+            fn abs(self) -> Self {
+                $float::abs(self)
+            }
+            fn min(self, other: Self) -> Self {
+                $float::min(self, other)
+            }
+            fn max(self, other: Self) -> Self {
+                $float::max(self, other)
+            }
+            fn clamp(self, min: Self, max: Self) -> Self {
+                $float::clamp(self, min, max)
+            }
+            fn floor(self) -> Self {
+                $float::floor(self)
+            }
+            fn signum(self) -> Self {
+                $float::signum(self)
+            }
+            fn sqrt(self) -> Self {
+                $float::sqrt(self)
+            }
+            fn powi(self, exponent: i32) -> Self {
+                $float::powi(self, exponent)
+            }
+            fn sin(self) -> Self {
+                $float::sin(self)
+            }
+            fn cos(self) -> Self {
+                $float::cos(self)
+            }
+            fn asin(self) -> Self {
+                $float::asin(self)
+            }
+            fn acos(self) -> Self {
+                $float::acos(self)
+            }
+            fn atan2(self, other: Self) -> Self {
+                $float::atan2(self, other)
+            }
+            fn is_finite(self) -> bool {
+                $float::is_finite(self)
+            }
+        }
+    };
+}
+
+impl_float!(f32, from_f32);
+impl_float!(f64, from_f64);
 
 #[derive(Copy, Clone, Debug)]
-pub struct Vec3f {
-    pub coordinates: [f32; 3],
+pub struct Vec3<T = f32> {
+    pub coordinates: [T; 3],
 }
 
-impl Default for Vec3f {
+pub type Vec3f = Vec3<f32>;
+pub type Vec3d = Vec3<f64>;
+
+impl<T: Float> Default for Vec3<T> {
     fn default() -> Self {
         Self {
-            coordinates: [0.0; 3],
+            coordinates: [T::ZERO; 3],
         }
     }
 }
 
-impl Index<usize> for Vec3f {
-    type Output = f32;
+impl<T> Index<usize> for Vec3<T> {
+    type Output = T;
 
     fn index(&self, index: usize) -> &Self::Output {
         &self.coordinates[index]
     }
 }
 
-impl Add for Vec3f {
+impl<T: Float> Add for Vec3<T> {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self::Output {
@@ -33,7 +165,7 @@ impl Add for Vec3f {
     }
 }
 
-impl Sub for Vec3f {
+impl<T: Float> Sub for Vec3<T> {
     type Output = Self;
 
     fn sub(self, rhs: Self) -> Self::Output {
@@ -45,11 +177,11 @@ impl Sub for Vec3f {
     }
 }
 
-impl Mul for Vec3f {
-    type Output = f32;
+impl<T: Float> Mul for Vec3<T> {
+    type Output = T;
 
     fn mul(self, rhs: Self) -> Self::Output {
-        let mut res = 0.0;
+        let mut res = T::ZERO;
         for i in 0..self.coordinates.len() {
             res += self.coordinates[i] * rhs.coordinates[i];
         }
@@ -57,10 +189,10 @@ impl Mul for Vec3f {
     }
 }
 
-impl Mul<f32> for Vec3f {
+impl<T: Float> Mul<T> for Vec3<T> {
     type Output = Self;
 
-    fn mul(self, rhs: f32) -> Self::Output {
+    fn mul(self, rhs: T) -> Self::Output {
         let mut res = Self::default();
         for i in 0..self.coordinates.len() {
             res.coordinates[i] = self.coordinates[i] * rhs;
@@ -69,7 +201,7 @@ impl Mul<f32> for Vec3f {
     }
 }
 
-impl Vec3f {
+impl<T: Float> Vec3<T> {
     pub fn normalize(&mut self) {
         let norm = self.norm();
         for coord in &mut self.coordinates {
@@ -80,12 +212,27 @@ impl Vec3f {
         self.normalize();
         self
     }
-    pub fn norm(&self) -> f32 {
+    pub fn norm(&self) -> T {
         (*self * *self).sqrt()
     }
-    pub fn new(x: f32, y: f32, z: f32) -> Self {
+    pub fn cross(&self, rhs: Self) -> Self {
+        Self::new(
+            self[1] * rhs[2] - self[2] * rhs[1],
+            self[2] * rhs[0] - self[0] * rhs[2],
+            self[0] * rhs[1] - self[1] * rhs[0],
+        )
+    }
+    /// Component-wise product, e.g. to filter a color by another one.
+    pub fn component_mul(&self, rhs: Self) -> Self {
+        Self::new(self[0] * rhs[0], self[1] * rhs[1], self[2] * rhs[2])
+    }
+    pub fn new(x: T, y: T, z: T) -> Self {
         Self {
             coordinates: [x, y, z],
         }
     }
+    /// The same vector in another precision, rounded if it is a lower one.
+    pub fn cast<U: Float>(self) -> Vec3<U> {
+        Vec3::new(self[0].cast(), self[1].cast(), self[2].cast())
+    }
 }
@@ -0,0 +1,286 @@
+use raytracing::animation::{Animated, Animation, Interpolation, Track};
+use raytracing::camera::{Camera, CameraPose, PerspectiveCamera, PosedCamera};
+use raytracing::cuboid::Cuboid;
+use raytracing::demo::demo_scene;
+use raytracing::error::RenderError;
+use raytracing::framebuffer::FrameBuffer;
+use raytracing::primitives::{Material, MaterialId, Ray};
+use raytracing::quad::Quad;
+use raytracing::render::{render_animation_frame, render_frame, Integrator, RenderParams};
+use raytracing::scene::{Primitive, Renderable, Scene};
+use raytracing::sphere::Sphere;
+use raytracing::translated::Translated;
+use raytracing::vec::Vec3f;
+
+#[test]
+fn tracks_pass_through_keys_and_hold_outside() {
+    let keys = vec![(2.0, 4.0), (0.0, 0.0), (1.0, 1.0)];
+    for &interpolation in [Interpolation::Linear, Interpolation::Smooth].iter() {
+        let track = Track::new(interpolation, keys.clone());
+        for &(time, value) in keys.iter() {
+            assert!((track.sample(time) - value).abs() < 1e-6);
+        }
+        assert_eq!(track.sample(-1.0), 0.0);
+        assert_eq!(track.sample(3.0), 4.0);
+    }
+    let linear = Track::new(Interpolation::Linear, keys.clone());
+    assert!((linear.sample(0.5) - 0.5).abs() < 1e-6);
+    assert!((linear.sample(1.25) - 1.75).abs() < 1e-6);
+
+    // Catmull-Rom tangent at the middle key is (4 - 0) / 2, zero at the ends.
+    let smooth = Track::new(Interpolation::Smooth, keys);
+    let slope = (smooth.sample(1.001) - smooth.sample(0.999)) / 0.002;
+    assert!((slope - 2.0).abs() < 1e-2);
+    let slope = (smooth.sample(0.001) - smooth.sample(0.0)) / 0.001;
+    assert!(slope.abs() < 1e-2);
+}
+
+#[test]
+fn animation_sets_scene_and_camera() {
+    let animation = Animation {
+        frame_rate: 10.0,
+        tracks: vec![
+            Animated::CameraPosition(Track::new(
+                Interpolation::Linear,
+                vec![
+                    (0.0, Vec3f::new(0.0, 0.0, 0.0)),
+                    (1.0, Vec3f::new(2.0, 0.0, 0.0)),
+                ],
+            )),
+            Animated::LightIntensity(1, Track::new(Interpolation::Linear, vec![(0.0, 0.0)])),
+            Animated::RefractiveIndex(
+                MaterialId(1),
+                Track::new(Interpolation::Linear, vec![(0.0, 1.0), (1.0, 2.0)]),
+            ),
+        ],
+    };
+    let mut scene = demo_scene();
+    let mut params = RenderParams::default();
+    animation
+        .apply(animation.frame_time(5), &mut scene, &mut params)
+        .unwrap();
+    assert!((params.pose.position[0] - 1.0).abs() < 1e-6);
+    assert_eq!(scene.lights[1].intensity, 0.0);
+    assert!((scene.materials[1].refractive_index - 1.5).abs() < 1e-6);
+}
+
+#[test]
+fn offset_objects_render_like_moved_ones() {
+    let params = RenderParams {
+        width: 40,
+        height: 30,
+        ..RenderParams::default()
+    };
+    let offset = Vec3f::new(0.5, 1.5, -1.0);
+    let animation = Animation {
+        frame_rate: 1.0,
+        tracks: vec![Animated::ObjectOffset(
+            1,
+            Track::new(Interpolation::Linear, vec![(0.0, offset)]),
+        )],
+    };
+    let mut animated = demo_scene();
+    let mut animated_params = params;
+    animation
+        .apply(0.0, &mut animated, &mut animated_params)
+        .unwrap();
+
+    let mut moved = demo_scene();
+    moved.objects[1] = Box::new(Sphere {
+        center: Vec3f::new(-1.0, -1.5, -12.0) + offset,
+        radius: 2.0,
+        material: MaterialId(1),
+    });
+
+    let animated = render_frame(&animated, animated_params);
+    let moved = render_frame(&moved, params);
+    let mut total_difference = 0.0;
+    for (a, b) in animated.color.iter().zip(&moved.color) {
+        let difference = *a - *b;
+        total_difference += (difference * difference).sqrt();
+    }
+    assert!(total_difference / (40.0 * 30.0) < 1e-3);
+}
+
+#[test]
+fn tracks_of_missing_targets_are_rejected() {
+    let still = Track::new(Interpolation::Linear, vec![(0.0, Vec3f::default())]);
+    let dim = Track::new(Interpolation::Linear, vec![(0.0, 0.0)]);
+    let scene = demo_scene();
+    let missing = vec![
+        Animated::ObjectOffset(scene.objects.len(), still.clone()),
+        Animated::LightIntensity(scene.lights.len(), dim.clone()),
+        Animated::Emission(MaterialId(scene.materials.len()), still.clone()),
+    ];
+    for track in missing {
+        let animation = Animation {
+            frame_rate: 1.0,
+            tracks: vec![Animated::FieldOfView(dim.clone()), track],
+        };
+        let mut scene = demo_scene();
+        let mut params = RenderParams::default();
+        assert!(matches!(
+            animation.apply(0.0, &mut scene, &mut params),
+            Err(RenderError::UnknownTrackTarget(1))
+        ));
+        // Nothing is applied.
+        assert_eq!(params.field_of_view, RenderParams::<f32>::default().field_of_view);
+        assert!(matches!(
+            render_animation_frame(demo_scene, &animation, params, 0),
+            Err(RenderError::UnknownTrackTarget(1))
+        ));
+    }
+}
+
+#[test]
+fn offset_objects_keep_their_surface() {
+    let offset = Vec3f::new(2.0, -1.0, 0.5);
+    let cuboid = Cuboid {
+        min: Vec3f::new(-1.0, -1.0, -6.0),
+        max: Vec3f::new(1.0, 1.0, -4.0),
+        material: MaterialId(1),
+    };
+    let translated = Translated {
+        object: Box::new(cuboid),
+        offset,
+    };
+    let moved = Cuboid {
+        min: cuboid.min + offset,
+        max: cuboid.max + offset,
+        ..cuboid
+    };
+    let ray = Ray {
+        origin: Vec3f::new(0.0, 0.0, 0.0),
+        direction: Vec3f::new(2.5, -0.5, -4.5).normalized(),
+    };
+    let distance = moved
+        .ray_intersect(ray, 0.0, f32::INFINITY)
+        .unwrap()
+        .distance;
+    let expected = moved.surface(ray, distance);
+    let surface = translated.surface(ray, distance);
+    let miss = surface.hit - expected.hit;
+    assert!(miss * miss < 1e-10);
+    assert_eq!(surface.normal.coordinates, expected.normal.coordinates);
+    assert_eq!(surface.material, expected.material);
+}
+
+/// Floor lit by a glowing sphere at `center`.
+fn lamp_scene(center: Vec3f) -> Scene {
+    let mut scene = Scene::default();
+    let floor = scene.add_material(Material {
+        diffuse_color: Vec3f::new(0.7, 0.7, 0.7),
+        ..Material::default()
+    });
+    let lamp = scene.add_material(Material {
+        emission: Vec3f::new(8.0, 6.0, 4.0),
+        ..Material::default()
+    });
+    scene.objects.push(Box::new(Quad {
+        corner: Vec3f::new(-20.0, -3.0, 0.0),
+        edge_u: Vec3f::new(40.0, 0.0, 0.0),
+        edge_v: Vec3f::new(0.0, 0.0, -40.0),
+        material: floor,
+    }));
+    scene.objects.push(Box::new(Sphere {
+        center,
+        radius: 1.0,
+        material: lamp,
+    }));
+    scene
+}
+
+#[test]
+fn offset_emitters_stay_area_lights() {
+    let params = RenderParams {
+        width: 40,
+        height: 30,
+        samples_per_pixel: 4,
+        depth: 3,
+        integrator: Integrator::PathTracing,
+        ..RenderParams::default()
+    };
+    let center = Vec3f::new(-2.0, 0.0, -12.0);
+    let offset = Vec3f::new(3.0, -1.0, 1.0);
+    let animation = Animation {
+        frame_rate: 1.0,
+        tracks: vec![Animated::ObjectOffset(
+            1,
+            Track::new(Interpolation::Linear, vec![(0.0, offset)]),
+        )],
+    };
+    let mut animated = lamp_scene(center);
+    let mut animated_params = params;
+    animation
+        .apply(0.0, &mut animated, &mut animated_params)
+        .unwrap();
+    assert_eq!(animated.emitters().len(), 1);
+
+    let moved = lamp_scene(center + offset);
+    for &integrator in &[Integrator::PathTracing, Integrator::Bidirectional] {
+        let params = RenderParams {
+            integrator,
+            ..params
+        };
+        let animated = render_frame(&animated, params);
+        let moved = render_frame(&moved, params);
+        for (a, b) in animated.color.iter().zip(&moved.color) {
+            let difference = *a - *b;
+            assert!(
+                difference * difference < 1e-6,
+                "{:?}: {:?} vs {:?}",
+                integrator,
+                a,
+                b
+            );
+        }
+    }
+}
+
+#[test]
+fn posed_camera_looks_at_target() {
+    let pose = CameraPose {
+        position: Vec3f::new(1.0, 2.0, 3.0),
+        target: Vec3f::new(4.0, 2.0, -1.0),
+    };
+    let camera = PosedCamera::new(Box::new(PerspectiveCamera::new(1.0, 64, 48)), pose);
+    let ray = camera.generate_ray(32.0, 24.0).unwrap();
+    let forward = (pose.target - pose.position).normalized();
+    let miss = ray.direction - forward;
+    assert!(miss * miss < 1e-8);
+    let center = camera.center().unwrap() - pose.position;
+    assert!(center * center < 1e-10);
+
+    // Points above the center of the image are above the camera.
+    let up = camera.generate_ray(32.0, 4.0).unwrap();
+    assert!(up.direction[1] > 0.0);
+    let (x, y) = camera.project(up.direction).unwrap();
+    assert!((x - 32.0).abs() < 1e-3 && (y - 4.0).abs() < 1e-3);
+}
+
+#[test]
+fn png_holds_image_chunks() {
+    let mut frame = FrameBuffer::new(3, 2);
+    frame.color[4] = Vec3f::new(1.0, 0.5, 0.0);
+    let mut png = Vec::new();
+    frame.write_png(&mut png).unwrap();
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+
+    let mut chunks = Vec::new();
+    let mut rest = &png[8..];
+    while !rest.is_empty() {
+        let length = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+        chunks.push((rest[4..8].to_vec(), rest[8..8 + length].to_vec()));
+        rest = &rest[12 + length..];
+    }
+    let kinds: Vec<&[u8]> = chunks.iter().map(|(kind, _)| kind.as_slice()).collect();
+    assert_eq!(kinds, [&b"IHDR"[..], b"IDAT", b"IEND"]);
+    assert_eq!(chunks[0].1, [0, 0, 0, 3, 0, 0, 0, 2, 8, 2, 0, 0, 0]);
+
+    // A single stored deflate block of two filtered scanlines.
+    let idat = &chunks[1].1;
+    assert_eq!(&idat[..3], [0x78, 0x01, 1]);
+    let scanlines = &idat[7..idat.len() - 4];
+    assert_eq!(scanlines.len(), 2 * (1 + 3 * 3));
+    assert_eq!(&scanlines[10..], [0, 0, 0, 0, 255, 127, 0, 0, 0, 0]);
+}
@@ -0,0 +1,62 @@
+use raytracing::bvh::Bvh;
+use raytracing::checkerboard::Checkerboard;
+use raytracing::primitives::{MaterialId, Ray};
+use raytracing::random::Rng;
+use raytracing::scene::{Renderable, RAY_EPSILON};
+use raytracing::sphere::Sphere;
+use raytracing::vec::Vec3f;
+
+fn sphere_grid() -> Vec<Box<dyn Renderable>> {
+    let mut objects: Vec<Box<dyn Renderable>> = Vec::new();
+    for i in 0..6 {
+        for j in 0..6 {
+            objects.push(Box::new(Sphere {
+                center: Vec3f::new(i as f32 * 3.0 - 7.5, j as f32 * 2.0 - 3.0, -15.0 - j as f32),
+                radius: 0.5 + 0.1 * ((i + j) % 4) as f32,
+                material: MaterialId::default(),
+            }));
+        }
+    }
+    objects.push(Box::new(Checkerboard::default()));
+    objects
+}
+
+fn random_ray(rng: &mut Rng) -> Ray {
+    let origin = Vec3f::new(
+        rng.next_f32() * 10.0 - 5.0,
+        rng.next_f32() * 6.0 - 3.0,
+        rng.next_f32() * 4.0,
+    );
+    let target = Vec3f::new(
+        rng.next_f32() * 20.0 - 10.0,
+        rng.next_f32() * 12.0 - 6.0,
+        -20.0 + rng.next_f32() * 10.0,
+    );
+    Ray {
+        origin,
+        direction: (target - origin).normalized(),
+    }
+}
+
+#[test]
+fn bvh_matches_linear_search() {
+    let linear = sphere_grid();
+    let bvh = Bvh::new(sphere_grid());
+    let mut rng = Rng::new(7);
+    for _ in 0..2000 {
+        let ray = random_ray(&mut rng);
+        let expected = linear.ray_intersect(ray, 0.0, f32::INFINITY);
+        let actual = bvh.ray_intersect(ray, 0.0, f32::INFINITY);
+        assert_eq!(
+            expected.map(|hit| hit.distance),
+            actual.map(|hit| hit.distance)
+        );
+
+        let max_dist = rng.next_f32() * 25.0;
+        assert_eq!(linear.occluded(ray, max_dist), bvh.occluded(ray, max_dist));
+        assert_eq!(
+            linear.occluded(ray, max_dist),
+            linear.ray_intersect(ray, RAY_EPSILON, max_dist).is_some()
+        );
+    }
+}
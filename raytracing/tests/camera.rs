@@ -0,0 +1,59 @@
+use raytracing::camera::{
+    Camera, EquirectangularCamera, FisheyeCamera, OrthographicCamera, PerspectiveCamera,
+};
+use raytracing::random::Rng;
+use raytracing::sampling::uniform_sphere;
+
+/// Cameras whose rays leave a single point, with the fraction of the image they cover.
+fn centered_cameras() -> Vec<(Box<dyn Camera>, f64)> {
+    let circle = std::f64::consts::PI * 16.0 * 16.0 / (64.0 * 32.0);
+    vec![
+        (Box::new(PerspectiveCamera::new(1.2, 64, 32)), 1.0),
+        (Box::new(FisheyeCamera::new(3.0, 64, 32)), circle),
+        (Box::new(EquirectangularCamera::new(64, 32)), 1.0),
+    ]
+}
+
+#[test]
+fn projection_inverts_camera_rays() {
+    for (camera, _) in centered_cameras() {
+        let center = camera.center().unwrap();
+        for &(x, y) in [(31.5, 15.5), (3.25, 30.0), (60.0, 7.75), (20.0, 20.5)].iter() {
+            let ray = match camera.generate_ray(x, y) {
+                Some(ray) => ray,
+                None => continue,
+            };
+            let offset = ray.origin - center;
+            assert_eq!(offset * offset, 0.0);
+            let (px, py) = camera.project(ray.direction * 3.0).unwrap();
+            assert!((px - x).abs() < 1e-2 && (py - y).abs() < 1e-2);
+        }
+    }
+}
+
+#[test]
+fn camera_ray_densities_integrate_to_covered_fraction() {
+    let mut rng = Rng::new(5);
+    for (camera, covered) in centered_cameras() {
+        let count = 200_000;
+        let mut sum = 0.0;
+        for _ in 0..count {
+            let direction = uniform_sphere(rng.next_f32(), rng.next_f32());
+            sum += camera.pdf(direction) as f64;
+        }
+        let integral = sum * 4.0 * std::f64::consts::PI / count as f64;
+        assert!((integral - covered).abs() < 0.03, "{}", integral);
+    }
+}
+
+#[test]
+fn orthographic_rays_are_parallel() {
+    let camera = OrthographicCamera::new(4.0, 40, 20);
+    let top_left = camera.generate_ray(0.0, 0.0).unwrap();
+    let bottom_right = camera.generate_ray(40.0, 20.0).unwrap();
+    let turn = top_left.direction - bottom_right.direction;
+    assert_eq!(turn * turn, 0.0);
+    let span = bottom_right.origin - top_left.origin;
+    assert!((span[0] - 8.0).abs() < 1e-5 && (span[1] + 4.0).abs() < 1e-5);
+    assert!(camera.center().is_none());
+}
@@ -0,0 +1,121 @@
+use raytracing::bvh::Bvh;
+use raytracing::debug::DebugView;
+use raytracing::demo::demo_scene;
+use raytracing::primitives::{Material, Ray};
+use raytracing::render::{render_frame, RenderParams};
+use raytracing::scene::{Renderable, Scene};
+use raytracing::sphere::Sphere;
+use raytracing::vec::Vec3f;
+
+fn close(a: Vec3f, b: Vec3f) -> bool {
+    let difference = a - b;
+    difference * difference < 1e-8
+}
+
+fn forward(x: f32, y: f32) -> Ray {
+    Ray {
+        origin: Vec3f::new(x, y, 0.0),
+        direction: Vec3f::new(0.0, 0.0, -1.0),
+    }
+}
+
+#[test]
+fn surface_views_show_first_hit() {
+    let mut scene = Scene::default();
+    let first = scene.add_material(Material::default());
+    let second = scene.add_material(Material::default());
+    scene.objects.push(Box::new(Sphere {
+        center: Vec3f::new(0.0, 0.0, -10.0),
+        radius: 2.0,
+        material: first,
+    }));
+    scene.objects.push(Box::new(Sphere {
+        center: Vec3f::new(5.0, 0.0, -10.0),
+        radius: 2.0,
+        material: second,
+    }));
+    let ray = forward(0.0, 0.0);
+    let normal = DebugView::Normals.shade(&scene, ray, 4);
+    assert!(close(normal, Vec3f::new(0.5, 0.5, 1.0)));
+    let depth = DebugView::Depth { far: 16.0 }.shade(&scene, ray, 4);
+    assert!(close(depth, Vec3f::new(0.5, 0.5, 0.5)));
+
+    let first = DebugView::MaterialId.shade(&scene, ray, 4);
+    let second = DebugView::MaterialId.shade(&scene, forward(5.0, 0.0), 4);
+    assert!(!close(first, second));
+    assert!(close(
+        first,
+        DebugView::MaterialId.shade(&scene, forward(0.5, 0.5), 4)
+    ));
+    let miss = DebugView::MaterialId.shade(&scene, forward(0.0, 5.0), 4);
+    assert!(close(miss, Vec3f::default()));
+}
+
+#[test]
+fn debug_views_ignore_lighting() {
+    let params = RenderParams {
+        width: 32,
+        height: 24,
+        ..RenderParams::default()
+    };
+    let mut dark = demo_scene();
+    dark.lights.clear();
+    dark.background_color = Vec3f::default();
+    for &view in [DebugView::UvChecker, DebugView::RecursionDepth].iter() {
+        let params = RenderParams {
+            debug_view: Some(view),
+            ..params
+        };
+        let lit = render_frame(&demo_scene(), params);
+        let unlit = render_frame(&dark, params);
+        for (lit, unlit) in lit.color.iter().zip(&unlit.color) {
+            assert!(close(*lit, *unlit));
+        }
+    }
+}
+
+#[test]
+fn recursion_follows_reflective_materials() {
+    let scene = demo_scene();
+    let view = DebugView::RecursionDepth;
+    let toward = |target: Vec3f| Ray {
+        origin: Vec3f::default(),
+        direction: target.normalized(),
+    };
+    // The rubber sphere stops the ray tree, the glass sphere refracts to the ones behind.
+    let rubber = view.shade(&scene, toward(Vec3f::new(2.5, -0.5, -18.0)), 4);
+    assert!(close(rubber, Vec3f::new(0.0, 0.0, 1.0)));
+    let glass = view.shade(&scene, toward(Vec3f::new(-1.0, -1.5, -12.0)), 4);
+    assert!(glass[0] > 0.0);
+}
+
+#[test]
+fn traversal_cost_drops_with_bvh() {
+    let spheres = || -> Vec<Box<dyn Renderable>> {
+        (0..64)
+            .map(|i| -> Box<dyn Renderable> {
+                Box::new(Sphere {
+                    center: Vec3f::new((i % 8) as f32 * 3.0, (i / 8) as f32 * 3.0, -20.0),
+                    radius: 1.0,
+                    material: Default::default(),
+                })
+            })
+            .collect()
+    };
+    let linear = Scene {
+        objects: spheres(),
+        ..Scene::default()
+    };
+    let bvh = Scene {
+        objects: vec![Box::new(Bvh::new(spheres()))],
+        ..Scene::default()
+    };
+
+    let view = DebugView::TraversalCost { max_cost: 64 };
+    let ray = forward(0.0, 0.0);
+    let linear = view.shade(&linear, ray, 4);
+    let bvh = view.shade(&bvh, ray, 4);
+    // Every sphere is tested without acceleration structure.
+    assert!(close(linear, Vec3f::new(1.0, 0.0, 0.0)));
+    assert!(bvh[2] > 0.0);
+}
@@ -0,0 +1,66 @@
+use std::f32::consts::PI;
+
+use raytracing::primitives::MaterialId;
+use raytracing::scene::Emitter;
+use raytracing::sphere::Sphere;
+use raytracing::vec::Vec3f;
+
+fn grid(n: usize) -> impl Iterator<Item = (f32, f32)> {
+    (0..n * n).map(move |i| {
+        (
+            ((i % n) as f32 + 0.5) / n as f32,
+            ((i / n) as f32 + 0.5) / n as f32,
+        )
+    })
+}
+
+#[test]
+fn sphere_samples_only_its_visible_cap() {
+    let sphere = Sphere {
+        center: Vec3f::new(0.0, 4.0, 0.0),
+        radius: 1.0,
+        material: MaterialId(0),
+    };
+    let reference = Vec3f::new(0.0, 0.0, 0.0);
+    let up = Vec3f::new(0.0, 1.0, 0.0);
+    let n = 64;
+    let mut irradiance = 0.0;
+    for (u, v) in grid(n) {
+        let (sample, pdf) = sphere.sample_from(reference, u, v).unwrap();
+        let to_reference = reference - sample.point;
+        assert!(sample.normal * to_reference > -1e-4);
+        assert!((pdf - sphere.pdf_from(reference, sample.point, sample.normal)).abs() < 1e-3 * pdf);
+        irradiance += (to_reference.normalized() * -1.0) * up / pdf;
+    }
+    irradiance /= (n * n) as f32;
+    // Irradiance of a uniformly bright sphere of radiance 1 straight above the point.
+    let expected = PI / 16.0;
+    assert!(
+        (irradiance - expected).abs() < 1e-3 * expected,
+        "{} vs {}",
+        irradiance,
+        expected
+    );
+}
+
+#[test]
+fn sphere_samples_its_whole_surface_from_inside() {
+    let sphere = Sphere {
+        center: Vec3f::new(0.0, 0.0, 0.0),
+        radius: 2.0,
+        material: MaterialId(0),
+    };
+    let reference = Vec3f::new(0.5, 0.0, 0.0);
+    let n = 64;
+    let mut solid_angle = 0.0;
+    for (u, v) in grid(n) {
+        let (_, pdf) = sphere.sample_from(reference, u, v).unwrap();
+        solid_angle += 1.0 / pdf;
+    }
+    solid_angle /= (n * n) as f32;
+    assert!(
+        (solid_angle - 4.0 * PI).abs() < 0.05 * 4.0 * PI,
+        "{}",
+        solid_angle
+    );
+}
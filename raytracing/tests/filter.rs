@@ -0,0 +1,55 @@
+use raytracing::demo::demo_scene;
+use raytracing::filter::Filter;
+use raytracing::render::{render_frame, RenderParams};
+
+const FILTERS: [Filter; 5] = [
+    Filter::Box { radius: 0.5 },
+    Filter::Tent { radius: 1.0 },
+    Filter::Gaussian {
+        radius: 1.5,
+        alpha: 2.0,
+    },
+    Filter::Mitchell {
+        radius: 2.0,
+        b: 1.0 / 3.0,
+        c: 1.0 / 3.0,
+    },
+    Filter::Lanczos { radius: 3.0 },
+];
+
+#[test]
+fn filters_are_symmetric_and_bounded() {
+    for filter in FILTERS.iter() {
+        let radius = filter.radius();
+        assert!(filter.evaluate(0.0, 0.0) > 0.0, "{:?}", filter);
+        assert_eq!(filter.evaluate(radius * 1.01, 0.0), 0.0, "{:?}", filter);
+        assert_eq!(filter.evaluate(0.0, -radius * 1.01), 0.0, "{:?}", filter);
+        for step in 0..20 {
+            let x = radius * step as f32 / 20.0;
+            assert_eq!(filter.evaluate(x, 0.3), filter.evaluate(-x, -0.3));
+            assert_eq!(filter.evaluate(x, 0.3), filter.evaluate(0.3, x));
+        }
+    }
+}
+
+#[test]
+fn half_pixel_box_filter_averages_each_pixel() {
+    let params = RenderParams {
+        width: 48,
+        height: 32,
+        samples_per_pixel: 4,
+        ..RenderParams::default()
+    };
+    let averaged = render_frame(&demo_scene(), params);
+    let filtered = render_frame(
+        &demo_scene(),
+        RenderParams {
+            filter: Some(Filter::Box { radius: 0.5 }),
+            ..params
+        },
+    );
+    for (a, b) in averaged.color.iter().zip(&filtered.color) {
+        let difference = *a - *b;
+        assert!(difference * difference < 1e-8, "{:?} vs {:?}", a, b);
+    }
+}
@@ -0,0 +1,141 @@
+use raytracing::framebuffer::FrameBuffer;
+use raytracing::gif::{lzw_compress, write_gif, GifParams, Palette};
+use raytracing::random::Rng;
+use raytracing::vec::Vec3f;
+
+/// Reference GIF LZW decoder for 8-bit codes.
+fn lzw_decompress(data: &[u8]) -> Vec<u8> {
+    let reset = || -> Vec<Vec<u8>> { (0..258).map(|i| vec![i as u8]).collect() };
+    let mut table = reset();
+    let (mut buffer, mut bits, mut position, mut size) = (0u32, 0, 0, 9);
+    let mut previous: Option<Vec<u8>> = None;
+    let mut output = Vec::new();
+    loop {
+        while bits < size {
+            buffer |= u32::from(data[position]) << bits;
+            position += 1;
+            bits += 8;
+        }
+        let code = (buffer & ((1 << size) - 1)) as usize;
+        buffer >>= size;
+        bits -= size;
+        match code {
+            256 => {
+                table = reset();
+                size = 9;
+                previous = None;
+                continue;
+            }
+            257 => return output,
+            _ => {}
+        }
+        let entry = match &previous {
+            None => table[code].clone(),
+            Some(previous) => {
+                let entry = if code < table.len() {
+                    table[code].clone()
+                } else {
+                    assert_eq!(code, table.len());
+                    let mut entry = previous.clone();
+                    entry.push(previous[0]);
+                    entry
+                };
+                if table.len() < 4096 {
+                    let mut added = previous.clone();
+                    added.push(entry[0]);
+                    table.push(added);
+                }
+                if table.len() == 1 << size && size < 12 {
+                    size += 1;
+                }
+                entry
+            }
+        };
+        output.extend_from_slice(&entry);
+        previous = Some(entry);
+    }
+}
+
+#[test]
+fn lzw_round_trips() {
+    let mut rng = Rng::new(3);
+    // Runs make long strings, noise fills the table and forces clear codes.
+    let mut mixed: Vec<u8> = (0..20000).map(|i| (i / 300) as u8).collect();
+    mixed.extend((0..50000).map(|_| (rng.next_f32() * 256.0) as u8));
+    let inputs = [vec![], vec![7], vec![1, 1, 1, 1, 1, 1, 1, 1], mixed];
+    for input in inputs.iter() {
+        assert_eq!(&lzw_decompress(&lzw_compress(input)), input);
+    }
+}
+
+#[test]
+fn palette_keeps_few_colors_exactly() {
+    let colors = [
+        [0, 0, 0],
+        [255, 0, 0],
+        [3, 200, 90],
+        [4, 200, 90],
+        [255, 255, 255],
+    ];
+    let image: Vec<u8> = (0..100).flat_map(|i| colors[i * 7 % 5].to_vec()).collect();
+    let palette = Palette::median_cut(&[&image], 256);
+    assert_eq!(palette.colors.len(), colors.len());
+    let indices = palette.index_image(&image, 10, false);
+    for (pixel, &index) in image.chunks(3).zip(&indices) {
+        assert_eq!(pixel, palette.colors[index as usize]);
+    }
+}
+
+#[test]
+fn dithering_preserves_average_color() {
+    let palette = Palette {
+        colors: vec![[0, 0, 0], [255, 255, 255]],
+    };
+    let image = vec![64; 32 * 32 * 3];
+    let plain = palette.index_image(&image, 32, false);
+    assert!(plain.iter().all(|&index| index == 0));
+    let dithered = palette.index_image(&image, 32, true);
+    let white = dithered.iter().filter(|&&index| index == 1).count() as f32;
+    assert!((white / 1024.0 - 64.0 / 255.0).abs() < 0.01);
+}
+
+#[test]
+fn gif_holds_looping_frames() {
+    let mut frames = vec![FrameBuffer::new(4, 3), FrameBuffer::new(4, 3)];
+    frames[1].color[5] = Vec3f::new(1.0, 0.0, 0.0);
+    let mut gif = Vec::new();
+    write_gif(&mut gif, &frames, GifParams::default()).unwrap();
+    assert_eq!(&gif[..10], b"GIF89a\x04\x00\x03\x00");
+    assert_eq!(gif[10], 0xf7);
+    let palette = &gif[13..13 + 768];
+    assert_eq!(
+        &gif[781..800],
+        b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00"
+    );
+
+    let mut position = 800;
+    for frame in &frames {
+        assert_eq!(&gif[position..position + 4], [0x21, 0xf9, 0x04, 0x04]);
+        position += 8;
+        assert_eq!(
+            &gif[position..position + 10],
+            [0x2c, 0, 0, 0, 0, 4, 0, 3, 0, 0]
+        );
+        assert_eq!(gif[position + 10], 8);
+        position += 11;
+        let mut data = Vec::new();
+        while gif[position] != 0 {
+            let length = gif[position] as usize;
+            data.extend_from_slice(&gif[position + 1..position + 1 + length]);
+            position += length + 1;
+        }
+        position += 1;
+        let indices = lzw_decompress(&data);
+        let rgb: Vec<u8> = indices
+            .iter()
+            .flat_map(|&i| palette[3 * i as usize..3 * i as usize + 3].to_vec())
+            .collect();
+        assert_eq!(rgb, frame.to_rgb8());
+    }
+    assert_eq!(&gif[position..], [0x3b]);
+}
@@ -0,0 +1,801 @@
+//! Golden-image regression tests.
+//!
+//! Each case renders a small scene and compares it with a reference image in
+//! `tests/golden`. Run with `UPDATE_GOLDEN=1` to regenerate the references after an
+//! intentional change in shading; with `--nocapture`, every changed reference is listed
+//! with its PSNR against the old one. The commit regenerating references names the changed
+//! images and the intended change in its message, and adds them to
+//! `tests/golden/CHANGES.md`, so that a re-baseline can be told from a regression. On
+//! failure the rendered image and an amplified difference image are written to the cargo
+//! temporary directory.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use raytracing::aabb::Aabb;
+use raytracing::camera::Projection;
+use raytracing::checkerboard::Checkerboard;
+use raytracing::cone::Cone;
+use raytracing::csg::Csg;
+use raytracing::cuboid::Cuboid;
+use raytracing::cylinder::Cylinder;
+use raytracing::demo::demo_scene;
+use raytracing::denoise::DenoiseParams;
+use raytracing::disk::Disk;
+use raytracing::filter::Filter;
+use raytracing::framebuffer::FrameBuffer;
+use raytracing::medium::{Fog, FogDensity, Media, Volume};
+use raytracing::photon_map::PhotonMapParams;
+use raytracing::primitives::{Light, Material};
+use raytracing::quad::Quad;
+use raytracing::render::{render_frame, AdaptiveParams, Integrator, RenderParams};
+use raytracing::sampler::SamplerKind;
+use raytracing::scene::{Renderable, Scene};
+use raytracing::sdf::{self, Sdf};
+use raytracing::sphere::Sphere;
+use raytracing::stereo::{StereoLayout, StereoParams};
+use raytracing::torus::Torus;
+use raytracing::vec::Vec3f;
+
+#[derive(Copy, Clone, Debug)]
+struct Tolerance {
+    /// Largest allowed absolute difference of a single 8-bit channel.
+    channel: u8,
+    /// Fraction of channels allowed to exceed `channel`.
+    max_outlier_fraction: f64,
+    min_psnr: f64,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Self {
+            channel: 2,
+            max_outlier_fraction: 0.001,
+            min_psnr: 40.0,
+        }
+    }
+}
+
+struct Image {
+    width: usize,
+    height: usize,
+    data: Vec<u8>,
+}
+
+fn read_ppm(path: &Path) -> Image {
+    let bytes = fs::read(path).unwrap_or_else(|err| {
+        panic!(
+            "cannot read reference image {}: {}; rerun with UPDATE_GOLDEN=1 to create it",
+            path.display(),
+            err
+        )
+    });
+    let mut fields = Vec::new();
+    let mut pos = 0;
+    while fields.len() < 4 {
+        while bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        let start = pos;
+        while !bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        fields.push(String::from_utf8_lossy(&bytes[start..pos]).into_owned());
+    }
+    assert_eq!(fields[0], "P6", "{} is not a binary PPM", path.display());
+    assert_eq!(fields[3], "255", "{} is not an 8-bit PPM", path.display());
+    let width: usize = fields[1].parse().unwrap();
+    let height: usize = fields[2].parse().unwrap();
+    let data = bytes[pos + 1..].to_vec();
+    assert_eq!(
+        data.len(),
+        width * height * 3,
+        "{} has pixel data of the wrong length for {}x{}",
+        path.display(),
+        width,
+        height
+    );
+    Image {
+        width,
+        height,
+        data,
+    }
+}
+
+fn write_ppm(path: &Path, width: usize, height: usize, data: &[u8]) {
+    let mut bytes = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    bytes.extend_from_slice(data);
+    fs::write(path, bytes).unwrap();
+}
+
+fn psnr(actual: &[u8], expected: &[u8]) -> f64 {
+    let squared_error: f64 = actual
+        .iter()
+        .zip(expected)
+        .map(|(&a, &e)| {
+            let delta = a as f64 - e as f64;
+            delta * delta
+        })
+        .sum();
+    let mse = squared_error / actual.len() as f64;
+    if mse == 0.0 {
+        f64::INFINITY
+    } else {
+        10.0 * (255.0 * 255.0 / mse).log10()
+    }
+}
+
+fn mean(data: &[u8]) -> f64 {
+    data.iter().map(|&channel| channel as f64).sum::<f64>() / data.len() as f64
+}
+
+fn check_golden(name: &str, frame: &FrameBuffer, tolerance: Tolerance) {
+    let reference_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.ppm", name));
+    let actual = frame.to_rgb8();
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        if reference_path.exists() {
+            let old = read_ppm(&reference_path);
+            if old.data != actual {
+                println!(
+                    "{}: reference changed, PSNR {:.1} dB, mean channel {:.2} -> {:.2}",
+                    name,
+                    psnr(&actual, &old.data),
+                    mean(&old.data),
+                    mean(&actual)
+                );
+            }
+        }
+        write_ppm(&reference_path, frame.width, frame.height, &actual);
+        return;
+    }
+
+    let expected = read_ppm(&reference_path);
+    assert_eq!(
+        (frame.width, frame.height),
+        (expected.width, expected.height),
+        "{}: image size differs from the reference",
+        name
+    );
+    assert_eq!(
+        actual.len(),
+        expected.data.len(),
+        "{}: rendered image has {} channels for {}x{} pixels",
+        name,
+        actual.len(),
+        frame.width,
+        frame.height
+    );
+
+    let diff: Vec<u8> = actual
+        .iter()
+        .zip(&expected.data)
+        .map(|(&a, &e)| {
+            (a as i32 - e as i32)
+                .unsigned_abs()
+                .saturating_mul(8)
+                .min(255) as u8
+        })
+        .collect();
+    let outliers = actual
+        .iter()
+        .zip(&expected.data)
+        .filter(|(&a, &e)| (a as i32 - e as i32).unsigned_abs() > tolerance.channel as u32)
+        .count();
+    let outlier_fraction = outliers as f64 / actual.len() as f64;
+    let psnr = psnr(&actual, &expected.data);
+
+    if outlier_fraction > tolerance.max_outlier_fraction || psnr < tolerance.min_psnr {
+        let out_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("golden");
+        fs::create_dir_all(&out_dir).unwrap();
+        let actual_path = out_dir.join(format!("{}-actual.ppm", name));
+        let diff_path = out_dir.join(format!("{}-diff.ppm", name));
+        write_ppm(&actual_path, frame.width, frame.height, &actual);
+        write_ppm(&diff_path, frame.width, frame.height, &diff);
+        panic!(
+            "{}: {} channels ({:.3}%) differ by more than {}, PSNR {:.2} dB (min {:.2} dB)\n\
+             rendered image: {}\ndifference image: {}",
+            name,
+            outliers,
+            outlier_fraction * 100.0,
+            tolerance.channel,
+            psnr,
+            tolerance.min_psnr,
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
+
+fn small_params() -> RenderParams {
+    RenderParams {
+        width: 128,
+        height: 96,
+        ..RenderParams::default()
+    }
+}
+
+fn ivory() -> Material {
+    Material {
+        albedo: [0.6, 0.3, 0.1, 0.0],
+        diffuse_color: Vec3f::new(0.4, 0.4, 0.3),
+        spectacular_component: 50.0,
+        refractive_index: 1.0,
+        ..Material::default()
+    }
+}
+
+fn glass() -> Material {
+    Material {
+        albedo: [0.0, 0.5, 0.1, 0.8],
+        diffuse_color: Vec3f::new(0.6, 0.7, 0.8),
+        spectacular_component: 125.0,
+        refractive_index: 1.5,
+        ..Material::default()
+    }
+}
+
+fn mirror() -> Material {
+    Material {
+        albedo: [0.0, 10.0, 0.8, 0.0],
+        diffuse_color: Vec3f::new(1.0, 1.0, 1.0),
+        spectacular_component: 1425.0,
+        refractive_index: 1.0,
+        ..Material::default()
+    }
+}
+
+/// Scene with the demo checkerboard, lit by a single light.
+fn board_scene() -> Scene {
+    let mut scene = Scene {
+        lights: vec![Light {
+            position: Vec3f::new(-20.0, 20.0, 20.0),
+            intensity: 1.5,
+        }],
+        background_color: Vec3f::new(0.2, 0.7, 0.8),
+        ..Scene::default()
+    };
+    let even = scene.add_material(Material {
+        diffuse_color: Vec3f::new(0.3, 0.3, 0.3),
+        ..Material::default()
+    });
+    let odd = scene.add_material(Material {
+        diffuse_color: Vec3f::new(0.3, 0.21, 0.09),
+        ..Material::default()
+    });
+    scene.objects.push(Box::new(Checkerboard { even, odd }));
+    scene
+}
+
+#[test]
+fn demo_scene_matches_reference() {
+    let frame = render_frame(&demo_scene(), small_params());
+    check_golden("demo", &frame, Tolerance::default());
+}
+
+#[test]
+fn demo_scene_adaptive_denoised_matches_reference() {
+    let params = RenderParams {
+        adaptive: Some(AdaptiveParams {
+            threshold: 0.05,
+            min_samples: 2,
+            max_samples: 8,
+        }),
+        denoise: Some(DenoiseParams::default()),
+        ..small_params()
+    };
+    let frame = render_frame(&demo_scene(), params);
+    check_golden("demo_adaptive_denoised", &frame, Tolerance::default());
+}
+
+#[test]
+fn demo_scene_caustics_match_reference() {
+    let params = RenderParams {
+        caustics: Some(PhotonMapParams {
+            photons: 50_000,
+            ..PhotonMapParams::default()
+        }),
+        ..small_params()
+    };
+    let frame = render_frame(&demo_scene(), params);
+    check_golden("demo_caustics", &frame, Tolerance::default());
+}
+
+#[test]
+fn demo_scene_mitchell_filtered_matches_reference() {
+    let params = RenderParams {
+        samples_per_pixel: 4,
+        sampler: SamplerKind::Stratified,
+        filter: Some(Filter::mitchell(2.0)),
+        ..small_params()
+    };
+    let frame = render_frame(&demo_scene(), params);
+    check_golden("demo_mitchell_filtered", &frame, Tolerance::default());
+}
+
+#[test]
+fn demo_scene_orthographic_matches_reference() {
+    let params = RenderParams {
+        projection: Projection::Orthographic { height: 14.0 },
+        ..small_params()
+    };
+    let frame = render_frame(&demo_scene(), params);
+    check_golden("demo_orthographic", &frame, Tolerance::default());
+}
+
+#[test]
+fn demo_scene_fisheye_matches_reference() {
+    let params = RenderParams {
+        projection: Projection::Fisheye,
+        field_of_view: 3.0,
+        ..small_params()
+    };
+    let frame = render_frame(&demo_scene(), params);
+    check_golden("demo_fisheye", &frame, Tolerance::default());
+}
+
+#[test]
+fn demo_scene_equirectangular_matches_reference() {
+    let params = RenderParams {
+        projection: Projection::Equirectangular,
+        width: 128,
+        height: 64,
+        ..small_params()
+    };
+    let frame = render_frame(&demo_scene(), params);
+    check_golden("demo_equirectangular", &frame, Tolerance::default());
+}
+
+#[test]
+fn demo_scene_anaglyph_matches_reference() {
+    let params = RenderParams {
+        stereo: Some(StereoParams {
+            interocular_distance: 1.0,
+            layout: StereoLayout::Anaglyph,
+            ..StereoParams::default()
+        }),
+        ..small_params()
+    };
+    let frame = render_frame(&demo_scene(), params);
+    check_golden("demo_anaglyph", &frame, Tolerance::default());
+}
+
+#[test]
+fn diffuse_sphere_matches_reference() {
+    let mut scene = board_scene();
+    let ivory = scene.add_material(ivory());
+    scene.objects.push(Box::new(Sphere {
+        center: Vec3f::new(0.0, -1.0, -16.0),
+        radius: 3.0,
+        material: ivory,
+    }));
+    let frame = render_frame(&scene, small_params());
+    check_golden("diffuse_sphere", &frame, Tolerance::default());
+}
+
+#[test]
+fn glass_and_mirror_match_reference() {
+    let mut scene = board_scene();
+    let glass = scene.add_material(glass());
+    let mirror = scene.add_material(mirror());
+    scene.objects.push(Box::new(Sphere {
+        center: Vec3f::new(-2.0, -1.0, -12.0),
+        radius: 2.0,
+        material: glass,
+    }));
+    scene.objects.push(Box::new(Sphere {
+        center: Vec3f::new(3.0, 1.0, -20.0),
+        radius: 4.0,
+        material: mirror,
+    }));
+    scene.lights.push(Light {
+        position: Vec3f::new(30.0, 50.0, -25.0),
+        intensity: 1.8,
+    });
+    let frame = render_frame(
+        &scene,
+        RenderParams {
+            depth: 5,
+            ..small_params()
+        },
+    );
+    check_golden("glass_and_mirror", &frame, Tolerance::default());
+}
+
+#[test]
+fn analytic_primitives_match_reference() {
+    let mut scene = board_scene();
+    let ivory = scene.add_material(ivory());
+    let red = scene.add_material(Material {
+        albedo: [0.9, 0.1, 0.0, 0.0],
+        diffuse_color: Vec3f::new(0.3, 0.1, 0.1),
+        spectacular_component: 10.0,
+        refractive_index: 1.0,
+        ..Material::default()
+    });
+    let mirror = scene.add_material(mirror());
+    scene.objects.push(Box::new(Cuboid {
+        min: Vec3f::new(-7.0, -4.0, -20.0),
+        max: Vec3f::new(-4.0, -1.0, -17.0),
+        material: ivory,
+    }));
+    scene.objects.push(Box::new(Cylinder {
+        base: Vec3f::new(-1.5, -4.0, -18.0),
+        radius: 1.2,
+        height: 4.0,
+        material: red,
+    }));
+    scene.objects.push(Box::new(Cone {
+        base: Vec3f::new(2.0, -4.0, -16.0),
+        radius: 1.5,
+        height: 3.5,
+        material: ivory,
+    }));
+    scene.objects.push(Box::new(Disk {
+        center: Vec3f::new(6.0, 0.0, -22.0),
+        normal: Vec3f::new(-0.5, 0.2, 1.0).normalized(),
+        radius: 3.0,
+        material: mirror,
+    }));
+    scene.objects.push(Box::new(Torus {
+        center: Vec3f::new(0.0, 2.5, -15.0),
+        major_radius: 2.0,
+        minor_radius: 0.6,
+        material: red,
+    }));
+    let frame = render_frame(&scene, small_params());
+    check_golden("analytic_primitives", &frame, Tolerance::default());
+}
+
+#[test]
+fn csg_shapes_match_reference() {
+    let mut scene = board_scene();
+    let ivory = scene.add_material(ivory());
+    let glass = scene.add_material(glass());
+    let sphere = |x: f32, y: f32, z: f32, radius: f32, material| -> Box<dyn Renderable> {
+        Box::new(Sphere {
+            center: Vec3f::new(x, y, z),
+            radius,
+            material,
+        })
+    };
+
+    // Lens: intersection of two overlapping spheres.
+    scene.objects.push(Box::new(Csg::intersection(
+        sphere(-5.5, 0.0, -16.0, 3.0, glass),
+        sphere(-3.5, 0.0, -16.0, 3.0, glass),
+    )));
+    // Dice: rounded cube with a pip carved out of its front face.
+    let rounded_cube = Csg::intersection(
+        Box::new(Cuboid {
+            min: Vec3f::new(-1.5, -3.5, -17.5),
+            max: Vec3f::new(1.5, -0.5, -14.5),
+            material: ivory,
+        }),
+        sphere(0.0, -2.0, -16.0, 2.0, ivory),
+    );
+    scene.objects.push(Box::new(Csg::difference(
+        Box::new(rounded_cube),
+        sphere(0.0, -2.0, -14.3, 0.5, ivory),
+    )));
+    // Hollow sphere with a wedge cut away to show the inside.
+    let shell = Csg::difference(
+        sphere(5.0, -1.0, -18.0, 3.0, ivory),
+        sphere(5.0, -1.0, -18.0, 2.6, ivory),
+    );
+    scene.objects.push(Box::new(Csg::difference(
+        Box::new(shell),
+        Box::new(Cuboid {
+            min: Vec3f::new(3.0, -1.0, -16.0),
+            max: Vec3f::new(8.5, 2.5, -14.0),
+            material: ivory,
+        }),
+    )));
+    let frame = render_frame(&scene, small_params());
+    check_golden("csg_shapes", &frame, Tolerance::default());
+}
+
+#[test]
+fn sdf_shapes_match_reference() {
+    let mut scene = board_scene();
+    let ivory = scene.add_material(ivory());
+    let red = scene.add_material(Material {
+        albedo: [0.9, 0.1, 0.0, 0.0],
+        diffuse_color: Vec3f::new(0.3, 0.1, 0.1),
+        spectacular_component: 10.0,
+        refractive_index: 1.0,
+        ..Material::default()
+    });
+
+    let bulb_center = Vec3f::new(-4.5, -1.0, -16.0);
+    scene.objects.push(Box::new(Sdf::new(
+        sdf::mandelbulb(bulb_center, 2.0, 8.0, 8),
+        Aabb::new(
+            bulb_center - Vec3f::new(2.6, 2.6, 2.6),
+            bulb_center + Vec3f::new(2.6, 2.6, 2.6),
+        ),
+        ivory,
+    )));
+
+    let blob = sdf::smooth_union(
+        sdf::sphere(Vec3f::new(0.0, -2.0, -15.0), 1.5),
+        sdf::torus(Vec3f::new(0.0, -3.0, -15.0), 2.0, 0.5),
+        0.8,
+    );
+    scene.objects.push(Box::new(Sdf::new(
+        blob,
+        Aabb::new(Vec3f::new(-3.0, -4.0, -18.0), Vec3f::new(3.0, 0.0, -12.0)),
+        red,
+    )));
+
+    let mut column = Sdf::new(
+        sdf::translate(
+            sdf::twist(
+                sdf::rounded_box(Vec3f::new(0.0, 0.0, 0.0), Vec3f::new(0.8, 2.5, 0.8), 0.1),
+                0.6,
+            ),
+            Vec3f::new(5.0, -1.5, -17.0),
+        ),
+        Aabb::new(Vec3f::new(3.8, -4.0, -18.2), Vec3f::new(6.2, 1.0, -15.8)),
+        ivory,
+    );
+    column.step_scale = 0.5;
+    scene.objects.push(Box::new(column));
+
+    let beads = sdf::repeat(
+        sdf::sphere(Vec3f::new(0.0, 0.0, 0.0), 0.3),
+        Vec3f::new(1.0, 0.0, 0.0),
+    );
+    scene.objects.push(Box::new(Sdf::new(
+        sdf::translate(beads, Vec3f::new(0.0, 3.0, -20.0)),
+        Aabb::new(Vec3f::new(-8.0, 2.7, -20.3), Vec3f::new(8.0, 3.3, -19.7)),
+        red,
+    )));
+
+    let frame = render_frame(&scene, small_params());
+    check_golden("sdf_shapes", &frame, Tolerance::default());
+}
+
+#[test]
+fn fog_and_smoke_match_reference() {
+    let mut scene = board_scene();
+    let ivory = scene.add_material(ivory());
+    scene.objects.push(Box::new(Sphere {
+        center: Vec3f::new(-2.0, 1.0, -16.0),
+        radius: 2.0,
+        material: ivory,
+    }));
+    scene.lights = vec![Light {
+        position: Vec3f::new(-3.0, 12.0, -14.0),
+        intensity: 3.0,
+    }];
+    scene.background_color = Vec3f::new(0.05, 0.05, 0.1);
+    scene.media = Media {
+        fog: Some(Fog {
+            density: FogDensity::Height {
+                base_height: -4.0,
+                falloff: 0.15,
+            },
+            absorption: 0.01,
+            scattering: 0.1,
+            asymmetry: 0.3,
+        }),
+        volumes: vec![Volume {
+            boundary: Box::new(Sphere {
+                center: Vec3f::new(4.0, -1.5, -15.0),
+                radius: 2.5,
+                material: ivory,
+            }),
+            absorption: 0.1,
+            scattering: 0.5,
+            asymmetry: 0.0,
+        }],
+        steps: 24,
+        max_distance: 40.0,
+    };
+    let frame = render_frame(&scene, small_params());
+    check_golden("fog_and_smoke", &frame, Tolerance::default());
+}
+
+/// Closed room lit by an emissive ceiling panel and a glowing sphere.
+fn emissive_box_scene() -> Scene {
+    let mut scene = Scene::default();
+    let white = scene.add_material(Material {
+        diffuse_color: Vec3f::new(0.75, 0.75, 0.75),
+        ..Material::default()
+    });
+    let red = scene.add_material(Material {
+        diffuse_color: Vec3f::new(0.75, 0.2, 0.2),
+        ..Material::default()
+    });
+    let green = scene.add_material(Material {
+        diffuse_color: Vec3f::new(0.2, 0.75, 0.2),
+        ..Material::default()
+    });
+    let lamp = scene.add_material(Material {
+        albedo: [0.0; 4],
+        emission: Vec3f::new(8.0, 8.0, 7.0),
+        ..Material::default()
+    });
+    let glow = scene.add_material(Material {
+        albedo: [0.0; 4],
+        emission: Vec3f::new(4.0, 1.5, 0.3),
+        ..Material::default()
+    });
+    let ivory = scene.add_material(ivory());
+
+    let walls = [
+        (
+            Vec3f::new(-6.0, -4.0, -4.0),
+            Vec3f::new(12.0, 0.0, 0.0),
+            white,
+        ),
+        (
+            Vec3f::new(-6.0, 6.0, -4.0),
+            Vec3f::new(12.0, 0.0, 0.0),
+            white,
+        ),
+        (
+            Vec3f::new(-6.0, -4.0, -4.0),
+            Vec3f::new(0.0, 10.0, 0.0),
+            red,
+        ),
+        (
+            Vec3f::new(6.0, -4.0, -4.0),
+            Vec3f::new(0.0, 10.0, 0.0),
+            green,
+        ),
+    ];
+    for &(corner, edge_u, material) in walls.iter() {
+        scene.objects.push(Box::new(Quad {
+            corner,
+            edge_u,
+            edge_v: Vec3f::new(0.0, 0.0, -18.0),
+            material,
+        }));
+    }
+    scene.objects.push(Box::new(Quad {
+        corner: Vec3f::new(-6.0, -4.0, -22.0),
+        edge_u: Vec3f::new(12.0, 0.0, 0.0),
+        edge_v: Vec3f::new(0.0, 10.0, 0.0),
+        material: white,
+    }));
+    scene.objects.push(Box::new(Quad {
+        corner: Vec3f::new(-2.0, 5.99, -11.0),
+        edge_u: Vec3f::new(4.0, 0.0, 0.0),
+        edge_v: Vec3f::new(0.0, 0.0, -4.0),
+        material: lamp,
+    }));
+    scene.objects.push(Box::new(Sphere {
+        center: Vec3f::new(-2.5, -1.5, -16.0),
+        radius: 2.5,
+        material: ivory,
+    }));
+    scene.objects.push(Box::new(Sphere {
+        center: Vec3f::new(2.5, -3.0, -12.0),
+        radius: 1.0,
+        material: glow,
+    }));
+    scene
+}
+
+#[test]
+fn emissive_path_traced_matches_reference() {
+    let frame = render_frame(
+        &emissive_box_scene(),
+        RenderParams {
+            samples_per_pixel: 8,
+            depth: 5,
+            integrator: Integrator::PathTracing,
+            ..small_params()
+        },
+    );
+    check_golden("emissive_path_traced", &frame, Tolerance::default());
+}
+
+#[test]
+fn emissive_sobol_sampled_matches_reference() {
+    let frame = render_frame(
+        &emissive_box_scene(),
+        RenderParams {
+            samples_per_pixel: 8,
+            depth: 5,
+            integrator: Integrator::PathTracing,
+            sampler: SamplerKind::Sobol,
+            ..small_params()
+        },
+    );
+    check_golden("emissive_sobol_sampled", &frame, Tolerance::default());
+}
+
+/// Glossy plates of increasing sharpness reflecting emitters of decreasing size, which
+/// needs both light and BSDF sampling to converge.
+#[test]
+fn glossy_plates_path_traced_match_reference() {
+    let mut scene = Scene {
+        background_color: Vec3f::new(0.05, 0.05, 0.08),
+        ..Scene::default()
+    };
+    let floor = scene.add_material(Material {
+        diffuse_color: Vec3f::new(0.4, 0.4, 0.4),
+        ..Material::default()
+    });
+    scene.objects.push(Box::new(Quad {
+        corner: Vec3f::new(-20.0, -4.0, 0.0),
+        edge_u: Vec3f::new(40.0, 0.0, 0.0),
+        edge_v: Vec3f::new(0.0, 0.0, -40.0),
+        material: floor,
+    }));
+
+    let light_center = Vec3f::new(0.0, 1.0, -14.0);
+    for (k, &exponent) in [20.0, 80.0, 300.0, 1500.0].iter().enumerate() {
+        let plate = scene.add_material(Material {
+            albedo: [0.1, 0.8, 0.0, 0.0],
+            diffuse_color: Vec3f::new(0.3, 0.3, 0.3),
+            spectacular_component: exponent,
+            ..Material::default()
+        });
+        // Tilted to reflect the lights towards the camera.
+        let center = Vec3f::new(0.0, -3.4 + k as f32 * 0.6, -4.5 - k as f32 * 1.5);
+        let normal =
+            ((center * -1.0).normalized() + (light_center - center).normalized()).normalized();
+        let edge_v = Vec3f::new(0.0, -normal[2], normal[1]) * 1.2;
+        scene.objects.push(Box::new(Quad {
+            corner: center - Vec3f::new(5.0, 0.0, 0.0) - edge_v * 0.5,
+            edge_u: Vec3f::new(10.0, 0.0, 0.0),
+            edge_v,
+            material: plate,
+        }));
+    }
+
+    let lights = [
+        (0.05, Vec3f::new(1.0, 0.6, 0.3) * 3200.0),
+        (0.2, Vec3f::new(0.4, 1.0, 0.4) * 200.0),
+        (0.6, Vec3f::new(0.4, 0.6, 1.0) * 24.0),
+        (1.2, Vec3f::new(1.0, 1.0, 1.0) * 6.0),
+    ];
+    for (k, &(radius, emission)) in lights.iter().enumerate() {
+        let material = scene.add_material(Material {
+            albedo: [0.0; 4],
+            emission,
+            ..Material::default()
+        });
+        scene.objects.push(Box::new(Sphere {
+            center: light_center + Vec3f::new(-4.5 + 3.0 * k as f32, 0.0, 0.0),
+            radius,
+            material,
+        }));
+    }
+
+    let frame = render_frame(
+        &scene,
+        RenderParams {
+            samples_per_pixel: 8,
+            integrator: Integrator::PathTracing,
+            ..small_params()
+        },
+    );
+    check_golden("glossy_plates_path_traced", &frame, Tolerance::default());
+}
+
+#[test]
+fn emissive_bidirectional_matches_reference() {
+    let mut scene = emissive_box_scene();
+    let glass = scene.add_material(glass());
+    scene.objects.push(Box::new(Sphere {
+        center: Vec3f::new(2.5, 0.0, -15.0),
+        radius: 1.5,
+        material: glass,
+    }));
+    let frame = render_frame(
+        &scene,
+        RenderParams {
+            samples_per_pixel: 4,
+            depth: 5,
+            integrator: Integrator::Bidirectional,
+            ..small_params()
+        },
+    );
+    check_golden("emissive_bidirectional", &frame, Tolerance::default());
+}
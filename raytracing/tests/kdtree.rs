@@ -0,0 +1,41 @@
+use raytracing::kdtree::KdTree;
+use raytracing::random::Rng;
+use raytracing::vec::Vec3f;
+
+fn random_point(rng: &mut Rng) -> Vec3f {
+    Vec3f::new(
+        rng.next_f32() * 10.0 - 5.0,
+        rng.next_f32() * 2.0,
+        rng.next_f32() * 6.0 - 3.0,
+    )
+}
+
+#[test]
+fn kdtree_matches_linear_search() {
+    let mut rng = Rng::new(11);
+    let points: Vec<_> = (0..2000).map(|_| random_point(&mut rng)).collect();
+    let tree = KdTree::new(points.iter().cloned().zip(0..).collect());
+    assert_eq!(tree.len(), points.len());
+
+    for _ in 0..200 {
+        let query = random_point(&mut rng);
+        for &(count, max_distance) in [(1, f32::INFINITY), (16, 1.0), (50, 0.3)].iter() {
+            let mut expected: Vec<(f32, usize)> = points
+                .iter()
+                .enumerate()
+                .map(|(index, &point)| ((point - query) * (point - query), index))
+                .filter(|&(distance_sqr, _)| distance_sqr <= max_distance * max_distance)
+                .collect();
+            expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            expected.truncate(count);
+
+            let found = tree.nearest(query, count, max_distance);
+            assert_eq!(found.len(), expected.len());
+            for (&(distance_sqr, &index), &(expected_sqr, _)) in found.iter().zip(&expected) {
+                assert_eq!(distance_sqr, expected_sqr);
+                let point = points[index];
+                assert_eq!((point - query) * (point - query), distance_sqr);
+            }
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use raytracing::medium::{Media, Volume};
+use raytracing::primitives::{Light, MaterialId, Ray};
+use raytracing::scene::Renderable;
+use raytracing::sphere::Sphere;
+use raytracing::vec::Vec3f;
+
+fn smoke(scattering: f32) -> Volume {
+    Volume {
+        boundary: Box::new(Sphere {
+            center: Vec3f::new(0.0, 0.0, -10.0),
+            radius: 2.0,
+            material: MaterialId(0),
+        }),
+        absorption: 0.0,
+        scattering,
+        asymmetry: 0.0,
+    }
+}
+
+#[test]
+fn overlapping_volumes_add_up() {
+    let ray = Ray {
+        origin: Vec3f::new(0.0, 0.5, 0.0),
+        direction: Vec3f::new(0.0, 0.0, -1.0),
+    };
+    let lights = [Light {
+        position: Vec3f::new(5.0, 5.0, -5.0),
+        intensity: 100.0,
+    }];
+    let nothing: Vec<Box<dyn Renderable>> = Vec::new();
+    let background = Vec3f::new(0.2, 0.4, 0.6);
+    let apply = |volumes: Vec<Volume>| {
+        let media = Media {
+            volumes,
+            ..Media::default()
+        };
+        let color = media.apply(ray, f32::INFINITY, background, &lights, &nothing);
+        (color, media.transmittance(ray, 0.0, 20.0))
+    };
+
+    let (single, single_transmittance) = apply(vec![smoke(0.4)]);
+    let (double, double_transmittance) = apply(vec![smoke(0.2), smoke(0.2)]);
+    assert!((single_transmittance - double_transmittance).abs() < 1e-5);
+    assert!(single_transmittance < 0.5);
+    for c in 0..3 {
+        assert!(single[c] > background[c] * single_transmittance);
+        assert!((single[c] - double[c]).abs() < 1e-4 * single[c]);
+    }
+}
@@ -0,0 +1,45 @@
+use raytracing::cone::Cone;
+use raytracing::cuboid::Cuboid;
+use raytracing::primitives::{MaterialId, Ray};
+use raytracing::scene::Primitive;
+use raytracing::vec::Vec3f;
+
+fn is_unit(v: Vec3f) -> bool {
+    (v.norm() - 1.0).abs() < 1e-5
+}
+
+#[test]
+fn cone_apex_normal_is_the_axis() {
+    let cone = Cone {
+        base: Vec3f::new(0.0, 0.0, 0.0),
+        radius: 1.0,
+        height: 2.0,
+        material: MaterialId(0),
+    };
+    let ray = Ray {
+        origin: Vec3f::new(0.0, 5.0, 0.0),
+        direction: Vec3f::new(0.0, -1.0, 0.0),
+    };
+    let intersection = cone.surface(ray, 3.0);
+    assert_eq!(intersection.normal.coordinates, [0.0, 1.0, 0.0]);
+}
+
+#[test]
+fn flat_cuboid_normal_faces_the_ray() {
+    let cuboid = Cuboid {
+        min: Vec3f::new(-1.0, 0.0, -1.0),
+        max: Vec3f::new(1.0, 0.0, 1.0),
+        material: MaterialId(0),
+    };
+    for &dy in &[-1.0, 1.0] {
+        let ray = Ray {
+            origin: Vec3f::new(0.25, -dy * 2.0, 0.5),
+            direction: Vec3f::new(0.0, dy, 0.0),
+        };
+        let intersection = cuboid.surface(ray, 2.0);
+        assert!(is_unit(intersection.normal));
+        assert!(intersection.normal * ray.direction < 0.0);
+        let (u, v) = intersection.uv;
+        assert!(u.is_finite() && v.is_finite());
+    }
+}
@@ -0,0 +1,166 @@
+use raytracing::camera::CameraPose;
+use raytracing::path_tracer::PathTracer;
+use raytracing::primitives::{Material, Ray};
+use raytracing::quad::Quad;
+use raytracing::render::{render_frame, Integrator, RenderParams};
+use raytracing::sampler::SamplerKind;
+use raytracing::sampling::MisHeuristic;
+use raytracing::scene::Scene;
+use raytracing::sphere::Sphere;
+use raytracing::vec::Vec3f;
+
+const ALBEDO: f32 = 0.5;
+
+/// Scene with a large diffuse floor through the origin, facing up.
+fn floor_scene() -> Scene {
+    let mut scene = Scene::default();
+    let floor = scene.add_material(Material {
+        diffuse_color: Vec3f::new(ALBEDO, ALBEDO, ALBEDO),
+        ..Material::default()
+    });
+    scene.objects.push(Box::new(Quad {
+        corner: Vec3f::new(-1000.0, 0.0, 1000.0),
+        edge_u: Vec3f::new(2000.0, 0.0, 0.0),
+        edge_v: Vec3f::new(0.0, 0.0, -2000.0),
+        material: floor,
+    }));
+    scene
+}
+
+/// Mean radiance the path tracer finds looking down at the origin of the floor.
+fn mean_radiance(scene: &Scene, heuristic: MisHeuristic, depth: usize) -> f32 {
+    let mut path_tracer = PathTracer::new(scene);
+    path_tracer.heuristic = heuristic;
+    let ray = Ray {
+        origin: Vec3f::new(0.0, 2.0, 2.0),
+        direction: Vec3f::new(0.0, -1.0, -1.0).normalized(),
+    };
+    let samples = 20_000;
+    let mut sampler = SamplerKind::Independent.create((0, 0), 1, samples);
+    let mut total = 0.0;
+    for i in 0..samples {
+        sampler.start_sample(i);
+        total += path_tracer.radiance(ray, depth, &mut *sampler)[1];
+    }
+    total / samples as f32
+}
+
+fn assert_close(actual: f32, expected: f32, what: &str) {
+    assert!(
+        (actual - expected).abs() < 0.02 * expected,
+        "{}: {} instead of {}",
+        what,
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn environment_lighting_is_unbiased_at_any_depth() {
+    let sky = 0.8;
+    let scene = Scene {
+        background_color: Vec3f::new(sky, sky, sky),
+        ..floor_scene()
+    };
+    // A diffuse floor under a uniform sky reflects the albedo times the sky radiance.
+    for &heuristic in &[MisHeuristic::Balance, MisHeuristic::Power] {
+        for depth in 1..=3 {
+            let what = format!("{:?} at depth {}", heuristic, depth);
+            assert_close(mean_radiance(&scene, heuristic, depth), ALBEDO * sky, &what);
+        }
+    }
+}
+
+const GLOW: f32 = 10.0;
+const LAMP_HEIGHT: f32 = 4.0;
+const LAMP_RADIUS: f32 = 1.0;
+
+/// The floor lit by a glowing sphere above its origin.
+fn lamp_scene() -> Scene {
+    let mut scene = floor_scene();
+    let lamp = scene.add_material(Material {
+        emission: Vec3f::new(GLOW, GLOW, GLOW),
+        ..Material::default()
+    });
+    scene.objects.push(Box::new(Sphere {
+        center: Vec3f::new(0.0, LAMP_HEIGHT, 0.0),
+        radius: LAMP_RADIUS,
+        material: lamp,
+    }));
+    scene
+}
+
+/// Radiance of the floor of [`lamp_scene`] at `point`. The irradiance of a sphere is
+/// `PI * GLOW * sin^2` of its angular radius times the cosine towards its center.
+fn lamp_radiance(point: Vec3f) -> f32 {
+    let to_lamp = Vec3f::new(0.0, LAMP_HEIGHT, 0.0) - point;
+    let distance = to_lamp.norm();
+    ALBEDO * GLOW * (LAMP_RADIUS / distance).powi(2) * (LAMP_HEIGHT / distance)
+}
+
+#[test]
+fn emitter_lighting_is_unbiased_at_any_depth() {
+    let scene = lamp_scene();
+    let expected = lamp_radiance(Vec3f::default());
+    for &heuristic in &[MisHeuristic::Balance, MisHeuristic::Power] {
+        for depth in 1..=3 {
+            let what = format!("{:?} at depth {}", heuristic, depth);
+            assert_close(mean_radiance(&scene, heuristic, depth), expected, &what);
+        }
+    }
+}
+
+#[test]
+fn bidirectional_images_converge_to_the_path_traced_ones() {
+    let scene = lamp_scene();
+    // Looking down at the floor below the lamp. The wide view makes the light subpaths
+    // reaching the camera, which are splatted, a large part of the image.
+    let params = RenderParams {
+        field_of_view: 0.8 * std::f32::consts::PI,
+        pose: CameraPose {
+            position: Vec3f::new(0.0, 1.0, 0.0),
+            target: Vec3f::default(),
+        },
+        width: 8,
+        height: 8,
+        depth: 3,
+        samples_per_pixel: 512,
+        ..RenderParams::default()
+    };
+    let mean = |integrator: Integrator| {
+        let frame = render_frame(
+            &scene,
+            RenderParams {
+                integrator,
+                ..params
+            },
+        );
+        frame.color.iter().map(|color| color[1]).sum::<f32>() / frame.color.len() as f32
+    };
+
+    // Floor radiance averaged over a grid of points in every pixel.
+    let camera = params.camera();
+    let grid = 4;
+    let mut expected = 0.0;
+    for y in 0..params.height * grid {
+        for x in 0..params.width * grid {
+            let position = |i: usize| (i as f32 + 0.5) / grid as f32;
+            let ray = camera.generate_ray(position(x), position(y)).unwrap();
+            let floor = scene.objects[0]
+                .ray_intersect(ray, 0.0, f32::INFINITY)
+                .unwrap();
+            expected += lamp_radiance(ray.origin + ray.direction * floor.distance);
+        }
+    }
+    expected /= (params.width * params.height * grid * grid) as f32;
+
+    let path_traced = mean(Integrator::PathTracing);
+    let bidirectional = mean(Integrator::Bidirectional);
+    assert_close(path_traced, expected, "path tracing");
+    assert_close(bidirectional, expected, "bidirectional");
+    assert_close(
+        bidirectional,
+        path_traced,
+        "bidirectional against path tracing",
+    );
+}
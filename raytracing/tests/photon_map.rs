@@ -0,0 +1,43 @@
+use raytracing::photon_map::{PhotonMap, PhotonMapParams};
+use raytracing::primitives::{Light, Material};
+use raytracing::quad::Quad;
+use raytracing::scene::Scene;
+use raytracing::sphere::Sphere;
+use raytracing::vec::Vec3f;
+
+#[test]
+fn fewer_photons_than_objects_still_make_caustics() {
+    let mut scene = Scene::default();
+    let floor = scene.add_material(Material {
+        diffuse_color: Vec3f::new(0.8, 0.8, 0.8),
+        ..Material::default()
+    });
+    let glass = scene.add_material(Material {
+        albedo: [0.0, 0.0, 0.0, 1.0],
+        refractive_index: 1.5,
+        ..Material::default()
+    });
+    scene.objects.push(Box::new(Quad {
+        corner: Vec3f::new(-50.0, 0.0, 50.0),
+        edge_u: Vec3f::new(100.0, 0.0, 0.0),
+        edge_v: Vec3f::new(0.0, 0.0, -100.0),
+        material: floor,
+    }));
+    for i in 0..20 {
+        scene.objects.push(Box::new(Sphere {
+            center: Vec3f::new(i as f32 * 3.0 - 30.0, 2.0, 0.0),
+            radius: 1.0,
+            material: glass,
+        }));
+    }
+    scene.lights.push(Light {
+        position: Vec3f::new(0.0, 20.0, 0.0),
+        intensity: 1.0,
+    });
+
+    let params = PhotonMapParams {
+        photons: scene.objects.len() / 2,
+        ..PhotonMapParams::default()
+    };
+    assert!(!PhotonMap::new(&scene, params).is_empty());
+}
@@ -0,0 +1,83 @@
+use raytracing::demo::demo_scene;
+use raytracing::pick::{pick, RayKind, RayTree};
+use raytracing::render::{render_frame, RenderParams};
+use raytracing::vec::Vec3f;
+
+fn params() -> RenderParams {
+    RenderParams {
+        width: 64,
+        height: 48,
+        ..RenderParams::default()
+    }
+}
+
+/// Pixel showing the point `target` of the scene.
+fn pixel_of(params: &RenderParams, target: Vec3f) -> (usize, usize) {
+    let (x, y) = params.camera().project(target.normalized()).unwrap();
+    (x as usize, y as usize)
+}
+
+fn levels(tree: &RayTree) -> usize {
+    1 + tree.children.iter().map(levels).max().unwrap_or(0)
+}
+
+#[test]
+fn pick_finds_object_and_material() {
+    let scene = demo_scene();
+    let params = params();
+    let frame = render_frame(&scene, params);
+    // Points inside the ivory, glass and rubber spheres, away from the others.
+    let targets = [
+        Vec3f::new(-3.5, 1.5, -16.0),
+        Vec3f::new(-1.0, -1.5, -12.0),
+        Vec3f::new(1.5, -0.5, -18.0),
+    ];
+    for (object, &target) in targets.iter().enumerate() {
+        let (x, y) = pixel_of(&params, target);
+        let tree = pick(&scene, &params, x, y).unwrap();
+        assert_eq!(tree.kind, RayKind::Camera);
+        let hit = tree.hit.unwrap();
+        assert_eq!(hit.object, object);
+        assert_eq!(hit.intersection.material.0, object);
+        assert_eq!(
+            hit.material.refractive_index,
+            scene.materials[object].refractive_index
+        );
+        assert!(hit.intersection.distance < target.norm());
+        assert!(hit.intersection.normal * target < 0.0);
+
+        // The root of the tree explains the rendered color.
+        let difference = tree.radiance - frame.color[y * params.width + x];
+        assert!(difference * difference < 1e-10);
+    }
+}
+
+#[test]
+fn ray_tree_follows_whitted_recursion() {
+    let scene = demo_scene();
+    let params = params();
+    let (x, y) = pixel_of(&params, Vec3f::new(-1.0, -1.5, -12.0));
+    let tree = pick(&scene, &params, x, y).unwrap();
+    assert_eq!(levels(&tree), params.depth);
+    let kinds: Vec<RayKind> = tree.children.iter().map(|child| child.kind).collect();
+    assert_eq!(kinds, [RayKind::Reflected, RayKind::Refracted]);
+    for child in &tree.children {
+        let origin = child.ray.origin - tree.hit.unwrap().intersection.hit;
+        assert!(origin * origin < 1e-10);
+    }
+
+    // The sky above the scene ends the tree at the camera ray.
+    let sky = pick(&scene, &params, 0, 0).unwrap();
+    assert!(sky.hit.is_none() && sky.children.is_empty());
+    let difference = sky.radiance - scene.background_color;
+    assert!(difference * difference < 1e-10);
+}
+
+#[test]
+fn pick_outside_the_image_finds_nothing() {
+    let scene = demo_scene();
+    let params = params();
+    assert!(pick(&scene, &params, params.width - 1, params.height - 1).is_some());
+    assert!(pick(&scene, &params, params.width, 0).is_none());
+    assert!(pick(&scene, &params, 0, params.height).is_none());
+}
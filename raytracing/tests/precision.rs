@@ -0,0 +1,145 @@
+use raytracing::camera::CameraPose;
+use raytracing::cuboid::Cuboid;
+use raytracing::framebuffer::FrameBuffer;
+use raytracing::primitives::{Light, Material, MaterialId, Ray};
+use raytracing::quad::Quad;
+use raytracing::random::Rng;
+use raytracing::render::{render_frame, RenderParams};
+use raytracing::scene::{Renderable, Scene};
+use raytracing::sphere::Sphere;
+use raytracing::vec::{Float, Vec3, Vec3d, Vec3f};
+
+#[test]
+fn vectors_cast_between_precisions() {
+    let v = Vec3d::new(1.0, 2.0, 2.0);
+    assert_eq!(v.norm(), 3.0);
+    assert_eq!(v * v.cross(Vec3d::new(0.0, 1.0, 0.0)), 0.0);
+    let single: Vec3f = v.cast();
+    assert_eq!(single.coordinates, [1.0f32, 2.0, 2.0]);
+    let third = Vec3d::new(1.0 / 3.0, 0.0, 0.0);
+    assert_eq!(third.cast::<f32>()[0], 1.0f32 / 3.0);
+}
+
+/// Number of rays leaving the top of a huge sphere that are wrongly found blocked by it.
+///
+/// The rays start at the hits of rays falling onto the sphere, as secondary rays of a
+/// render do.
+fn self_occlusions<T: Float>(sphere: &Sphere<T>) -> usize {
+    let mut rng = Rng::new(5);
+    (0..1000)
+        .filter(|_| {
+            let x = rng.next_f32() * 200.0 - 100.0;
+            let z = rng.next_f32() * 200.0 - 100.0;
+            let falling = Ray {
+                origin: Vec3f::new(x, 10.0, z).cast::<T>(),
+                direction: Vec3::new(T::ZERO, -T::ONE, T::ZERO),
+            };
+            let surface = sphere
+                .ray_intersect(falling, T::ZERO, T::INFINITY)
+                .expect("the sphere is below every ray")
+                .surface(falling);
+            // Grazing directions above the surface.
+            let tilt = Vec3f::new(rng.next_f32() - 0.5, 0.0, 1.0) * 4.0;
+            let ray = Ray {
+                origin: surface.hit,
+                direction: (surface.normal + tilt.cast()).normalized(),
+            };
+            sphere.occluded(ray, 100.0f32.cast())
+        })
+        .count()
+}
+
+#[test]
+fn double_precision_spheres_avoid_self_intersection() {
+    let radius = 1e5;
+    let center = Vec3d::new(0.0, -radius, 0.0);
+    let single = Sphere {
+        center: center.cast::<f32>(),
+        radius: radius as f32,
+        material: MaterialId::default(),
+    };
+    let double = Sphere {
+        center,
+        radius,
+        material: MaterialId::default(),
+    };
+    let single_errors = self_occlusions(&single);
+    let double_errors = self_occlusions(&double);
+    assert!(single_errors > 100);
+    assert_eq!(double_errors, 0);
+}
+
+/// Shadowed and mirrored objects on a floor, everything moved by `offset`, and the
+/// parameters of a view of them.
+fn offset_scene<T: Float>(offset: Vec3d) -> (Scene<T>, RenderParams<T>) {
+    let at = |x: f64, y: f64, z: f64| (offset + Vec3d::new(x, y, z)).cast::<T>();
+    let mut scene = Scene::default();
+    let matte = scene.add_material(Material {
+        albedo: [0.9, 0.1, 0.0, 0.0],
+        diffuse_color: Vec3f::new(0.6, 0.5, 0.3),
+        spectacular_component: 20.0,
+        ..Material::default()
+    });
+    let mirror = scene.add_material(Material {
+        albedo: [0.2, 0.2, 0.6, 0.0],
+        diffuse_color: Vec3f::new(0.2, 0.3, 0.8),
+        spectacular_component: 200.0,
+        ..Material::default()
+    });
+    scene.objects.push(Box::new(Quad {
+        corner: at(-4.0, 0.0, -4.0),
+        edge_u: Vec3d::new(0.0, 0.0, 8.0).cast(),
+        edge_v: Vec3d::new(8.0, 0.0, 0.0).cast(),
+        material: matte,
+    }));
+    scene.objects.push(Box::new(Sphere {
+        center: at(-1.0, 1.0, 0.0),
+        radius: T::ONE,
+        material: mirror,
+    }));
+    scene.objects.push(Box::new(Cuboid {
+        min: at(0.5, 0.0, -0.5),
+        max: at(1.5, 1.5, 0.5),
+        material: matte,
+    }));
+    scene.lights.push(Light {
+        position: at(3.0, 6.0, 4.0),
+        intensity: 1.5,
+    });
+    scene.background_color = Vec3f::new(0.2, 0.3, 0.4);
+    let params = RenderParams {
+        width: 48,
+        height: 32,
+        depth: 3,
+        pose: CameraPose {
+            position: at(0.0, 3.0, 6.0),
+            target: at(0.0, 0.5, 0.0),
+        },
+        ..RenderParams::default()
+    };
+    (scene, params)
+}
+
+/// Largest difference of a color channel between two images of the same size.
+fn largest_difference(a: &FrameBuffer, b: &FrameBuffer) -> f32 {
+    a.color
+        .iter()
+        .zip(&b.color)
+        .flat_map(|(a, b)| (0..3).map(move |channel| (a[channel] - b[channel]).abs()))
+        .fold(0.0, f32::max)
+}
+
+#[test]
+fn double_precision_renders_far_from_the_origin() {
+    let far = Vec3d::new(1e6, 2e6, -1e6);
+    let (scene, params) = offset_scene::<f32>(Vec3d::default());
+    let reference = render_frame(&scene, params);
+    let (scene, params) = offset_scene::<f64>(far);
+    let double = render_frame(&scene, params);
+    assert!(largest_difference(&double, &reference) < 1e-3);
+
+    // Rounding the same scene to single precision covers it in shadow acne.
+    let (scene, params) = offset_scene::<f32>(far);
+    let single = render_frame(&scene, params);
+    assert!(largest_difference(&single, &reference) > 0.1);
+}
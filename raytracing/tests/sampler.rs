@@ -0,0 +1,95 @@
+use raytracing::sampler::{blue_noise_ranks, SamplerKind, BLUE_NOISE_SIZE};
+
+const KINDS: [SamplerKind; 5] = [
+    SamplerKind::Independent,
+    SamplerKind::Stratified,
+    SamplerKind::Halton,
+    SamplerKind::Sobol,
+    SamplerKind::BlueNoise,
+];
+
+#[test]
+fn samples_lie_in_unit_interval() {
+    for &kind in KINDS.iter() {
+        let mut sampler = kind.create((3, 5), 16, 64);
+        for index in 0..64 {
+            sampler.start_sample(index);
+            for _ in 0..40 {
+                let u = sampler.next_1d();
+                let (x, y) = sampler.next_2d();
+                for value in [u, x, y].iter() {
+                    assert!((0.0..1.0).contains(value), "{:?} gave {}", kind, value);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn stratified_and_sobol_fill_every_stratum() {
+    for &kind in [SamplerKind::Stratified, SamplerKind::Sobol].iter() {
+        // The pixel dimensions and a 2D draw deeper into the path.
+        for &skip in [0, 7].iter() {
+            let mut sampler = kind.create((1, 2), 8, 16);
+            let mut cells = [0; 16];
+            let mut intervals = [0; 16];
+            for index in 0..16 {
+                sampler.start_sample(index);
+                for _ in 0..skip {
+                    sampler.next_1d();
+                }
+                let (x, y) = sampler.next_2d();
+                cells[(y * 4.0) as usize * 4 + (x * 4.0) as usize] += 1;
+                intervals[(sampler.next_1d() * 16.0) as usize] += 1;
+            }
+            assert_eq!(cells, [1; 16], "{:?}", kind);
+            assert_eq!(intervals, [1; 16], "{:?}", kind);
+        }
+    }
+}
+
+/// Root mean square error over many pixels of the 16-sample estimate of a smooth 4D
+/// integral, whose exact value is 1/2.
+fn integration_error(kind: SamplerKind) -> f32 {
+    let mut squared_error = 0.0;
+    let pixels = 256;
+    for pixel in 0..pixels {
+        let mut sampler = kind.create((pixel % 16, pixel / 16), 16, 16);
+        let mut sum = 0.0;
+        for index in 0..16 {
+            sampler.start_sample(index);
+            let (x, y) = sampler.next_2d();
+            let (z, w) = sampler.next_2d();
+            sum += x * y + z * w;
+        }
+        let error = sum / 16.0 - 0.5;
+        squared_error += error * error;
+    }
+    (squared_error / pixels as f32).sqrt()
+}
+
+#[test]
+fn low_discrepancy_samplers_converge_faster() {
+    let independent = integration_error(SamplerKind::Independent);
+    for &kind in KINDS[1..].iter() {
+        let error = integration_error(kind);
+        assert!(
+            error < 0.7 * independent,
+            "{:?}: {} vs {}",
+            kind,
+            error,
+            independent
+        );
+    }
+}
+
+#[test]
+fn blue_noise_texture_is_a_permutation() {
+    let ranks = blue_noise_ranks();
+    let mut seen = vec![false; BLUE_NOISE_SIZE * BLUE_NOISE_SIZE];
+    for &rank in ranks {
+        assert!(!seen[rank as usize]);
+        seen[rank as usize] = true;
+    }
+    assert!(seen.iter().all(|&seen| seen));
+}
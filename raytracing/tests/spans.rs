@@ -0,0 +1,75 @@
+use raytracing::primitives::{MaterialId, Ray};
+use raytracing::scene::Renderable;
+use raytracing::torus::Torus;
+use raytracing::vec::Vec3f;
+
+fn torus() -> Torus {
+    Torus {
+        center: Vec3f::new(0.0, 0.0, 0.0),
+        major_radius: 2.0,
+        minor_radius: 0.5,
+        material: MaterialId(0),
+    }
+}
+
+fn along_x(y: f32, z: f32) -> Ray {
+    Ray {
+        origin: Vec3f::new(-5.0, y, z),
+        direction: Vec3f::new(1.0, 0.0, 0.0),
+    }
+}
+
+fn span_distances(ray: Ray) -> Vec<(f32, f32)> {
+    torus()
+        .spans(ray)
+        .iter()
+        .map(|span| (span.enter.distance, span.exit.distance))
+        .collect()
+}
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-3
+}
+
+#[test]
+fn torus_spans_cover_both_sides_of_the_tube() {
+    let spans = span_distances(along_x(0.0, 0.0));
+    assert_eq!(spans.len(), 2);
+    assert!(close(spans[0].0, 2.5) && close(spans[0].1, 3.5));
+    assert!(close(spans[1].0, 6.5) && close(spans[1].1, 7.5));
+}
+
+/// Length of the part of `ray` within 10 units of its origin that lies inside the torus,
+/// by point sampling.
+fn sampled_length(ray: Ray) -> f32 {
+    let samples = 100_000;
+    let inside = (0..samples)
+        .filter(|&k| {
+            let p = ray.origin + ray.direction * (10.0 * (k as f32 + 0.5) / samples as f32);
+            let ring = (p[0] * p[0] + p[2] * p[2]).sqrt() - 2.0;
+            ring * ring + p[1] * p[1] < 0.25
+        })
+        .count();
+    10.0 * inside as f32 / samples as f32
+}
+
+#[test]
+fn torus_spans_survive_near_tangent_rays() {
+    // Around the inner equator and the top of the tube, where roots pair up.
+    let rays = (0..40)
+        .map(|i| (i as f32 - 20.0) * 1e-5)
+        .flat_map(|offset| vec![along_x(0.0, 1.5 + offset), along_x(0.5 + offset, 2.0)]);
+    for ray in rays {
+        let spans = span_distances(ray);
+        assert!(spans.iter().all(|&(enter, exit)| enter <= exit));
+        let length: f32 = spans.iter().map(|(enter, exit)| exit - enter).sum();
+        let expected = sampled_length(ray);
+        assert!(
+            (length - expected).abs() < 0.01,
+            "{:?}: {} inside, expected {}",
+            ray.origin,
+            length,
+            expected
+        );
+    }
+}
@@ -0,0 +1,32 @@
+use raytracing::primitives::Material;
+use raytracing::render::{render_frame_with_stats, RenderParams};
+use raytracing::scene::Scene;
+use raytracing::sphere::Sphere;
+use raytracing::stats::PrimitiveKind;
+use raytracing::vec::Vec3f;
+
+#[test]
+fn intersection_tests_count_only_traced_rays() {
+    let mut scene = Scene::default();
+    let material = scene.add_material(Material::default());
+    scene.objects.push(Box::new(Sphere {
+        center: Vec3f::new(0.0, 0.0, -10.0),
+        radius: 2.0,
+        material,
+    }));
+    let params = RenderParams {
+        width: 8,
+        height: 6,
+        depth: 1,
+        ..RenderParams::default()
+    };
+    let (_, stats) = render_frame_with_stats(&scene, params);
+    let counters = stats.counters;
+    assert_eq!(counters.primary_rays, 48);
+    assert_eq!(
+        counters.intersection_tests(PrimitiveKind::Sphere),
+        counters.total_rays()
+    );
+    let tile_rays: u64 = stats.tiles.iter().map(|tile| tile.rays).sum();
+    assert_eq!(tile_rays, counters.total_rays());
+}
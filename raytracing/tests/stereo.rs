@@ -0,0 +1,71 @@
+use raytracing::camera::{Camera, PerspectiveCamera};
+use raytracing::demo::demo_scene;
+use raytracing::render::{render_frame, RenderParams};
+use raytracing::stereo::{Eye, StereoCamera, StereoLayout, StereoParams};
+
+#[test]
+fn eye_rays_meet_at_convergence_distance() {
+    let params = StereoParams {
+        interocular_distance: 0.5,
+        convergence_distance: 10.0,
+        ..StereoParams::default()
+    };
+    let eye = |eye| StereoCamera::new(Box::new(PerspectiveCamera::new(1.0, 64, 48)), eye, params);
+    let (left, right) = (eye(Eye::Left), eye(Eye::Right));
+    for &(x, y) in [(32.0, 24.0), (5.5, 40.25), (60.0, 3.0)].iter() {
+        let left = left.generate_ray(x, y).unwrap();
+        let right = right.generate_ray(x, y).unwrap();
+        let baseline = right.origin - left.origin;
+        assert!((baseline * baseline - 0.25).abs() < 1e-6);
+        let central = PerspectiveCamera::new(1.0, 64, 48)
+            .generate_ray(x, y)
+            .unwrap();
+        let target = central.direction * 10.0;
+        for ray in [left, right].iter() {
+            let to_target = target - ray.origin;
+            let distance = to_target.norm();
+            let miss = to_target - ray.direction * distance;
+            assert!(miss.norm() < 1e-4);
+        }
+    }
+}
+
+#[test]
+fn layouts_place_both_views() {
+    let mono = RenderParams {
+        width: 40,
+        height: 30,
+        ..RenderParams::default()
+    };
+    let central = render_frame(&demo_scene(), mono);
+    for &layout in [StereoLayout::SideBySide, StereoLayout::TopBottom].iter() {
+        // Without eye separation both views are the central one.
+        let stereo = StereoParams {
+            interocular_distance: 0.0,
+            layout,
+            ..StereoParams::default()
+        };
+        let frame = render_frame(
+            &demo_scene(),
+            RenderParams {
+                stereo: Some(stereo),
+                ..mono
+            },
+        );
+        let (dx, dy) = layout.right_offset(40, 30);
+        assert_eq!((frame.width, frame.height), (40 + dx, 30 + dy));
+        let mut total_difference = 0.0;
+        for y in 0..30 {
+            for x in 0..40 {
+                let left = frame.color[y * frame.width + x];
+                let right = frame.color[(y + dy) * frame.width + x + dx];
+                let difference = left - right;
+                assert_eq!(difference * difference, 0.0);
+                let difference = left - central.color[y * 40 + x];
+                total_difference += (difference * difference).sqrt();
+            }
+        }
+        // Eye rays only differ from central ones by rounding.
+        assert!(total_difference / (40.0 * 30.0) < 1e-3);
+    }
+}
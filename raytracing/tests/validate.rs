@@ -0,0 +1,295 @@
+use std::f32::consts::PI;
+
+use raytracing::animation::{Animated, Animation, Interpolation, Track};
+use raytracing::bvh::Bvh;
+use raytracing::camera::Projection;
+use raytracing::cylinder::Cylinder;
+use raytracing::demo::demo_scene;
+use raytracing::disk::Disk;
+use raytracing::error::{GeometryError, RenderError};
+use raytracing::medium::{Fog, FogDensity, Volume};
+use raytracing::primitives::{Light, Material, MaterialId};
+use raytracing::render::RenderParams;
+use raytracing::scene::Renderable;
+use raytracing::sphere::Sphere;
+use raytracing::vec::Vec3f;
+
+fn sphere(center: Vec3f, radius: f32) -> Box<dyn Renderable> {
+    Box::new(Sphere {
+        center,
+        radius,
+        material: MaterialId::default(),
+    })
+}
+
+#[test]
+fn demo_scene_is_valid() {
+    assert!(demo_scene().validate().is_ok());
+    assert!(RenderParams::<f32>::default().validate().is_ok());
+}
+
+#[test]
+fn invalid_objects_are_reported_with_their_index() {
+    let mut scene = demo_scene();
+    scene.objects.push(sphere(Vec3f::new(0.0, 0.0, -5.0), 0.0));
+    match scene.validate() {
+        Err(RenderError::InvalidObject {
+            index: 5,
+            error: GeometryError::NonPositiveSize { quantity, value },
+        }) => assert_eq!((quantity, value), ("radius", 0.0)),
+        other => panic!("unexpected result {:?}", other),
+    }
+
+    // Errors are found inside acceleration structures too.
+    let mut scene = demo_scene();
+    let nan = Vec3f::new(f32::NAN, 0.0, 0.0);
+    scene.objects[1] = Box::new(Bvh::new(vec![
+        sphere(Vec3f::default(), 1.0),
+        sphere(nan, 1.0),
+    ]));
+    assert!(matches!(
+        scene.validate(),
+        Err(RenderError::InvalidObject {
+            index: 1,
+            error: GeometryError::NonFiniteCoordinate
+        })
+    ));
+
+    // Disks need a unit normal, not just a non-zero one.
+    let mut scene = demo_scene();
+    scene.objects.push(Box::new(Disk {
+        center: Vec3f::new(0.0, -4.0, -10.0),
+        normal: Vec3f::new(0.0, 2.0, 0.0),
+        radius: 1.0,
+        material: MaterialId::default(),
+    }));
+    match scene.validate() {
+        Err(RenderError::InvalidObject {
+            index: 5,
+            error: GeometryError::NonUnitLength { quantity, length },
+        }) => assert_eq!((quantity, length), ("normal", 2.0)),
+        other => panic!("unexpected result {:?}", other),
+    }
+
+    let mut scene = demo_scene();
+    scene.objects.push(Box::new(Cylinder {
+        base: Vec3f::default(),
+        radius: 1.0,
+        height: -2.0,
+        material: MaterialId::default(),
+    }));
+    assert!(matches!(
+        scene.validate(),
+        Err(RenderError::InvalidObject { index: 5, .. })
+    ));
+}
+
+#[test]
+fn lights_and_materials_are_checked() {
+    let mut scene = demo_scene();
+    scene.lights.push(Light {
+        position: Vec3f::new(0.0, f32::INFINITY, 0.0),
+        intensity: 1.0,
+    });
+    assert!(matches!(
+        scene.validate(),
+        Err(RenderError::InvalidLight(3))
+    ));
+
+    let mut scene = demo_scene();
+    let glowing = scene.add_material(Material {
+        albedo: [0.6, 0.0, 0.5, 0.0],
+        ..Material::default()
+    });
+    match scene.validate() {
+        Err(RenderError::AlbedoNotEnergyConserving { material, .. }) => {
+            assert_eq!(material, glowing)
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+    for broken in [
+        Material {
+            diffuse_color: Vec3f::new(0.5, f32::NAN, 0.5),
+            ..Material::default()
+        },
+        Material {
+            emission: Vec3f::new(f32::INFINITY, 0.0, 0.0),
+            ..Material::default()
+        },
+        Material {
+            refractive_index: 0.0,
+            ..Material::default()
+        },
+    ]
+    .iter()
+    {
+        let mut scene = demo_scene();
+        let id = scene.add_material(*broken);
+        assert!(matches!(
+            scene.validate(),
+            Err(RenderError::InvalidMaterial(material)) if material == id
+        ));
+    }
+
+    // Strong highlights are allowed, negative weights are not.
+    assert!(Material {
+        albedo: [0.5, 10.0, 0.5, 0.0],
+        ..Material::default()
+    }
+    .conserves_energy());
+    assert!(!Material {
+        albedo: [-0.1, 0.0, 0.5, 0.0],
+        ..Material::default()
+    }
+    .conserves_energy());
+}
+
+#[test]
+fn render_params_are_checked() {
+    let valid = RenderParams::default();
+    let check = |params: RenderParams| params.validate().err();
+    assert!(matches!(
+        check(RenderParams { width: 0, ..valid }),
+        Some(RenderError::EmptyImage {
+            width: 0,
+            height: 768
+        })
+    ));
+    assert!(matches!(
+        check(RenderParams { depth: 0, ..valid }),
+        Some(RenderError::ZeroDepth)
+    ));
+    for &fov in [0.0, PI, -1.0, f32::NAN].iter() {
+        assert!(matches!(
+            check(RenderParams {
+                field_of_view: fov,
+                ..valid
+            }),
+            Some(RenderError::FieldOfView(_))
+        ));
+    }
+    // Fisheye lenses may see more than a half turn, other projections ignore the angle.
+    let wide = RenderParams {
+        field_of_view: 1.5 * PI,
+        projection: Projection::Fisheye,
+        ..valid
+    };
+    assert!(check(wide).is_none());
+    assert!(check(RenderParams {
+        projection: Projection::Equirectangular,
+        ..wide
+    })
+    .is_none());
+
+    let mut lost = valid;
+    lost.pose.target = Vec3f::new(0.0, f32::NAN, -1.0);
+    assert!(matches!(check(lost), Some(RenderError::InvalidCamera)));
+    let mut blind = valid;
+    blind.pose.target = blind.pose.position;
+    assert!(matches!(check(blind), Some(RenderError::InvalidCamera)));
+
+    for &height in [0.0, -2.0, f32::NAN].iter() {
+        assert!(matches!(
+            check(RenderParams {
+                projection: Projection::Orthographic { height },
+                ..valid
+            }),
+            Some(RenderError::ViewHeight(_))
+        ));
+    }
+}
+
+#[test]
+fn material_ids_must_exist() {
+    let mut scene = demo_scene();
+    let missing = MaterialId(scene.materials.len());
+    let nested: Vec<Box<dyn Renderable>> = vec![Box::new(Sphere {
+        center: Vec3f::new(0.0, 0.0, -20.0),
+        radius: 1.0,
+        material: missing,
+    })];
+    scene.objects.push(Box::new(Bvh::new(nested)));
+    let index = scene.objects.len() - 1;
+    match scene.validate() {
+        Err(RenderError::UnknownMaterial { object, material }) => {
+            assert_eq!((object, material), (index, missing))
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn media_coefficients_are_checked() {
+    let fog = Fog {
+        density: FogDensity::Height {
+            base_height: -4.0,
+            falloff: 0.3,
+        },
+        absorption: 0.02,
+        scattering: 0.05,
+        asymmetry: 0.3,
+    };
+    let mut scene = demo_scene();
+    scene.media.fog = Some(fog);
+    assert!(scene.validate().is_ok());
+    for &broken in [
+        Fog {
+            scattering: -0.1,
+            ..fog
+        },
+        Fog {
+            absorption: f32::NAN,
+            ..fog
+        },
+        Fog {
+            asymmetry: 1.0,
+            ..fog
+        },
+        Fog {
+            density: FogDensity::Height {
+                base_height: 0.0,
+                falloff: f32::INFINITY,
+            },
+            ..fog
+        },
+    ]
+    .iter()
+    {
+        scene.media.fog = Some(broken);
+        assert!(matches!(scene.validate(), Err(RenderError::InvalidFog)));
+    }
+
+    let smoke = |scattering: f32, radius: f32| Volume {
+        boundary: sphere(Vec3f::new(0.0, 0.0, -10.0), radius),
+        absorption: 0.1,
+        scattering,
+        asymmetry: 0.0,
+    };
+    let mut scene = demo_scene();
+    scene.media.volumes = vec![smoke(0.5, 1.0), smoke(f32::NAN, 1.0)];
+    assert!(matches!(
+        scene.validate(),
+        Err(RenderError::InvalidVolume(1))
+    ));
+    scene.media.volumes = vec![smoke(0.5, -1.0)];
+    assert!(matches!(
+        scene.validate(),
+        Err(RenderError::InvalidVolume(0))
+    ));
+}
+
+#[test]
+fn animation_frame_rate_is_checked() {
+    let scene = demo_scene();
+    let still = Track::new(Interpolation::Linear, vec![(0.0, 1.0)]);
+    for &frame_rate in [0.0, -24.0, f32::NAN].iter() {
+        let animation = Animation {
+            frame_rate,
+            tracks: vec![Animated::FieldOfView(still.clone())],
+        };
+        assert!(matches!(
+            animation.validate(&scene),
+            Err(RenderError::FrameRate(_))
+        ));
+    }
+}